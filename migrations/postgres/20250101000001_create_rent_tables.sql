@@ -0,0 +1,50 @@
+-- Adapted from the SQLite migration of the same version. URLs are stored as JSONB, as
+-- encoded by `Json<Url>`, and timestamps as text in the format of SQLite's
+-- CURRENT_TIMESTAMP so that both backends compare and return them the same way. The JSON
+-- array columns are left out, they are normalized into child tables in version 4.
+
+CREATE TABLE rent_list (
+    id BIGSERIAL PRIMARY KEY,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    url JSONB NOT NULL,
+    page_count BIGINT,
+    item_count BIGINT,
+    UNIQUE (url, created_at)
+);
+
+CREATE TABLE rent_item_summary (
+    list_id BIGINT NOT NULL,
+    url JSONB NOT NULL,
+    title TEXT,
+    price TEXT,
+    PRIMARY KEY (list_id, url),
+    FOREIGN KEY (list_id) REFERENCES rent_list (id) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item (
+    url JSONB PRIMARY KEY,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    title TEXT,
+    content TEXT,
+    phone TEXT,
+    area JSONB,
+    floor JSONB,
+    price JSONB,
+    address JSONB
+);
+
+CREATE TABLE page_cache (
+    url JSONB PRIMARY KEY,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    html TEXT NOT NULL
+);
+
+CREATE INDEX idx_rent_list_url_latest ON rent_list (url, created_at DESC);
+
+CREATE INDEX idx_rent_item_summary_list ON rent_item_summary (list_id);
+
+CREATE INDEX idx_rent_item_summary_url ON rent_item_summary (url);
+
+CREATE INDEX idx_rent_item_created_at ON rent_item (created_at);
+
+CREATE INDEX idx_page_cache_created_at ON page_cache (created_at);
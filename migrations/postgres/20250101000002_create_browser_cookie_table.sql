@@ -0,0 +1,14 @@
+CREATE TABLE browser_cookie (
+    name TEXT NOT NULL,
+    domain TEXT NOT NULL,
+    path TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    value TEXT NOT NULL,
+    expires DOUBLE PRECISION,
+    http_only BOOLEAN NOT NULL,
+    secure BOOLEAN NOT NULL,
+    same_site TEXT,
+    PRIMARY KEY (name, domain, path)
+);
+
+CREATE INDEX idx_browser_cookie_expires ON browser_cookie (expires);
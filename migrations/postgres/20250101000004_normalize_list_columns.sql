@@ -0,0 +1,101 @@
+-- Adapted from the SQLite migration of the same version. There is nothing to move as the
+-- JSON array columns were never created, and the full-text index of version 3 is
+-- SQLite-only, so only the child tables and the views folding them back are created.
+
+CREATE TABLE rent_item_label (
+    item_url JSONB NOT NULL,
+    position BIGINT NOT NULL,
+    label TEXT NOT NULL,
+    PRIMARY KEY (item_url, position),
+    FOREIGN KEY (item_url) REFERENCES rent_item (url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_pattern (
+    item_url JSONB NOT NULL,
+    position BIGINT NOT NULL,
+    pattern TEXT NOT NULL,
+    PRIMARY KEY (item_url, position),
+    FOREIGN KEY (item_url) REFERENCES rent_item (url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_image (
+    item_url JSONB NOT NULL,
+    position BIGINT NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (item_url, position),
+    FOREIGN KEY (item_url) REFERENCES rent_item (url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_summary_tag (
+    list_id BIGINT NOT NULL,
+    item_url JSONB NOT NULL,
+    position BIGINT NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (list_id, item_url, position),
+    FOREIGN KEY (list_id, item_url) REFERENCES rent_item_summary (list_id, url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_summary_txt (
+    list_id BIGINT NOT NULL,
+    item_url JSONB NOT NULL,
+    position BIGINT NOT NULL,
+    txt TEXT NOT NULL,
+    PRIMARY KEY (list_id, item_url, position),
+    FOREIGN KEY (list_id, item_url) REFERENCES rent_item_summary (list_id, url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_summary_image (
+    list_id BIGINT NOT NULL,
+    item_url JSONB NOT NULL,
+    position BIGINT NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (list_id, item_url, position),
+    FOREIGN KEY (list_id, item_url) REFERENCES rent_item_summary (list_id, url) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_rent_item_label_label ON rent_item_label (label);
+
+CREATE INDEX idx_rent_item_pattern_pattern ON rent_item_pattern (pattern);
+
+CREATE INDEX idx_rent_item_summary_tag_tag ON rent_item_summary_tag (tag);
+
+CREATE INDEX idx_rent_item_summary_tag_item ON rent_item_summary_tag (item_url);
+
+-- Views with the child tables folded back into JSON arrays, in the shape of RentItem and
+-- RentItemSummary
+
+CREATE VIEW rent_item_view AS
+SELECT
+    ri.url,
+    ri.created_at,
+    ri.title,
+    COALESCE((SELECT jsonb_agg(label ORDER BY position) FROM rent_item_label WHERE item_url = ri.url), '[]') AS labels,
+    COALESCE((SELECT jsonb_agg(pattern ORDER BY position) FROM rent_item_pattern WHERE item_url = ri.url), '[]') AS patterns,
+    ri.content,
+    ri.phone,
+    COALESCE((SELECT jsonb_agg(url ORDER BY position) FROM rent_item_image WHERE item_url = ri.url), '[]') AS album,
+    ri.area,
+    ri.floor,
+    ri.price,
+    ri.address
+FROM rent_item ri;
+
+CREATE VIEW rent_item_summary_view AS
+SELECT
+    ris.list_id,
+    ris.url,
+    ris.title,
+    ris.price,
+    COALESCE((
+        SELECT jsonb_agg(tag ORDER BY position) FROM rent_item_summary_tag
+        WHERE list_id = ris.list_id AND item_url = ris.url
+    ), '[]') AS tags,
+    COALESCE((
+        SELECT jsonb_agg(txt ORDER BY position) FROM rent_item_summary_txt
+        WHERE list_id = ris.list_id AND item_url = ris.url
+    ), '[]') AS txts,
+    COALESCE((
+        SELECT jsonb_agg(url ORDER BY position) FROM rent_item_summary_image
+        WHERE list_id = ris.list_id AND item_url = ris.url
+    ), '[]') AS images
+FROM rent_item_summary ris;
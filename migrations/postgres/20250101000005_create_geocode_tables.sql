@@ -0,0 +1,26 @@
+-- Geocoding results keyed by normalized address, so an address is only ever queried once.
+-- Addresses without a result are kept with NULL coordinates.
+
+CREATE TABLE geocode (
+    address TEXT PRIMARY KEY,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    lat DOUBLE PRECISION,
+    lng DOUBLE PRECISION,
+    formatted_address TEXT,
+    location_type TEXT,
+    place_id TEXT
+);
+
+-- The address read for each listing, from its OCR'd address image or from the location
+-- shown on the list page. Listings without scraped details have one too, so there is no
+-- foreign key to rent_item.
+
+CREATE TABLE rent_item_address (
+    item_url JSONB PRIMARY KEY,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    address TEXT NOT NULL,
+    normalized TEXT NOT NULL,
+    source TEXT NOT NULL
+);
+
+CREATE INDEX idx_rent_item_address_normalized ON rent_item_address (normalized);
@@ -0,0 +1,11 @@
+-- Great-circle distance from each geocoded listing to each point of interest of
+-- `rentmap.toml`, keyed by the POI name. Like rent_item_address, listings without scraped
+-- details have them too, so there is no foreign key to rent_item.
+
+CREATE TABLE rent_item_distance (
+    item_url JSONB NOT NULL,
+    poi TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    distance_km DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (item_url, poi)
+);
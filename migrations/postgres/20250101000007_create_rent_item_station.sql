@@ -0,0 +1,14 @@
+-- The nearest metro or light rail station of each line within walking distance of each
+-- geocoded listing, with the straight-line and estimated walking distances in meters.
+
+CREATE TABLE rent_item_station (
+    item_url JSONB NOT NULL,
+    line TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    code TEXT NOT NULL,
+    name TEXT NOT NULL,
+    distance_m INTEGER NOT NULL,
+    walk_m INTEGER NOT NULL,
+    walk_minutes INTEGER NOT NULL,
+    PRIMARY KEY (item_url, line)
+);
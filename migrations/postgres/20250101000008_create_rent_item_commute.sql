@@ -0,0 +1,10 @@
+-- Public transit commute minutes from each geocoded listing to each destination point of
+-- interest, searched in a GTFS timetable.
+
+CREATE TABLE rent_item_commute (
+    item_url JSONB NOT NULL,
+    poi TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS'),
+    minutes INTEGER NOT NULL,
+    PRIMARY KEY (item_url, poi)
+);
@@ -0,0 +1,6 @@
+-- Listings are searched by area with a bounding box query over the geocoded coordinates
+-- before exact radius and polygon tests. A GiST index over each located geocode as a
+-- point(lng, lat) answers containment in a box.
+
+CREATE INDEX idx_geocode_location ON geocode USING gist (point(lng, lat))
+WHERE lat IS NOT NULL AND lng IS NOT NULL;
@@ -0,0 +1,7 @@
+-- The geocoding service each result is from, so that results of one provider, misses
+-- included, are not taken as cached for another. Results cached before providers were
+-- recorded are from Google, the only provider at the time.
+
+ALTER TABLE geocode ADD COLUMN provider TEXT;
+
+UPDATE geocode SET provider = 'google';
@@ -0,0 +1,10 @@
+-- Trigram indexes over the searched columns of rent_item, the counterpart of the SQLite
+-- FTS5 table: ILIKE with a pattern of three or more characters is answered from the index,
+-- which also covers CJK text without word boundaries
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+CREATE INDEX idx_rent_item_title_trgm ON rent_item USING gin (title gin_trgm_ops);
+
+CREATE INDEX idx_rent_item_content_trgm ON rent_item USING gin (content gin_trgm_ops);
+
+CREATE INDEX idx_rent_item_label_label_trgm ON rent_item_label USING gin (label gin_trgm_ops);
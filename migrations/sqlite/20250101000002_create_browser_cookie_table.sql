@@ -0,0 +1,14 @@
+CREATE TABLE browser_cookie (
+    name TEXT NOT NULL,
+    domain TEXT NOT NULL,
+    path TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    value TEXT NOT NULL,
+    expires REAL,
+    http_only INTEGER NOT NULL,
+    secure INTEGER NOT NULL,
+    same_site TEXT,
+    PRIMARY KEY (name, domain, path)
+);
+
+CREATE INDEX idx_browser_cookie_expires ON browser_cookie (expires);
\ No newline at end of file
@@ -0,0 +1,29 @@
+-- Full-text index over rent_item, using the trigram tokenizer so that CJK text without
+-- word boundaries can be matched by any substring of three or more characters
+CREATE VIRTUAL TABLE rent_item_fts USING fts5 (
+    title,
+    labels,
+    content,
+    content = 'rent_item',
+    content_rowid = 'rowid',
+    tokenize = 'trigram'
+);
+
+INSERT INTO rent_item_fts (rent_item_fts) VALUES ('rebuild');
+
+CREATE TRIGGER rent_item_fts_insert AFTER INSERT ON rent_item BEGIN
+    INSERT INTO rent_item_fts (rowid, title, labels, content)
+    VALUES (new.rowid, new.title, new.labels, new.content);
+END;
+
+CREATE TRIGGER rent_item_fts_delete AFTER DELETE ON rent_item BEGIN
+    INSERT INTO rent_item_fts (rent_item_fts, rowid, title, labels, content)
+    VALUES ('delete', old.rowid, old.title, old.labels, old.content);
+END;
+
+CREATE TRIGGER rent_item_fts_update AFTER UPDATE ON rent_item BEGIN
+    INSERT INTO rent_item_fts (rent_item_fts, rowid, title, labels, content)
+    VALUES ('delete', old.rowid, old.title, old.labels, old.content);
+    INSERT INTO rent_item_fts (rowid, title, labels, content)
+    VALUES (new.rowid, new.title, new.labels, new.content);
+END;
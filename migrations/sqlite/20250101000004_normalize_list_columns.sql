@@ -0,0 +1,188 @@
+-- Move the JSON array columns of rent_item and rent_item_summary into child tables, one
+-- row per value with its position in the array
+
+CREATE TABLE rent_item_label (
+    item_url TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    PRIMARY KEY (item_url, position),
+    FOREIGN KEY (item_url) REFERENCES rent_item (url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_pattern (
+    item_url TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    pattern TEXT NOT NULL,
+    PRIMARY KEY (item_url, position),
+    FOREIGN KEY (item_url) REFERENCES rent_item (url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_image (
+    item_url TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (item_url, position),
+    FOREIGN KEY (item_url) REFERENCES rent_item (url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_summary_tag (
+    list_id INTEGER NOT NULL,
+    item_url TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (list_id, item_url, position),
+    FOREIGN KEY (list_id, item_url) REFERENCES rent_item_summary (list_id, url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_summary_txt (
+    list_id INTEGER NOT NULL,
+    item_url TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    txt TEXT NOT NULL,
+    PRIMARY KEY (list_id, item_url, position),
+    FOREIGN KEY (list_id, item_url) REFERENCES rent_item_summary (list_id, url) ON DELETE CASCADE
+);
+
+CREATE TABLE rent_item_summary_image (
+    list_id INTEGER NOT NULL,
+    item_url TEXT NOT NULL,
+    position INTEGER NOT NULL,
+    url TEXT NOT NULL,
+    PRIMARY KEY (list_id, item_url, position),
+    FOREIGN KEY (list_id, item_url) REFERENCES rent_item_summary (list_id, url) ON DELETE CASCADE
+);
+
+INSERT INTO rent_item_label (item_url, position, label)
+SELECT ri.url, je.key, je.value FROM rent_item ri, json_each(ri.labels) je;
+
+INSERT INTO rent_item_pattern (item_url, position, pattern)
+SELECT ri.url, je.key, je.value FROM rent_item ri, json_each(ri.patterns) je;
+
+INSERT INTO rent_item_image (item_url, position, url)
+SELECT ri.url, je.key, je.value FROM rent_item ri, json_each(ri.album) je;
+
+INSERT INTO rent_item_summary_tag (list_id, item_url, position, tag)
+SELECT ris.list_id, ris.url, je.key, je.value FROM rent_item_summary ris, json_each(ris.tags) je;
+
+INSERT INTO rent_item_summary_txt (list_id, item_url, position, txt)
+SELECT ris.list_id, ris.url, je.key, je.value FROM rent_item_summary ris, json_each(ris.txts) je;
+
+INSERT INTO rent_item_summary_image (list_id, item_url, position, url)
+SELECT ris.list_id, ris.url, je.key, je.value FROM rent_item_summary ris, json_each(ris.images) je;
+
+CREATE INDEX idx_rent_item_label_label ON rent_item_label (label);
+
+CREATE INDEX idx_rent_item_pattern_pattern ON rent_item_pattern (pattern);
+
+CREATE INDEX idx_rent_item_summary_tag_tag ON rent_item_summary_tag (tag);
+
+CREATE INDEX idx_rent_item_summary_tag_item ON rent_item_summary_tag (item_url);
+
+-- The external content index read labels from rent_item, replace it with one that stores
+-- its own copy and gets labels from rent_item_label
+
+DROP TRIGGER rent_item_fts_insert;
+
+DROP TRIGGER rent_item_fts_delete;
+
+DROP TRIGGER rent_item_fts_update;
+
+DROP TABLE rent_item_fts;
+
+ALTER TABLE rent_item DROP COLUMN labels;
+
+ALTER TABLE rent_item DROP COLUMN patterns;
+
+ALTER TABLE rent_item DROP COLUMN album;
+
+ALTER TABLE rent_item_summary DROP COLUMN tags;
+
+ALTER TABLE rent_item_summary DROP COLUMN txts;
+
+ALTER TABLE rent_item_summary DROP COLUMN images;
+
+CREATE VIRTUAL TABLE rent_item_fts USING fts5 (
+    title,
+    labels,
+    content,
+    tokenize = 'trigram'
+);
+
+INSERT INTO rent_item_fts (rowid, title, labels, content)
+SELECT
+    ri.rowid,
+    ri.title,
+    (SELECT group_concat(label, ' ' ORDER BY position) FROM rent_item_label WHERE item_url = ri.url),
+    ri.content
+FROM rent_item ri;
+
+CREATE TRIGGER rent_item_fts_insert AFTER INSERT ON rent_item BEGIN
+    INSERT INTO rent_item_fts (rowid, title, labels, content)
+    VALUES (new.rowid, new.title, NULL, new.content);
+END;
+
+CREATE TRIGGER rent_item_fts_delete AFTER DELETE ON rent_item BEGIN
+    DELETE FROM rent_item_fts WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER rent_item_fts_update AFTER UPDATE ON rent_item BEGIN
+    UPDATE rent_item_fts SET title = new.title, content = new.content WHERE rowid = old.rowid;
+END;
+
+CREATE TRIGGER rent_item_label_fts_insert AFTER INSERT ON rent_item_label BEGIN
+    UPDATE rent_item_fts
+    SET labels = (
+        SELECT group_concat(label, ' ' ORDER BY position)
+        FROM rent_item_label WHERE item_url = new.item_url
+    )
+    WHERE rowid = (SELECT rowid FROM rent_item WHERE url = new.item_url);
+END;
+
+CREATE TRIGGER rent_item_label_fts_delete AFTER DELETE ON rent_item_label BEGIN
+    UPDATE rent_item_fts
+    SET labels = (
+        SELECT group_concat(label, ' ' ORDER BY position)
+        FROM rent_item_label WHERE item_url = old.item_url
+    )
+    WHERE rowid = (SELECT rowid FROM rent_item WHERE url = old.item_url);
+END;
+
+-- Views with the child tables folded back into JSON arrays, in the shape of RentItem and
+-- RentItemSummary
+
+CREATE VIEW rent_item_view AS
+SELECT
+    ri.rowid AS id,
+    ri.url,
+    ri.created_at,
+    ri.title,
+    (SELECT json_group_array(label ORDER BY position) FROM rent_item_label WHERE item_url = ri.url) AS labels,
+    (SELECT json_group_array(pattern ORDER BY position) FROM rent_item_pattern WHERE item_url = ri.url) AS patterns,
+    ri.content,
+    ri.phone,
+    (SELECT json_group_array(url ORDER BY position) FROM rent_item_image WHERE item_url = ri.url) AS album,
+    ri.area,
+    ri.floor,
+    ri.price,
+    ri.address
+FROM rent_item ri;
+
+CREATE VIEW rent_item_summary_view AS
+SELECT
+    ris.list_id,
+    ris.url,
+    ris.title,
+    ris.price,
+    (
+        SELECT json_group_array(tag ORDER BY position) FROM rent_item_summary_tag
+        WHERE list_id = ris.list_id AND item_url = ris.url
+    ) AS tags,
+    (
+        SELECT json_group_array(txt ORDER BY position) FROM rent_item_summary_txt
+        WHERE list_id = ris.list_id AND item_url = ris.url
+    ) AS txts,
+    (
+        SELECT json_group_array(url ORDER BY position) FROM rent_item_summary_image
+        WHERE list_id = ris.list_id AND item_url = ris.url
+    ) AS images
+FROM rent_item_summary ris;
@@ -0,0 +1,10 @@
+-- Public transit commute minutes from each geocoded listing to each destination point of
+-- interest, searched in a GTFS timetable.
+
+CREATE TABLE rent_item_commute (
+    item_url TEXT NOT NULL,
+    poi TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    minutes INTEGER NOT NULL,
+    PRIMARY KEY (item_url, poi)
+);
@@ -0,0 +1,52 @@
+-- Listings are searched by area with a bounding box query over the geocoded coordinates
+-- before exact radius and polygon tests. An R*Tree holds each located geocode as a point
+-- box, kept in sync with geocode by triggers.
+--
+-- The R*Tree is keyed by geocode id, so geocode is rebuilt with an INTEGER PRIMARY KEY
+-- that VACUUM keeps, unlike the implicit rowid.
+
+CREATE TABLE geocode_new (
+    id INTEGER PRIMARY KEY,
+    address TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    lat REAL,
+    lng REAL,
+    formatted_address TEXT,
+    location_type TEXT,
+    place_id TEXT
+);
+
+INSERT INTO geocode_new (address, created_at, lat, lng, formatted_address, location_type, place_id)
+SELECT address, created_at, lat, lng, formatted_address, location_type, place_id FROM geocode;
+
+DROP TABLE geocode;
+ALTER TABLE geocode_new RENAME TO geocode;
+
+CREATE VIRTUAL TABLE geocode_location USING rtree (
+    id,
+    min_lat, max_lat,
+    min_lng, max_lng
+);
+
+INSERT INTO geocode_location (id, min_lat, max_lat, min_lng, max_lng)
+SELECT id, lat, lat, lng, lng FROM geocode WHERE lat IS NOT NULL AND lng IS NOT NULL;
+
+CREATE TRIGGER geocode_location_insert AFTER INSERT ON geocode
+WHEN new.lat IS NOT NULL AND new.lng IS NOT NULL
+BEGIN
+    INSERT INTO geocode_location (id, min_lat, max_lat, min_lng, max_lng)
+    VALUES (new.id, new.lat, new.lat, new.lng, new.lng);
+END;
+
+CREATE TRIGGER geocode_location_update AFTER UPDATE OF lat, lng ON geocode
+BEGIN
+    DELETE FROM geocode_location WHERE id = old.id;
+    INSERT INTO geocode_location (id, min_lat, max_lat, min_lng, max_lng)
+    SELECT new.id, new.lat, new.lat, new.lng, new.lng
+    WHERE new.lat IS NOT NULL AND new.lng IS NOT NULL;
+END;
+
+CREATE TRIGGER geocode_location_delete AFTER DELETE ON geocode
+BEGIN
+    DELETE FROM geocode_location WHERE id = old.id;
+END;
@@ -0,0 +1,144 @@
+//! Commute command implementation
+
+use std::path::PathBuf;
+
+use chrono::{Local, NaiveDate, NaiveTime, Timelike};
+use clap::Parser;
+use colored::Colorize;
+use miette::Result;
+use tracing::{debug, info};
+use url::Url;
+
+use super::error::Error;
+use super::located::{Located, PoiArgs, format_poi_results, no_location_note};
+use crate::transit::{Commutes, Timetable};
+use crate::url::UrlExt;
+use crate::workspace::WorkspaceArgs;
+
+/// Compute public transit commute times from every geocoded listing to points of interest
+///
+/// Trips running on `--date` are loaded from a GTFS feed, and the earliest arrival at each
+/// destination leaving a listing at `--time` is searched with RAPTOR, walking to and from
+/// stops within `--max-walk` and between stops within `--max-transfer-walk`. Minutes are
+/// saved per destination and used by `rentmap query --max-commute` and `--sort commute` and
+/// the `commutes` export column.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Target URL for rent.591.com.tw search results
+    pub url: Url,
+
+    /// GTFS feed zip (e.g., from the TDX transport data platform)
+    #[arg(long)]
+    pub gtfs: PathBuf,
+
+    /// Point of interest to commute to by name, repeat for several [default: all]
+    #[arg(long = "to", value_name = "NAME")]
+    pub to: Vec<String>,
+
+    /// Day of travel (YYYY-MM-DD) [default: today]
+    #[arg(long)]
+    pub date: Option<NaiveDate>,
+
+    /// Departure time (HH:MM)
+    #[arg(long, default_value = "08:30")]
+    pub time: NaiveTime,
+
+    /// Maximum walking meters between a listing or destination and a stop
+    #[arg(long, default_value_t = 1000)]
+    pub max_walk: u32,
+
+    /// Maximum walking meters to transfer between stops
+    #[arg(long, default_value_t = 300)]
+    pub max_transfer_walk: u32,
+
+    /// Maximum number of trips in a journey
+    #[arg(long, default_value_t = 4)]
+    pub max_trips: usize,
+
+    #[clap(flatten)]
+    pub poi: PoiArgs,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+pub async fn run(mut args: Args) -> Result<()> {
+    args.url.normalize();
+
+    let mut pois = args.poi.load_pois();
+    debug!(?args, ?pois);
+
+    miette::ensure!(!pois.is_empty(), Error::NoPoi);
+
+    if !args.to.is_empty() {
+        if let Some(name) = args
+            .to
+            .iter()
+            .find(|name| !pois.iter().any(|poi| &poi.name == *name))
+        {
+            return Err(Error::UnknownPoi(name.clone()).into());
+        }
+
+        pois.retain(|poi| args.to.contains(&poi.name));
+    }
+
+    let workspace = args.workspace.build().await?;
+
+    miette::ensure!(workspace.list_exists(&args.url).await?, Error::NoRentList);
+
+    let pois = args.poi.resolve_pois(&workspace, pois).await?;
+
+    let date = args.date.unwrap_or_else(|| Local::now().date_naive());
+    let timetable = Timetable::load(&args.gtfs, date, args.max_transfer_walk)?;
+    let departure = args.time.num_seconds_from_midnight();
+
+    info!(stops = timetable.stops().len(), %date, "load GTFS feed");
+
+    let located = Located::select(&workspace, &args.url).await?;
+
+    let mut results = Vec::with_capacity(located.listings.len());
+
+    for (url, location) in located.listings {
+        let arrivals =
+            timetable.earliest_arrivals(location, departure, args.max_walk, args.max_trips);
+
+        let commutes: Commutes = pois
+            .iter()
+            .map(|poi| {
+                let seconds = arrivals.at((poi.lat, poi.lng)) - departure;
+                (poi.name.clone(), seconds.div_ceil(60))
+            })
+            .collect();
+
+        workspace.save_item_commutes(&url, &commutes).await?;
+
+        results.push((url, commutes));
+    }
+
+    info!(
+        routed = results.len(),
+        no_location = located.no_location,
+        "route commutes"
+    );
+
+    let summary = format!(
+        "Routed {} listings to {} destinations",
+        results.len(),
+        pois.len()
+    )
+    .bright_green()
+    .to_string();
+
+    println!(
+        "\n{}",
+        format_poi_results(
+            "Commutes:",
+            &pois,
+            &results,
+            |minutes| format!("{minutes} min"),
+            no_location_note(summary, located.no_location),
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,91 @@
+//! Distances command implementation
+
+use clap::Parser;
+use colored::Colorize;
+use miette::Result;
+use tracing::{debug, info};
+use url::Url;
+
+use super::error::Error;
+use super::located::{Located, PoiArgs, format_poi_results, no_location_note};
+use crate::geocode::Distances;
+use crate::url::UrlExt;
+use crate::workspace::WorkspaceArgs;
+
+/// Compute the distance from every geocoded listing to each point of interest
+///
+/// Points of interest are the `[[poi]]` tables of `rentmap.toml`, each with a `name` and
+/// either `lat` and `lng` or an `address` to geocode. Distances are great-circle
+/// kilometers from the locations found by `rentmap geocode-items`, and are used by
+/// `rentmap query --max-distance` and `--sort distance` and the `distances` export column.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Target URL for rent.591.com.tw search results
+    pub url: Url,
+
+    #[clap(flatten)]
+    pub poi: PoiArgs,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+pub async fn run(mut args: Args) -> Result<()> {
+    args.url.normalize();
+
+    let pois = args.poi.load_pois();
+    debug!(?args, ?pois);
+
+    miette::ensure!(!pois.is_empty(), Error::NoPoi);
+
+    let workspace = args.workspace.build().await?;
+
+    miette::ensure!(workspace.list_exists(&args.url).await?, Error::NoRentList);
+
+    let pois = args.poi.resolve_pois(&workspace, pois).await?;
+    let located = Located::select(&workspace, &args.url).await?;
+
+    let mut results = Vec::with_capacity(located.listings.len());
+
+    for (url, location) in located.listings {
+        let distances: Distances = pois
+            .iter()
+            // to the meter, anything finer is noise in exports
+            .map(|poi| {
+                let km = poi.distance_km(location);
+                (poi.name.clone(), (km * 1000.0).round() / 1000.0)
+            })
+            .collect();
+
+        workspace.save_item_distances(&url, &distances).await?;
+
+        results.push((url, distances));
+    }
+
+    info!(
+        measured = results.len(),
+        no_location = located.no_location,
+        "measure distances"
+    );
+
+    let summary = format!(
+        "Measured {} listings to {} points of interest",
+        results.len(),
+        pois.len()
+    )
+    .bright_green()
+    .to_string();
+
+    println!(
+        "\n{}",
+        format_poi_results(
+            "Distances:",
+            &pois,
+            &results,
+            |km| format!("{km:.2} km"),
+            no_location_note(summary, located.no_location),
+        )
+    );
+
+    Ok(())
+}
@@ -9,13 +9,121 @@ pub enum Error {
         help("run `rentmap list` to fetch rental list")
     )]
     NoRentList,
+
+    #[error("invalid URL {text:?} on line {line}")]
+    #[diagnostic(
+        code(rentmap::fetch::invalid_url),
+        help("put one absolute URL per line, blank lines and lines starting with `#` are ignored")
+    )]
+    InvalidUrl {
+        line: usize,
+        text: String,
+        #[source]
+        source: url::ParseError,
+    },
+
+    #[error("failed to fetch {failed} of {total} pages")]
+    #[diagnostic(
+        code(rentmap::fetch::failed),
+        help("see the errors logged above for the cause of each failure")
+    )]
+    FetchFailed { failed: usize, total: usize },
+
+    #[error("invalid record on line {line}")]
+    #[diagnostic(
+        code(rentmap::import::invalid_record),
+        help(
+            "each line must be a rental list or rental item as written by `RentList` or `RentItem`"
+        )
+    )]
+    InvalidRecord {
+        line: usize,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    #[error("search query is empty")]
+    #[diagnostic(
+        code(rentmap::search::empty_query),
+        help("give one or more search terms separated by spaces, e.g. \"可養寵物 近捷運\"")
+    )]
+    EmptySearch,
+
+    #[error("no workspace database at `{}`", .0.display())]
+    #[diagnostic(
+        code(rentmap::sql::no_workspace),
+        help("run `rentmap list` first, or pass the workspace directory with `--workspace`")
+    )]
+    NoWorkspace(std::path::PathBuf),
+
+    #[error("SQL query failed")]
+    #[diagnostic(
+        code(rentmap::sql::query),
+        help(
+            "the workspace is opened read-only, only SELECT and other read statements are allowed"
+        )
+    )]
+    Sql(#[source] sqlx::Error),
+
+    #[error("no column {column:?} in the input header")]
+    #[diagnostic(
+        code(rentmap::geocoding::missing_column),
+        help("choose one of the input columns with `--column`: {}", headers.join(", "))
+    )]
+    MissingColumn {
+        column: String,
+        headers: Vec<String>,
+    },
+
+    #[error("invalid CSV input")]
+    #[diagnostic(
+        code(rentmap::geocoding::invalid_csv),
+        help("the input must be comma-separated values with a header row")
+    )]
+    InvalidCsv(#[source] csv::Error),
+
+    #[error("no points of interest configured")]
+    #[diagnostic(
+        code(rentmap::distances::no_poi),
+        help("add `[[poi]]` tables with a `name` and `lat`/`lng` or `address` to rentmap.toml")
+    )]
+    NoPoi,
+
+    #[error("point of interest {0:?} has no location")]
+    #[diagnostic(
+        code(rentmap::distances::invalid_poi),
+        help("give both `lat` and `lng`, or an `address` to geocode")
+    )]
+    InvalidPoi(String),
+
+    #[error("no point of interest named {0:?}")]
+    #[diagnostic(
+        code(rentmap::commute::unknown_poi),
+        help("`--to` takes the `name` of a `[[poi]]` table in rentmap.toml")
+    )]
+    UnknownPoi(String),
+
+    #[error("invalid station file `{}`", path.display())]
+    #[diagnostic(
+        code(rentmap::stations::invalid_stations),
+        help("the file must be CSV with a `code,line,name,name_en,lat,lng` header row")
+    )]
+    InvalidStations {
+        path: std::path::PathBuf,
+        #[source]
+        source: csv::Error,
+    },
+
+    #[error("failed to read from stdin")]
+    #[diagnostic(code(rentmap::stdin))]
+    Stdin(#[source] std::io::Error),
 }
 
 #[derive(Debug, Error, Diagnostic)]
 pub enum ServerError {
     #[error("failed to bind to address {addr}")]
     #[diagnostic(
-        code(rentmap::fetch::bind_error),
+        code(rentmap::preview::bind_error),
         help(
             "port {} may already be in use. Try using a different port or stop other services using this port.", addr.port()
         )
@@ -28,7 +136,7 @@ pub enum ServerError {
 
     #[error("server failed during operation")]
     #[diagnostic(
-        code(rentmap::fetch::serve_error),
+        code(rentmap::preview::serve_error),
         help(
             "the HTTP server encountered an error while serving requests. This could be due to network issues or resource constraints."
         )
@@ -38,3 +146,21 @@ pub enum ServerError {
         source: std::io::Error,
     },
 }
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum OutputError {
+    #[error("failed to write output")]
+    #[diagnostic(
+        code(rentmap::output::io),
+        help("check the output path is writable and the disk is not full")
+    )]
+    Io(#[from] std::io::Error),
+
+    #[error("failed to write JSON output")]
+    #[diagnostic(code(rentmap::output::json))]
+    Json(#[from] serde_json::Error),
+
+    #[error("failed to write CSV output")]
+    #[diagnostic(code(rentmap::output::csv))]
+    Csv(#[from] csv::Error),
+}
@@ -0,0 +1,144 @@
+//! Export command implementation
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+use clap::{Parser, ValueEnum};
+use miette::Result;
+use tracing::{debug, info};
+use url::Url;
+
+use super::error::{Error, OutputError};
+use crate::file::PathError;
+use crate::sites::rent591::{Column, ExportRecord, RentRecord};
+use crate::url::UrlExt;
+use crate::workspace::WorkspaceArgs;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum ExportFormat {
+    /// A single JSON array of objects
+    #[default]
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// Comma-separated values with a header row, lists joined by `; `
+    Csv,
+}
+
+/// Export the latest list snapshot with item details as JSON, NDJSON or CSV
+///
+/// With `--records`, the snapshot and its scraped items are written as the records
+/// `rentmap import` reads instead, so they can be imported into another workspace.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Target URL for rent.591.com.tw search results
+    pub url: Url,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = Default::default())]
+    pub format: ExportFormat,
+
+    /// Columns to export, in order [default: all]
+    #[arg(long, short, value_enum, value_delimiter = ',')]
+    pub columns: Vec<Column>,
+
+    /// Write the list snapshot and its scraped items as import records, one per line
+    #[arg(long, conflicts_with_all = ["format", "columns"])]
+    pub records: bool,
+
+    /// Write to this file instead of stdout
+    #[arg(long, short)]
+    pub out_file: Option<PathBuf>,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+/// Write records in the given format, returning any I/O or encoding error
+pub fn write_records<W>(
+    mut writer: W,
+    records: &[ExportRecord],
+    columns: &[Column],
+    format: ExportFormat,
+) -> Result<(), OutputError>
+where
+    W: Write,
+{
+    match format {
+        ExportFormat::Json => {
+            let values: Vec<_> = records.iter().map(|r| r.to_json(columns)).collect();
+            serde_json::to_writer_pretty(&mut writer, &values)?;
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+        ExportFormat::Ndjson => {
+            for record in records {
+                serde_json::to_writer(&mut writer, &record.to_json(columns))?;
+                writeln!(writer)?;
+            }
+            writer.flush()?;
+        }
+        ExportFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(writer);
+            writer.write_record(columns.iter().map(|column| column.name()))?;
+            for record in records {
+                writer.write_record(record.to_csv_row(columns))?;
+            }
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Write records as `rentmap import` reads them, one JSON object per line
+pub fn write_rent_records<W>(mut writer: W, records: &[RentRecord]) -> Result<(), OutputError>
+where
+    W: Write,
+{
+    for record in records {
+        serde_json::to_writer(&mut writer, record)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub async fn run(mut args: Args) -> Result<()> {
+    args.url.normalize();
+
+    debug!(?args);
+
+    let workspace = args.workspace.build_read_only().await?;
+
+    miette::ensure!(workspace.list_exists(&args.url).await?, Error::NoRentList);
+
+    let writer: Box<dyn Write> = match &args.out_file {
+        Some(path) => {
+            let file = File::create(path).map_err(|source| PathError::new(path, source))?;
+            Box::new(BufWriter::new(file))
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+
+    let count = if args.records {
+        let records = workspace.select_rent_records(&args.url).await?;
+        write_rent_records(writer, &records)?;
+        records.len()
+    } else {
+        let records = workspace.select_export_records(&args.url).await?;
+        let columns = if args.columns.is_empty() {
+            Column::value_variants().to_vec()
+        } else {
+            args.columns
+        };
+        write_records(writer, &records, &columns, args.format)?;
+        records.len()
+    };
+
+    info!(path = ?args.out_file, count, "export records");
+
+    Ok(())
+}
@@ -1,30 +1,46 @@
 //! Fetch command implementation
 
-use std::net::SocketAddr;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use axum::Router;
 use axum::response::Html;
 use axum::routing::get;
 use clap::Parser;
+use futures::StreamExt;
 use miette::Result;
-use tokio::net::TcpListener;
-use tokio::signal::ctrl_c;
-use tracing::{debug, info};
+use tracing::{debug, error, info, warn};
 use url::Url;
 
-use super::error::ServerError;
-use crate::web::FetcherArgs;
+use super::error::Error;
+use super::preview::{PREVIEW_PORT, serve};
+use crate::config::model::{Config, load_config};
+use crate::error::TraceReport;
+use crate::file::{load_text, make_directory, save_html};
+use crate::url::UrlExt;
+use crate::web::{Fetcher, FetcherArgs};
 use crate::workspace::WorkspaceArgs;
 
-const PREVIEW_PORT: u16 = 3000;
-
 /// Download and clean HTML pages
 #[derive(Debug, Parser)]
 pub struct Args {
-    /// Target URL to fetch and process
-    pub url: Url,
+    /// Target URLs to fetch and process
+    #[arg(required_unless_present = "input")]
+    pub urls: Vec<Url>,
+
+    /// Read URLs from a file, one per line, or `-` for stdin
+    #[arg(short, long)]
+    pub input: Option<PathBuf>,
 
-    /// Preview the HTML page in a web browser
+    /// Save pages into this directory, mirroring each URL as a host/path tree
+    #[arg(short, long)]
+    pub out_dir: Option<PathBuf>,
+
+    /// Maximum pages to fetch at the same time
+    #[arg(long, default_value_t = 10)]
+    pub concurrency: usize,
+
+    /// Preview the first HTML page in a web browser
     #[arg(short, long)]
     pub preview: bool,
 
@@ -35,49 +51,145 @@ pub struct Args {
     pub fetcher: FetcherArgs,
 }
 
-async fn start_preview_server<T>(html_content: String, addr: T) -> Result<()>
-where
-    T: Into<SocketAddr>,
-{
-    let addr: SocketAddr = addr.into();
-
-    info!(addr = %format!("http://{addr}"), "start preview server");
+fn merge_args(mut args: Args, config: Config) -> Args {
+    if let Some(fetcher) = config.fetcher {
+        args.fetcher = args.fetcher.merge(fetcher);
+    }
 
-    let listener = TcpListener::bind(addr)
-        .await
-        .map_err(|source| ServerError::Bind { source, addr })?;
+    args
+}
 
-    let app = Router::new().route("/", get(async || Html(html_content)));
+/// Parse URLs from text with one URL per line, skipping blank lines and `#` comments
+fn parse_urls(text: &str) -> Result<Vec<Url>, Error> {
+    text.lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(line, text)| {
+            Url::parse(text).map_err(|source| Error::InvalidUrl {
+                line,
+                text: text.to_string(),
+                source,
+            })
+        })
+        .collect()
+}
 
-    let signal = async {
-        ctrl_c().await.expect("failed to listen for Ctrl+C");
-        debug!("received shutdown signal");
+fn read_urls(path: &Path) -> Result<Vec<Url>> {
+    let text = if path == Path::new("-") {
+        io::read_to_string(io::stdin()).map_err(Error::Stdin)?
+    } else {
+        load_text(path)?
     };
 
-    axum::serve(listener, app)
-        .with_graceful_shutdown(signal)
-        .await
-        .map_err(|source| ServerError::Serve { source })?;
+    Ok(parse_urls(&text)?)
+}
 
-    info!("preview server stopped");
+/// Fetch a page and save it under `out_dir` if given, returning its HTML
+async fn fetch_page(fetcher: &Fetcher, url: Url, out_dir: Option<&Path>) -> Result<String> {
+    let html = fetcher.try_fetch(&url).await?.html();
 
-    Ok(())
+    if let Some(out_dir) = out_dir {
+        let path = out_dir.join(url.to_path_buf());
+        if let Some(parent) = path.parent() {
+            make_directory(parent)?;
+        }
+        save_html(&html, &path)?;
+    }
+
+    Ok(html)
+}
+
+/// Fetch all pages concurrently, returning the HTML of pages fetched successfully in order
+/// and the number of pages that failed
+async fn fetch_pages(
+    fetcher: &Fetcher,
+    urls: Vec<Url>,
+    out_dir: Option<&Path>,
+    concurrency: usize,
+) -> (Vec<String>, usize) {
+    let future = async |url| fetch_page(fetcher, url, out_dir).await.trace_report();
+
+    let results: Vec<_> = futures::stream::iter(urls)
+        .map(future)
+        .buffered(concurrency.max(1))
+        .collect()
+        .await;
+
+    let total = results.len();
+
+    let pages: Vec<_> = results.into_iter().filter_map(Result::ok).collect();
+
+    let ok = pages.len();
+
+    let failed = total - ok;
+    match failed {
+        0 => info!(ok, "fetch all pages"),
+        err => error!(ok, err, "fetch pages with errors"),
+    }
+
+    (pages, failed)
 }
 
 pub async fn run(args: Args) -> Result<()> {
+    let args = match load_config() {
+        Some(config) => merge_args(args, config),
+        None => args,
+    };
     debug!(?args);
 
     let workspace = args.workspace.build().await?;
 
+    let mut urls = args.urls;
+
+    if let Some(input) = &args.input {
+        urls.extend(read_urls(input)?);
+    }
+
+    info!(count = urls.len(), "fetch pages");
+
     let fetcher = args.fetcher.build(workspace).await?;
 
-    let html = fetcher.try_fetch(&args.url).await?.html();
+    let total = urls.len();
+
+    let (pages, failed) =
+        fetch_pages(&fetcher, urls, args.out_dir.as_deref(), args.concurrency).await;
 
     fetcher.shutdown().await;
 
+    miette::ensure!(failed == 0, Error::FetchFailed { failed, total });
+
     if args.preview {
-        start_preview_server(html, ([127, 0, 0, 1], PREVIEW_PORT)).await?;
+        match pages.into_iter().next() {
+            Some(html) => {
+                let app = Router::new().route("/", get(async || Html(html)));
+                serve(app, ([127, 0, 0, 1], PREVIEW_PORT)).await?
+            }
+            None => warn!("no page to preview"),
+        }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_urls() {
+        let text = "https://example.com\n\n# comment\n  https://rent.591.com.tw/list  \n";
+        let urls = parse_urls(text).unwrap();
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[1].as_str(), "https://rent.591.com.tw/list");
+    }
+
+    #[test]
+    fn test_parse_urls_invalid_line() {
+        let text = "https://example.com\nnot a url\n";
+        assert!(matches!(
+            parse_urls(text),
+            Err(Error::InvalidUrl { line: 2, .. })
+        ));
+    }
+}
@@ -0,0 +1,262 @@
+//! Geocode items command implementation
+
+use clap::Parser;
+use colored::Colorize;
+use miette::{IntoDiagnostic, Result};
+use tracing::{debug, info};
+use url::Url;
+
+use super::error::Error;
+use crate::apis::vision;
+use crate::config::geocoding::GeocodingConfig;
+use crate::config::google::GoogleConfig;
+use crate::config::model::{Config, load_config};
+use crate::error::TraceReport;
+use crate::geocode::{AddressSource, Geocode, Geocoder, GeocoderBackend, ItemAddress, Throttle};
+use crate::pretty::ToPrettyString;
+use crate::sites::rent591::ExportRecord;
+use crate::url::UrlExt;
+use crate::workspace::{Workspace, WorkspaceArgs};
+
+/// Geocode the addresses of stored listings and save their coordinates
+///
+/// Each listing's address is read from its address image with `--ocr`, otherwise from the
+/// location on the list page. Results are cached by normalized address, so an address
+/// shared by several listings or geocoded by an earlier run is not queried again, unless
+/// it was cached from another `--provider`. Requests are spaced to the rate the provider
+/// allows, 1 per second for the public Nominatim server.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Target URL for rent.591.com.tw search results
+    pub url: Url,
+
+    /// Read addresses from the address image of scraped items with Google Vision OCR
+    #[arg(long)]
+    pub ocr: bool,
+
+    /// Geocode at most this many listings
+    #[arg(long)]
+    pub limit: Option<usize>,
+
+    #[clap(flatten)]
+    pub google: GoogleConfig,
+
+    #[clap(flatten)]
+    pub config: GeocodingConfig,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+/// Language hints for reading address images when `rentmap.toml` has none
+const DEFAULT_OCR_LANGUAGES: [&str; 1] = ["zh-Hant"];
+
+/// Listings geocoded by a run
+#[derive(Clone, Copy, Debug, Default)]
+struct GeocodeReport {
+    queried: usize,
+    cached: usize,
+    no_address: usize,
+    failed: usize,
+}
+
+/// Reads the text of address images with Google Vision
+struct AddressReader {
+    http: reqwest::Client,
+    vision: vision::client::Client,
+    languages: Vec<String>,
+}
+
+impl AddressReader {
+    async fn read(&self, image: &Url) -> Result<String> {
+        let bytes = self
+            .http
+            .get(image.clone())
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .into_diagnostic()?
+            .bytes()
+            .await
+            .into_diagnostic()?;
+
+        let text = self
+            .vision
+            .text_detection_single(bytes, Some(&self.languages))
+            .await?;
+
+        // the image may wrap a long address over several lines
+        Ok(text.lines().map(str::trim).collect())
+    }
+}
+
+fn merge_args(mut args: Args, config: Config) -> Args {
+    if let Some(google_config) = config.google {
+        args.google.api_key = args.google.api_key.or(google_config.api_key);
+    }
+
+    if let Some(geocoding) = config.geocoding {
+        args.config = args.config.merge(geocoding);
+    }
+
+    args
+}
+
+/// Get the address of a listing, reading and saving it if there is none yet
+///
+/// An address from the list page is replaced once the address image can be read.
+async fn item_address(
+    workspace: &Workspace,
+    record: &ExportRecord,
+    reader: Option<&AddressReader>,
+) -> Result<Option<ItemAddress>> {
+    let url = &record.summary.url.0;
+    let stored = workspace.select_item_address(url).await?;
+
+    if stored
+        .as_ref()
+        .is_some_and(|stored| stored.source == AddressSource::Ocr || reader.is_none())
+    {
+        return Ok(stored);
+    }
+
+    let image = record.item.as_ref().and_then(|item| item.address.as_ref());
+
+    if let (Some(reader), Some(image)) = (reader, image)
+        && let Ok(text) = reader.read(image).await.trace_report()
+        && !text.is_empty()
+    {
+        let address = ItemAddress::new(url.clone(), text, AddressSource::Ocr);
+        workspace.save_item_address(&address).await?;
+        return Ok(Some(address));
+    }
+
+    if stored.is_some() {
+        return Ok(stored);
+    }
+
+    let Some(location) = record.summary.location() else {
+        return Ok(None);
+    };
+
+    let address = ItemAddress::new(
+        url.clone(),
+        location.replacen('-', "", 1),
+        AddressSource::Summary,
+    );
+    workspace.save_item_address(&address).await?;
+
+    Ok(Some(address))
+}
+
+fn format_results(results: &[(ItemAddress, Geocode)], report: &GeocodeReport) -> String {
+    let title = "Geocoded Listings:".bold().underline();
+    let table = results.to_pretty_string();
+
+    let mut summary = format!(
+        "Geocoded {} listings, {} queried and {} cached",
+        results.len(),
+        report.queried,
+        report.cached
+    )
+    .bright_green()
+    .to_string();
+
+    if report.no_address > 0 {
+        summary += &format!(", {} without address", report.no_address)
+            .yellow()
+            .to_string();
+    }
+
+    if report.failed > 0 {
+        summary += &format!(", {} failed", report.failed).red().to_string();
+    }
+
+    format!("{title}\n{table}\n{summary}")
+}
+
+pub async fn run(mut args: Args) -> Result<()> {
+    args.url.normalize();
+
+    let mut config = load_config();
+    let ocr_languages = config
+        .as_mut()
+        .and_then(|config| config.ocr.take())
+        .and_then(|ocr| ocr.languages);
+
+    if let Some(config) = config {
+        args = merge_args(args, config);
+    }
+    debug!(?args);
+
+    let workspace = args.workspace.build().await?;
+
+    miette::ensure!(workspace.list_exists(&args.url).await?, Error::NoRentList);
+
+    let summaries = workspace.select_item_summaries(&args.url).await?;
+    let items = workspace.select_items(&args.url).await?;
+    let records = ExportRecord::join(summaries, items);
+
+    let geocoder = GeocoderBackend::new(&args.config, args.google.clone())?;
+    let provider = geocoder.provider();
+    let mut throttle = Throttle::new(provider.default_rate());
+
+    let reader = match args.ocr {
+        true => Some(AddressReader {
+            http: reqwest::Client::new(),
+            vision: vision::client::Client::new(args.google.get_api_key()?).await?,
+            languages: ocr_languages
+                .unwrap_or_else(|| DEFAULT_OCR_LANGUAGES.map(String::from).to_vec()),
+        }),
+        false => None,
+    };
+
+    let mut report = GeocodeReport::default();
+    let mut results = Vec::new();
+
+    for record in records.iter().take(args.limit.unwrap_or(usize::MAX)) {
+        let Some(address) = item_address(&workspace, record, reader.as_ref()).await? else {
+            report.no_address += 1;
+            continue;
+        };
+
+        // results of another provider are queried again, misses included
+        let cached = workspace
+            .select_geocode(&address.normalized)
+            .await?
+            .filter(|geocode| geocode.is_from(provider));
+
+        let geocode = match cached {
+            Some(geocode) => {
+                report.cached += 1;
+                geocode
+            }
+            None => {
+                throttle.wait().await;
+
+                let result = geocoder
+                    .geocode(&address.normalized)
+                    .await
+                    .map_err(Into::into)
+                    .trace_report();
+
+                let Ok(geocode) = result else {
+                    report.failed += 1;
+                    continue;
+                };
+
+                workspace.save_geocode(&geocode).await?;
+                report.queried += 1;
+                geocode
+            }
+        };
+
+        results.push((address, geocode));
+    }
+
+    info!(?report, "geocode items");
+
+    println!("\n{}", format_results(&results, &report));
+
+    Ok(())
+}
@@ -1,27 +1,117 @@
 //! Geocoding command implementation
 
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
 use clap::Parser;
 use colored::Colorize;
-use google_maps::prelude::*;
+use csv::StringRecord;
+use futures::StreamExt;
 use miette::Result;
-use tracing::debug;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
 
+use super::error::{Error, OutputError};
+use super::output::{OutputFormat, write_json};
 use crate::config::geocoding::GeocodingConfig;
 use crate::config::google::GoogleConfig;
 use crate::config::model::{Config, load_config};
+use crate::file::PathError;
+use crate::geocode::{Geocode, GeocodeError, Geocoder, GeocoderBackend, parse_coordinate};
 use crate::pretty::ToPrettyString;
 
 /// Geocode addresses and locations
+///
+/// With `--reverse`, the query is a `lat,lng` coordinate and the addresses found there are
+/// shown instead.
+///
+/// With `--input`, every row of a CSV file is geocoded by its address column and written
+/// back with `lat`, `lng`, `formatted_address` and `status` columns appended, named
+/// `result_lat` and so on when the input has columns by those names. A row that fails to
+/// geocode keeps the error in its status instead of stopping the run.
 #[derive(Debug, Parser)]
 pub struct Args {
-    /// Address or place to geocode
-    pub query: String,
+    /// Address or place to geocode, or `lat,lng` with `--reverse`
+    #[arg(
+        required_unless_present = "input",
+        conflicts_with = "input",
+        allow_hyphen_values = true
+    )]
+    pub query: Option<String>,
+
+    /// Reverse geocode `lat,lng` coordinates into addresses
+    ///
+    /// Results use `--language`, the reverse endpoint has no region bias.
+    #[arg(long)]
+    pub reverse: bool,
+
+    /// Output format, `--input` always writes CSV
+    #[arg(long, short, value_enum, default_value_t = Default::default(), conflicts_with = "input")]
+    pub format: OutputFormat,
 
     #[clap(flatten)]
     pub google: GoogleConfig,
 
     #[clap(flatten)]
     pub config: GeocodingConfig,
+
+    #[clap(flatten)]
+    pub batch: BatchArgs,
+}
+
+/// Options for geocoding the rows of a CSV file
+#[derive(Debug, clap::Args)]
+#[command(next_help_heading = "Batch")]
+pub struct BatchArgs {
+    /// Geocode the rows of a CSV file with a header row, or `-` for stdin
+    #[arg(short, long)]
+    pub input: Option<PathBuf>,
+
+    /// Input columns holding the addresses, joined with commas if several
+    ///
+    /// With `--reverse`, the coordinates in one `lat,lng` column or two columns like
+    /// `--column lat,lng`.
+    #[arg(
+        long,
+        default_value = "address",
+        value_delimiter = ',',
+        requires = "input"
+    )]
+    pub column: Vec<String>,
+
+    /// Write the geocoded CSV to this file instead of stdout
+    #[arg(short, long, requires = "input")]
+    pub output: Option<PathBuf>,
+
+    /// Maximum addresses to geocode at the same time
+    #[arg(long, default_value_t = 10)]
+    pub concurrency: usize,
+
+    /// Maximum geocoding requests per second [default: 25, or 1 with `--provider nominatim`]
+    #[arg(long)]
+    pub rate: Option<u32>,
+}
+
+/// Columns appended to each input row, prefixed with `result_` when the input already has a
+/// column of the same name (e.g. `lat,lng` coordinates with `--reverse`)
+const RESULT_COLUMNS: [&str; 4] = ["lat", "lng", "formatted_address", "status"];
+
+/// Rows geocoded from a CSV input
+#[derive(Clone, Copy, Debug, Default)]
+struct BatchReport {
+    found: usize,
+    not_found: usize,
+    empty: usize,
+    failed: usize,
+}
+
+impl BatchReport {
+    fn total(&self) -> usize {
+        self.found + self.not_found + self.empty + self.failed
+    }
 }
 
 fn merge_args(mut args: Args, config: Config) -> Args {
@@ -30,20 +120,19 @@ fn merge_args(mut args: Args, config: Config) -> Args {
     }
 
     if let Some(geocoding) = config.geocoding {
-        args.config.language = args.config.language.or(geocoding.language);
-        args.config.region = args.config.region.or(geocoding.region);
+        args.config = args.config.merge(geocoding);
     }
 
     args
 }
 
-fn format_geocoding_response(response: &GeocodingResponse) -> String {
+fn format_geocodes(geocodes: &[Geocode]) -> String {
     let title = "Response:".bold().underline();
 
-    let table = response.to_pretty_string();
+    let table = geocodes.to_pretty_string();
 
-    let summary = match response.results.len() {
-        0 => "No locations found".red(), // unreachable
+    let summary = match geocodes.len() {
+        0 => "No locations found".red(),
         1 => "Found 1 location".bright_green(),
         n => format!("Found {n} locations").bright_green(),
     };
@@ -51,6 +140,195 @@ fn format_geocoding_response(response: &GeocodingResponse) -> String {
     format!("{title}\n{table}\n{summary}")
 }
 
+fn format_batch_report(report: &BatchReport) -> String {
+    let mut summary = format!("Geocoded {} of {} rows", report.found, report.total())
+        .bright_green()
+        .to_string();
+
+    if report.not_found + report.empty > 0 {
+        summary += &format!(
+            ", {} not found and {} without address",
+            report.not_found, report.empty
+        )
+        .yellow()
+        .to_string();
+    }
+
+    if report.failed > 0 {
+        summary += &format!(", {} failed", report.failed).red().to_string();
+    }
+
+    summary
+}
+
+/// Append the result columns to a row and count its status
+fn push_result<E: Display>(
+    row: &mut StringRecord,
+    result: Option<Result<Geocode, E>>,
+    report: &mut BatchReport,
+) {
+    let (location, formatted_address, status) = match result {
+        None => {
+            report.empty += 1;
+            (None, None, "empty".to_string())
+        }
+        Some(Ok(geocode)) => match geocode.location() {
+            Some(location) => {
+                report.found += 1;
+                (Some(location), geocode.formatted_address, "ok".to_string())
+            }
+            None => {
+                report.not_found += 1;
+                (None, None, "not_found".to_string())
+            }
+        },
+        Some(Err(err)) => {
+            report.failed += 1;
+            (None, None, format!("error: {err}"))
+        }
+    };
+
+    let (lat, lng) = location.map_or_else(Default::default, |(lat, lng)| {
+        (lat.to_string(), lng.to_string())
+    });
+
+    row.push_field(&lat);
+    row.push_field(&lng);
+    row.push_field(formatted_address.as_deref().unwrap_or_default());
+    row.push_field(&status);
+}
+
+/// Geocode the address columns of every CSV row and write the rows with results appended
+///
+/// Rows are read and written as a stream, in input order, with at most `concurrency`
+/// addresses in flight and requests spaced to at most `rate` per second.
+async fn geocode_csv<R, W, F, Fut, E>(
+    reader: R,
+    writer: W,
+    columns: &[String],
+    concurrency: usize,
+    rate: u32,
+    geocode: F,
+) -> Result<BatchReport>
+where
+    R: Read,
+    W: Write,
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Geocode, E>>,
+    E: Display,
+{
+    let mut reader = csv::Reader::from_reader(reader);
+    let mut writer = csv::Writer::from_writer(writer);
+
+    let headers = reader.headers().map_err(Error::InvalidCsv)?.clone();
+
+    let indices = columns
+        .iter()
+        .map(|column| {
+            headers
+                .iter()
+                .position(|header| header == column)
+                .ok_or_else(|| Error::MissingColumn {
+                    column: column.clone(),
+                    headers: headers.iter().map(String::from).collect(),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let result_columns = RESULT_COLUMNS.map(|column| match headers.iter().any(|h| h == column) {
+        true => format!("result_{column}"),
+        false => column.to_string(),
+    });
+
+    writer
+        .write_record(
+            headers
+                .iter()
+                .chain(result_columns.iter().map(String::as_str)),
+        )
+        .map_err(OutputError::from)?;
+
+    let start = Instant::now();
+    let interval = Duration::from_secs(1) / rate.max(1);
+    let geocode = &geocode;
+
+    let mut rows = futures::stream::iter(reader.into_records().zip(0u32..))
+        .map(async |(record, i)| {
+            let record = record.map_err(Error::InvalidCsv)?;
+
+            let fields: Vec<_> = indices
+                .iter()
+                .map(|&index| record.get(index).unwrap_or_default().trim())
+                .collect();
+
+            if fields.iter().all(|field| field.is_empty()) {
+                return Ok((record, None));
+            }
+
+            let address = fields.join(",");
+
+            tokio::time::sleep_until(start + interval * i).await;
+
+            let result = geocode(address.clone()).await;
+
+            if let Err(err) = &result {
+                warn!(%address, %err, "geocode row");
+            }
+
+            Ok::<_, Error>((record, Some(result)))
+        })
+        .buffered(concurrency.max(1));
+
+    let mut report = BatchReport::default();
+
+    while let Some(row) = rows.next().await {
+        let (mut record, result) = row?;
+        push_result(&mut record, result, &mut report);
+        writer.write_record(&record).map_err(OutputError::from)?;
+    }
+
+    writer.flush().map_err(OutputError::from)?;
+
+    Ok(report)
+}
+
+fn open_input(path: &Path) -> Result<Box<dyn Read>> {
+    if path == Path::new("-") {
+        return Ok(Box::new(io::stdin()));
+    }
+
+    let file = File::open(path).map_err(|source| PathError::new(path, source))?;
+
+    Ok(Box::new(BufReader::new(file)))
+}
+
+/// Geocode the rows of `input` and write them to `--output` or stdout
+async fn geocode_rows<F, Fut>(batch: &BatchArgs, input: &Path, rate: u32, geocode: F) -> Result<()>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<Geocode, GeocodeError>>,
+{
+    let reader = open_input(input)?;
+    let (columns, concurrency) = (&batch.column, batch.concurrency);
+
+    match &batch.output {
+        Some(path) => {
+            let file = File::create(path).map_err(|source| PathError::new(path, source))?;
+            let writer = BufWriter::new(file);
+            let report = geocode_csv(reader, writer, columns, concurrency, rate, geocode).await?;
+            info!(path = %path.display(), ?report, "geocode rows");
+            println!("\n{}", format_batch_report(&report));
+        }
+        None => {
+            let report =
+                geocode_csv(reader, io::stdout(), columns, concurrency, rate, geocode).await?;
+            info!(?report, "geocode rows");
+        }
+    }
+
+    Ok(())
+}
+
 /// Run the CLI application
 pub async fn run(args: Args) -> Result<()> {
     let args = match load_config() {
@@ -59,23 +337,153 @@ pub async fn run(args: Args) -> Result<()> {
     };
     debug!(?args);
 
-    let api_key = args.google.get_api_key()?;
+    let geocoder = GeocoderBackend::new(&args.config, args.google)?;
+
+    if let Some(input) = &args.batch.input {
+        let provider = args.config.provider.unwrap_or_default();
+        let rate = args.batch.rate.unwrap_or(provider.default_rate());
+        let geocoder = &geocoder;
+
+        return match args.reverse {
+            true => {
+                let geocode = async |text: String| {
+                    let (lat, lng) = parse_coordinate(&text)?;
+                    geocoder.reverse(lat, lng).await
+                };
+                geocode_rows(&args.batch, input, rate, geocode).await
+            }
+            false => {
+                let geocode = async |address: String| geocoder.geocode(&address).await;
+                geocode_rows(&args.batch, input, rate, geocode).await
+            }
+        };
+    }
+
+    let query = args.query.unwrap_or_default();
 
-    let client = Client::try_new(api_key)?;
+    let geocodes = match args.reverse {
+        true => {
+            let (lat, lng) = parse_coordinate(&query)?;
+            geocoder.search_reverse(lat, lng).await?
+        }
+        false => geocoder.search(&query).await?,
+    };
 
-    let mut builder = client.geocoding().with_address(&args.query);
+    let mut stdout = io::stdout().lock();
 
-    if let Some(language) = &args.config.language {
-        builder = builder.with_language(language);
+    match args.format {
+        OutputFormat::Table => {
+            writeln!(stdout, "\n{}", format_geocodes(&geocodes)).map_err(OutputError::from)?;
+        }
+        OutputFormat::Text => {
+            for geocode in &geocodes {
+                let (lat, lng) = geocode.location().unzip();
+                writeln!(
+                    stdout,
+                    "{}\t{}\t{}",
+                    lat.map(|lat| lat.to_string()).unwrap_or_default(),
+                    lng.map(|lng| lng.to_string()).unwrap_or_default(),
+                    geocode.formatted_address.as_deref().unwrap_or_default()
+                )
+                .map_err(OutputError::from)?;
+            }
+        }
+        OutputFormat::Json => write_json(&mut stdout, &geocodes)?,
     }
 
-    if let Some(region) = &args.config.region {
-        builder = builder.with_region(region);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn fake_geocode(address: String) -> Result<Geocode, String> {
+        match address.as_str() {
+            "台北車站" => Ok(Geocode {
+                address: address.clone(),
+                lat: Some(25.0478),
+                lng: Some(121.517),
+                formatted_address: Some("100台灣臺北市中正區北平西路3號".to_string()),
+                location_type: Some("ROOFTOP".to_string()),
+                place_id: None,
+                provider: None,
+            }),
+            "nowhere" | "0,0" => Ok(Geocode::not_found(address)),
+            _ => Err("over query limit".to_string()),
+        }
     }
 
-    let response = builder.execute().await?;
+    #[tokio::test]
+    async fn test_geocode_csv() {
+        let input = "name,address\nstation,台北車站\nhome,\"nowhere\"\nschool,  \nbad,x\n";
+        let mut output = Vec::new();
 
-    println!("\n{}", format_geocoding_response(&response));
+        let report = geocode_csv(
+            input.as_bytes(),
+            &mut output,
+            &["address".to_string()],
+            2,
+            1000,
+            fake_geocode,
+        )
+        .await
+        .unwrap();
 
-    Ok(())
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "name,address,lat,lng,formatted_address,status\n\
+             station,台北車站,25.0478,121.517,100台灣臺北市中正區北平西路3號,ok\n\
+             home,nowhere,,,,not_found\n\
+             school,  ,,,,empty\n\
+             bad,x,,,,error: over query limit\n"
+        );
+        assert_eq!(report.total(), 4);
+        assert_eq!(report.failed, 1);
+    }
+
+    #[tokio::test]
+    async fn test_geocode_csv_joins_columns() {
+        let input = "pin,lat,lng\nnull island,0,0\nblank,,\n";
+        let mut output = Vec::new();
+        let columns = ["lat".to_string(), "lng".to_string()];
+
+        let report = geocode_csv(
+            input.as_bytes(),
+            &mut output,
+            &columns,
+            1,
+            1000,
+            fake_geocode,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "pin,lat,lng,result_lat,result_lng,formatted_address,status\n\
+             null island,0,0,,,,not_found\n\
+             blank,,,,,,empty\n"
+        );
+        assert_eq!((report.not_found, report.empty), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_geocode_csv_missing_column() {
+        let result = geocode_csv(
+            "name,addr\na,b\n".as_bytes(),
+            Vec::new(),
+            &["address".to_string()],
+            1,
+            1,
+            fake_geocode,
+        )
+        .await;
+
+        let err = result.unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<Error>(),
+            Some(Error::MissingColumn { .. })
+        ));
+    }
 }
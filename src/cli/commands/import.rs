@@ -0,0 +1,255 @@
+//! Import command implementation
+
+use std::path::{Path, PathBuf};
+
+use clap::{Parser, ValueEnum};
+use miette::Result;
+use serde::Deserialize;
+use tracing::{debug, info};
+use url::Url;
+
+use super::error::Error;
+use crate::file::{load_json, load_text};
+use crate::sites::rent591::{Rent591Url, RentRecord, UrlError};
+use crate::url::UrlExt;
+use crate::workspace::{ConflictPolicy, Workspace, WorkspaceArgs, WorkspaceError};
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ImportFormat {
+    /// A single record or a JSON array of records
+    Json,
+    /// One record per line
+    Ndjson,
+}
+
+/// Import rental lists and items from JSON or NDJSON into the workspace
+///
+/// Records are rental lists (`url`, `page_count`, `item_count`, `pages`) or rental items
+/// (`url`, `title`, `labels`, `patterns`, `content`, ...), each with an optional
+/// `created_at` timestamp in `YYYY-MM-DD HH:MM:SS` UTC form. Records without one are
+/// stamped with the import time. `rentmap export --records` writes records in this form.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// File to import
+    pub path: PathBuf,
+
+    /// Input format [default: ndjson for .ndjson and .jsonl files, json otherwise]
+    #[arg(long, short, value_enum)]
+    pub format: Option<ImportFormat>,
+
+    /// How to resolve records that already exist in the workspace
+    #[arg(long, value_enum, default_value_t = Default::default())]
+    pub on_conflict: ConflictPolicy,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Records {
+    Many(Vec<RentRecord>),
+    One(RentRecord),
+}
+
+impl From<Records> for Vec<RentRecord> {
+    fn from(records: Records) -> Self {
+        match records {
+            Records::Many(records) => records,
+            Records::One(record) => vec![record],
+        }
+    }
+}
+
+fn infer_format(path: &Path) -> ImportFormat {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("ndjson" | "jsonl") => ImportFormat::Ndjson,
+        _ => ImportFormat::Json,
+    }
+}
+
+fn parse_ndjson(text: &str) -> Result<Vec<RentRecord>, Error> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|source| Error::InvalidRecord {
+                line: i + 1,
+                source,
+            })
+        })
+        .collect()
+}
+
+fn load_records(path: &Path, format: ImportFormat) -> Result<Vec<RentRecord>> {
+    let records = match format {
+        ImportFormat::Json => load_json::<_, Records>(path)?.into(),
+        ImportFormat::Ndjson => parse_ndjson(&load_text(path)?)?,
+    };
+
+    Ok(records)
+}
+
+fn expect_list(url: &mut Url) -> Result<(), UrlError> {
+    url.normalize();
+    match Rent591Url::try_from(url.clone())? {
+        Rent591Url::List(_) => Ok(()),
+        Rent591Url::Item(url) => Err(UrlError::ExpectList(url)),
+    }
+}
+
+fn expect_item(url: &Url) -> Result<(), UrlError> {
+    match Rent591Url::try_from(url.clone())? {
+        Rent591Url::Item(_) => Ok(()),
+        Rent591Url::List(url) => Err(UrlError::ExpectItem(url)),
+    }
+}
+
+/// Check every list and item URL is a rent.591.com.tw URL of the right kind
+fn validate(record: &mut RentRecord) -> Result<(), UrlError> {
+    match record {
+        RentRecord::List(list) => {
+            expect_list(&mut list.value.url)?;
+            list.value.item_urls().try_for_each(expect_item)
+        }
+        RentRecord::Item(item) => expect_item(&item.value.url),
+    }
+}
+
+pub async fn run(args: Args) -> Result<()> {
+    debug!(?args);
+
+    let format = args.format.unwrap_or_else(|| infer_format(&args.path));
+
+    let mut records = load_records(&args.path, format)?;
+
+    records.iter_mut().try_for_each(validate)?;
+
+    let workspace = args.workspace.build().await?;
+
+    import_records(&workspace, records, args.on_conflict).await?;
+
+    Ok(())
+}
+
+/// Import lists then items, returning the number of lists and items written
+async fn import_records(
+    workspace: &Workspace,
+    records: Vec<RentRecord>,
+    policy: ConflictPolicy,
+) -> Result<(usize, u64), WorkspaceError> {
+    let (lists, items): (Vec<_>, Vec<_>) = records
+        .into_iter()
+        .partition(|record| matches!(record, RentRecord::List(_)));
+
+    let mut lists_written = 0;
+
+    for record in &lists {
+        if let RentRecord::List(list) = record {
+            let written = workspace
+                .import_list(&list.value, list.created_at.as_deref(), policy)
+                .await?;
+            lists_written += written as usize;
+        }
+    }
+
+    let items: Vec<_> = items
+        .iter()
+        .filter_map(|record| match record {
+            RentRecord::Item(item) => Some((&item.value, item.created_at.as_deref())),
+            RentRecord::List(_) => None,
+        })
+        .collect();
+
+    let items_written = workspace.import_items(&items, policy).await?;
+
+    info!(
+        lists = lists.len(),
+        lists_written,
+        items = items.len(),
+        items_written,
+        "import records"
+    );
+
+    Ok((lists_written, items_written))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::commands::export::write_rent_records;
+    use crate::file::TempDir;
+    use crate::workspace::LockMode;
+
+    const LIST: &str = r#"{"url":"https://rent.591.com.tw/list?region=1","page_count":1,"item_count":1,"pages":[{"items":[{"url":"https://rent.591.com.tw/123","title":null,"price":null,"tags":[],"txts":[],"images":[]}]}]}"#;
+
+    const ITEM: &str = r#"{"url":"https://rent.591.com.tw/123","created_at":"2025-01-01 00:00:00","title":"套房","labels":[],"patterns":[],"content":"","phone":null,"album":[],"area":null,"floor":null,"price":null,"address":null}"#;
+
+    #[test]
+    fn test_parse_ndjson() {
+        let records = parse_ndjson(&format!("{LIST}\n\n{ITEM}\n")).unwrap();
+        assert!(matches!(records[0], RentRecord::List(_)));
+        assert!(
+            matches!(&records[1], RentRecord::Item(item) if item.created_at.as_deref() == Some("2025-01-01 00:00:00"))
+        );
+    }
+
+    #[test]
+    fn test_parse_json_one_or_many() {
+        let one: Vec<RentRecord> = serde_json::from_str::<Records>(ITEM).unwrap().into();
+        let many: Vec<RentRecord> = serde_json::from_str::<Records>(&format!("[{LIST},{ITEM}]"))
+            .unwrap()
+            .into();
+        assert_eq!(one.len(), 1);
+        assert_eq!(many.len(), 2);
+    }
+
+    #[test]
+    fn test_validate_rejects_item_as_list() {
+        let list = LIST.replace("list?region=1", "456");
+        let mut record: RentRecord = serde_json::from_str(&list).unwrap();
+        assert!(matches!(
+            validate(&mut record),
+            Err(UrlError::ExpectList(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_records_round_trip() {
+        let root = TempDir::new("import");
+        let mut source = Workspace::new(root.join("source"));
+        source.init(LockMode::Exclusive, false).await.unwrap();
+        let mut target = Workspace::new(root.join("target"));
+        target.init(LockMode::Exclusive, false).await.unwrap();
+
+        let Ok(RentRecord::List(list)) = serde_json::from_str(LIST) else {
+            panic!("expected a list record");
+        };
+        let Ok(RentRecord::Item(item)) = serde_json::from_str(ITEM) else {
+            panic!("expected an item record");
+        };
+        source.insert_list(&list.value).await.unwrap();
+        source.insert_items(&[item.value]).await.unwrap();
+
+        let list_url = list.value.url.0;
+        let exported = source.select_rent_records(&list_url).await.unwrap();
+        let mut output = Vec::new();
+        write_rent_records(&mut output, &exported).unwrap();
+
+        let mut records = parse_ndjson(&String::from_utf8(output).unwrap()).unwrap();
+        records.iter_mut().try_for_each(validate).unwrap();
+        let written = import_records(&target, records, ConflictPolicy::default())
+            .await
+            .unwrap();
+        assert_eq!(written, (1, 1));
+
+        let imported = target.select_rent_records(&list_url).await.unwrap();
+        assert_eq!(
+            serde_json::to_value(&imported).unwrap(),
+            serde_json::to_value(&exported).unwrap()
+        );
+
+        source.close().await;
+        target.close().await;
+    }
+}
@@ -1,10 +1,16 @@
+use std::io::{self, Write};
+
 use clap::Parser;
+use colored::Colorize;
 use miette::Result;
 use tracing::{debug, info, warn};
 use url::Url;
 
-use super::error::Error;
-use crate::sites::rent591::{Rent591Url, scrape_item, scrape_items};
+use super::error::{Error, OutputError};
+use super::output::{OutputFormat, write_json};
+use crate::config::model::{Config, load_config};
+use crate::pretty::ToPrettyString;
+use crate::sites::rent591::{Rent591Url, RentItem, scrape_item, scrape_items};
 use crate::url::UrlExt;
 use crate::web::{Fetcher, FetcherArgs};
 use crate::workspace::{Workspace, WorkspaceArgs};
@@ -23,6 +29,10 @@ pub struct Args {
     #[arg(long, short)]
     pub limit: Option<u32>,
 
+    /// Print the scraped item summaries in this format [default: print nothing]
+    #[arg(long, short, value_enum)]
+    pub format: Option<OutputFormat>,
+
     #[clap(flatten)]
     pub workspace: WorkspaceArgs,
 
@@ -30,14 +40,22 @@ pub struct Args {
     pub fetcher: FetcherArgs,
 }
 
-/// Handle Rent591 list URLs
+fn merge_args(mut args: Args, config: Config) -> Args {
+    if let Some(fetcher) = config.fetcher {
+        args.fetcher = args.fetcher.merge(fetcher);
+    }
+
+    args
+}
+
+/// Handle Rent591 list URLs, returning the items scraped
 async fn handle_list(
     url: Url,
     refresh: bool,
     limit: Option<u32>,
     workspace: &Workspace,
     fetcher: &Fetcher,
-) -> Result<()> {
+) -> Result<Vec<RentItem>> {
     miette::ensure!(workspace.list_exists(&url).await?, Error::NoRentList);
 
     let urls = workspace
@@ -45,37 +63,78 @@ async fn handle_list(
         .await?;
 
     match urls.len() {
-        0 => warn!("no items found"),
+        0 => {
+            warn!("no items found");
+            Ok(Vec::new())
+        }
         n => {
             info!(count = n, "find items");
             let items = scrape_items(fetcher, urls.into_iter().map(|url| url.0)).await?;
             workspace.insert_items(&items).await?;
+            Ok(items)
         }
     }
-
-    Ok(())
 }
 
-/// Handle Rent591 item URLs
+/// Handle Rent591 item URLs, returning the item scraped or the stored one if skipped
 async fn handle_item(
     url: Url,
     refresh: bool,
     workspace: &Workspace,
     fetcher: &Fetcher,
-) -> Result<()> {
-    if !refresh && workspace.item_exists(&url).await? {
+) -> Result<Vec<RentItem>> {
+    if !refresh && let Some(item) = workspace.select_item(&url).await? {
         info!("skip existing item");
-        return Ok(());
+        return Ok(vec![item]);
     }
 
     let item = scrape_item(fetcher, url).await?;
 
-    workspace.insert_items(&[item]).await?;
+    workspace.insert_items(std::slice::from_ref(&item)).await?;
+
+    Ok(vec![item])
+}
+
+fn format_items(items: &[RentItem]) -> String {
+    let table = items.to_pretty_string();
+
+    let summary = match items.len() {
+        0 => "No items scraped".yellow(),
+        1 => "Scraped 1 item".bright_green(),
+        n => format!("Scraped {n} items").bright_green(),
+    };
+
+    format!("{table}\n{summary}")
+}
+
+/// Print the summaries of scraped items
+fn print_items(items: &[RentItem], format: OutputFormat) -> Result<(), OutputError> {
+    let mut stdout = io::stdout().lock();
+
+    match format {
+        OutputFormat::Table => writeln!(stdout, "{}", format_items(items))?,
+        OutputFormat::Text => {
+            for item in items {
+                writeln!(
+                    stdout,
+                    "{}\t{}\t{}",
+                    item.url.as_str(),
+                    item.title.as_deref().unwrap_or_default(),
+                    item.patterns.join(" ")
+                )?;
+            }
+        }
+        OutputFormat::Json => write_json(&mut stdout, items)?,
+    }
 
     Ok(())
 }
 
-pub async fn run(mut args: Args) -> Result<()> {
+pub async fn run(args: Args) -> Result<()> {
+    let mut args = match load_config() {
+        Some(config) => merge_args(args, config),
+        None => args,
+    };
     args.url.normalize();
 
     debug!(?args);
@@ -84,16 +143,18 @@ pub async fn run(mut args: Args) -> Result<()> {
 
     let fetcher = args.fetcher.build(workspace.clone()).await?;
 
-    match Rent591Url::try_from(args.url)? {
+    let items = match Rent591Url::try_from(args.url)? {
         Rent591Url::List(url) => {
-            handle_list(url, args.refresh, args.limit, &workspace, &fetcher).await?;
-        }
-        Rent591Url::Item(url) => {
-            handle_item(url, args.refresh, &workspace, &fetcher).await?;
+            handle_list(url, args.refresh, args.limit, &workspace, &fetcher).await?
         }
-    }
+        Rent591Url::Item(url) => handle_item(url, args.refresh, &workspace, &fetcher).await?,
+    };
 
     fetcher.shutdown().await;
 
+    if let Some(format) = args.format {
+        print_items(&items, format)?;
+    }
+
     Ok(())
 }
@@ -1,9 +1,16 @@
+use std::io::{self, Write};
+
 use clap::Parser;
+use colored::Colorize;
 use miette::Result;
 use tracing::{debug, info};
 use url::Url;
 
-use crate::sites::rent591::scrape_list_and_pages;
+use super::error::OutputError;
+use super::output::{OutputFormat, write_json};
+use crate::config::model::{Config, load_config};
+use crate::pretty::ToPrettyString;
+use crate::sites::rent591::{RentItemSummary, scrape_list_and_pages};
 use crate::url::UrlExt;
 use crate::web::{Fetcher, FetcherArgs};
 use crate::workspace::{Workspace, WorkspaceArgs};
@@ -22,6 +29,10 @@ pub struct Args {
     #[arg(long, short)]
     pub limit: Option<u32>,
 
+    /// Print the listing summaries in this format [default: print nothing]
+    #[arg(long, short, value_enum)]
+    pub format: Option<OutputFormat>,
+
     #[clap(flatten)]
     pub workspace: WorkspaceArgs,
 
@@ -29,6 +40,14 @@ pub struct Args {
     pub fetcher: FetcherArgs,
 }
 
+fn merge_args(mut args: Args, config: Config) -> Args {
+    if let Some(fetcher) = config.fetcher {
+        args.fetcher = args.fetcher.merge(fetcher);
+    }
+
+    args
+}
+
 async fn handle_list(
     url: Url,
     refresh: bool,
@@ -47,7 +66,47 @@ async fn handle_list(
     Ok(())
 }
 
-pub async fn run(mut args: Args) -> Result<()> {
+fn format_summaries(summaries: &[RentItemSummary]) -> String {
+    let table = summaries.to_pretty_string();
+
+    let summary = match summaries.len() {
+        0 => "No listings found".red(),
+        1 => "Found 1 listing".bright_green(),
+        n => format!("Found {n} listings").bright_green(),
+    };
+
+    format!("{table}\n{summary}")
+}
+
+/// Print the summaries of the latest list snapshot
+fn print_summaries(summaries: &[RentItemSummary], format: OutputFormat) -> Result<(), OutputError> {
+    let mut stdout = io::stdout().lock();
+
+    match format {
+        OutputFormat::Table => writeln!(stdout, "{}", format_summaries(summaries))?,
+        OutputFormat::Text => {
+            for summary in summaries {
+                writeln!(
+                    stdout,
+                    "{}\t{}\t{}\t{}",
+                    summary.url.as_str(),
+                    summary.title.as_deref().unwrap_or_default(),
+                    summary.price.as_deref().unwrap_or_default(),
+                    summary.location().unwrap_or_default()
+                )?;
+            }
+        }
+        OutputFormat::Json => write_json(&mut stdout, summaries)?,
+    }
+
+    Ok(())
+}
+
+pub async fn run(args: Args) -> Result<()> {
+    let mut args = match load_config() {
+        Some(config) => merge_args(args, config),
+        None => args,
+    };
     args.url.normalize();
 
     debug!(?args);
@@ -56,9 +115,21 @@ pub async fn run(mut args: Args) -> Result<()> {
 
     let fetcher = args.fetcher.build(workspace.clone()).await?;
 
-    handle_list(args.url, args.refresh, args.limit, &workspace, &fetcher).await?;
+    handle_list(
+        args.url.clone(),
+        args.refresh,
+        args.limit,
+        &workspace,
+        &fetcher,
+    )
+    .await?;
 
     fetcher.shutdown().await;
 
+    if let Some(format) = args.format {
+        let summaries = workspace.select_item_summaries(&args.url).await?;
+        print_summaries(&summaries, format)?;
+    }
+
     Ok(())
 }
@@ -0,0 +1,175 @@
+//! Helpers shared by the commands over the locations found by `rentmap geocode-items`
+
+use std::collections::BTreeMap;
+
+use clap::Args;
+use colored::Colorize;
+use comfy_table::{Cell, ContentArrangement, Table, presets};
+use miette::Result;
+use url::Url;
+
+use super::error::Error;
+use crate::config::geocoding::GeocodingConfig;
+use crate::config::google::GoogleConfig;
+use crate::config::model::load_config;
+use crate::config::poi::PoiConfig;
+use crate::geocode::{Geocoder, GeocoderBackend, Poi, Throttle, normalize_address};
+use crate::workspace::Workspace;
+
+/// Options for geocoding the addresses of points of interest
+#[derive(Debug, Args)]
+pub struct PoiArgs {
+    #[clap(flatten)]
+    pub google: GoogleConfig,
+
+    #[clap(flatten)]
+    pub config: GeocodingConfig,
+}
+
+impl PoiArgs {
+    /// Take the points of interest of `rentmap.toml`, filling unset options from it
+    pub fn load_pois(&mut self) -> Vec<PoiConfig> {
+        let Some(config) = load_config() else {
+            return Vec::new();
+        };
+
+        if let Some(google_config) = config.google {
+            self.google.api_key = self.google.api_key.take().or(google_config.api_key);
+        }
+
+        if let Some(geocoding) = config.geocoding {
+            self.config = std::mem::take(&mut self.config).merge(geocoding);
+        }
+
+        config.poi.unwrap_or_default()
+    }
+
+    /// Locate the points of interest, geocoding addresses that are not cached yet
+    pub async fn resolve_pois(
+        &self,
+        workspace: &Workspace,
+        pois: Vec<PoiConfig>,
+    ) -> Result<Vec<Poi>> {
+        let provider = self.config.provider.unwrap_or_default();
+        let mut geocoder = None;
+        let mut throttle = Throttle::new(provider.default_rate());
+        let mut resolved = Vec::with_capacity(pois.len());
+
+        for poi in pois {
+            let location = match (poi.location(), &poi.address) {
+                (Some(location), _) => Some(location),
+                (None, Some(address)) => {
+                    let address = normalize_address(address);
+
+                    let cached = workspace
+                        .select_geocode(&address)
+                        .await?
+                        .filter(|geocode| geocode.is_from(provider));
+
+                    let geocode = match cached {
+                        Some(geocode) => geocode,
+                        None => {
+                            let geocoder = match &mut geocoder {
+                                Some(geocoder) => geocoder,
+                                None => geocoder.insert(GeocoderBackend::new(
+                                    &self.config,
+                                    self.google.clone(),
+                                )?),
+                            };
+
+                            throttle.wait().await;
+                            let geocode = geocoder.geocode(&address).await?;
+                            workspace.save_geocode(&geocode).await?;
+                            geocode
+                        }
+                    };
+
+                    geocode.location()
+                }
+                (None, None) => None,
+            };
+
+            let (lat, lng) = location.ok_or_else(|| Error::InvalidPoi(poi.name.clone()))?;
+
+            resolved.push(Poi {
+                name: poi.name,
+                lat,
+                lng,
+            });
+        }
+
+        Ok(resolved)
+    }
+}
+
+/// The located items of the latest snapshot of a list, in list order
+pub struct Located {
+    pub listings: Vec<(Url, (f64, f64))>,
+    /// Items without an address or whose address was not geocoded
+    pub no_location: usize,
+}
+
+impl Located {
+    pub async fn select(workspace: &Workspace, list_url: &Url) -> Result<Self> {
+        let summaries = workspace.select_item_summaries(list_url).await?;
+        let mut locations = workspace
+            .select_item_locations(Some(list_url), None)
+            .await?;
+
+        let total = summaries.len();
+        let listings: Vec<_> = summaries
+            .into_iter()
+            .filter_map(|summary| {
+                let location = locations.remove(&summary.url.0)?;
+                Some((summary.url.0, location))
+            })
+            .collect();
+        let no_location = total - listings.len();
+
+        Ok(Self {
+            listings,
+            no_location,
+        })
+    }
+}
+
+/// Append the count of listings without location to the summary line of a run, if any
+pub fn no_location_note(summary: String, no_location: usize) -> String {
+    if no_location == 0 {
+        return summary;
+    }
+
+    let note = format!(", {no_location} without location, run `rentmap geocode-items` first");
+    summary + &note.yellow().to_string()
+}
+
+/// Format a value per point of interest of each listing as a titled table with a summary
+pub fn format_poi_results<T>(
+    title: &str,
+    pois: &[Poi],
+    results: &[(Url, BTreeMap<String, T>)],
+    cell: impl Fn(&T) -> String,
+    summary: String,
+) -> String {
+    let mut table = Table::new();
+
+    table
+        .load_preset(presets::UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(
+            std::iter::once("URL")
+                .chain(pois.iter().map(|poi| poi.name.as_str()))
+                .map(|header| Cell::new(header.bold().dimmed())),
+        );
+
+    for (url, values) in results {
+        let mut row = vec![Cell::new(url.as_str().bright_blue())];
+        row.extend(
+            pois.iter()
+                .map(|poi| Cell::new(cell(&values[&poi.name]).bright_cyan())),
+        );
+        table.add_row(row);
+    }
+
+    format!("{}\n{table}\n{summary}", title.bold().underline())
+}
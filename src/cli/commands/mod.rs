@@ -1,7 +1,21 @@
 // Shared command helpers and options
+pub mod commute;
+pub mod distances;
 pub mod error;
+pub mod export;
 pub mod fetch;
+pub mod geocode_items;
 pub mod geocoding;
+pub mod import;
 pub mod item;
 pub mod list;
+pub mod located;
 pub mod ocr;
+pub mod output;
+pub mod preview;
+pub mod query;
+pub mod search;
+pub mod sql;
+pub mod stations;
+pub mod within;
+pub mod workspace;
@@ -1,10 +1,15 @@
+use std::io::{self, Write};
 use std::path::PathBuf;
 
 use clap::Parser;
 use colored::Colorize;
 use miette::Result;
+use serde_json::json;
 use tracing::debug;
 
+use super::error::OutputError;
+use super::output::{OutputFormat, write_json};
+
 use crate::apis::vision::client::Client;
 use crate::apis::vision::model::OcrString;
 use crate::config::google::GoogleConfig;
@@ -20,6 +25,10 @@ pub struct Args {
     /// Image file path
     pub path: PathBuf,
 
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = Default::default())]
+    pub format: OutputFormat,
+
     #[clap(flatten)]
     pub google: GoogleConfig,
 
@@ -66,7 +75,25 @@ pub async fn run(args: Args) -> Result<()> {
         .text_detection_single(image_bytes, args.config.languages)
         .await?;
 
-    println!("\n{}", format_ocr_result(&detected_text));
+    let mut stdout = io::stdout().lock();
+
+    match args.format {
+        OutputFormat::Table => {
+            writeln!(stdout, "\n{}", format_ocr_result(&detected_text))
+                .map_err(OutputError::from)?;
+        }
+        OutputFormat::Text => {
+            writeln!(stdout, "{}", detected_text.as_str()).map_err(OutputError::from)?;
+        }
+        OutputFormat::Json => {
+            let value = json!({
+                "path": args.path,
+                "text": detected_text.as_str(),
+                "lines": detected_text.lines().collect::<Vec<_>>(),
+            });
+            write_json(&mut stdout, &value)?;
+        }
+    }
 
     Ok(())
 }
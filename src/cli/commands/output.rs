@@ -0,0 +1,30 @@
+//! Output format shared by commands that print results
+
+use std::io::Write;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use super::error::OutputError;
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum OutputFormat {
+    /// A table for reading in the terminal
+    #[default]
+    Table,
+    /// Plain tab-separated lines, one result per line, for shell pipelines
+    Text,
+    /// JSON for scripting
+    Json,
+}
+
+/// Write a value as pretty-printed JSON followed by a newline
+pub fn write_json<W, T>(mut writer: W, value: &T) -> Result<(), OutputError>
+where
+    W: Write,
+    T: Serialize + ?Sized,
+{
+    serde_json::to_writer_pretty(&mut writer, value)?;
+    writeln!(writer)?;
+    Ok(())
+}
@@ -0,0 +1,225 @@
+//! Preview command implementation
+
+use std::fmt::Write;
+use std::net::SocketAddr;
+
+use axum::Router;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use clap::Parser;
+use miette::Result;
+use scraper::Html as Document;
+use serde::Deserialize;
+use tokio::net::TcpListener;
+use tokio::signal::ctrl_c;
+use tracing::{debug, error, info};
+use url::Url;
+use url::form_urlencoded::byte_serialize;
+
+use super::error::ServerError;
+use crate::pretty::format_size;
+use crate::scraper::HtmlExt;
+use crate::workspace::{Workspace, WorkspaceArgs, WorkspaceError};
+
+pub const PREVIEW_PORT: u16 = 3000;
+
+/// Browse cached pages of the workspace in a web browser
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Port to serve the preview on
+    #[arg(long, default_value_t = PREVIEW_PORT)]
+    pub port: u16,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+/// Serve the router on the given address until Ctrl+C is received
+pub async fn serve<T>(app: Router, addr: T) -> Result<()>
+where
+    T: Into<SocketAddr>,
+{
+    let addr: SocketAddr = addr.into();
+
+    info!(addr = %format!("http://{addr}"), "start preview server");
+
+    let listener = TcpListener::bind(addr)
+        .await
+        .map_err(|source| ServerError::Bind { source, addr })?;
+
+    let signal = async {
+        ctrl_c().await.expect("failed to listen for Ctrl+C");
+        debug!("received shutdown signal");
+    };
+
+    axum::serve(listener, app)
+        .with_graceful_shutdown(signal)
+        .await
+        .map_err(|source| ServerError::Serve { source })?;
+
+    info!("preview server stopped");
+
+    Ok(())
+}
+
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    url: Url,
+}
+
+/// Error response of the preview routes
+enum PreviewError {
+    NotFound(Url),
+    Workspace(WorkspaceError),
+}
+
+impl From<WorkspaceError> for PreviewError {
+    fn from(err: WorkspaceError) -> Self {
+        Self::Workspace(err)
+    }
+}
+
+impl IntoResponse for PreviewError {
+    fn into_response(self) -> Response {
+        match self {
+            Self::NotFound(url) => {
+                (StatusCode::NOT_FOUND, format!("page not cached: {url}")).into_response()
+            }
+            Self::Workspace(err) => {
+                error!(?err, "failed to read page cache");
+                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string()).into_response()
+            }
+        }
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn encode_query(url: &Url) -> String {
+    byte_serialize(url.as_str().as_bytes()).collect()
+}
+
+async fn load_document(
+    workspace: &Workspace,
+    url: Url,
+    clean: bool,
+) -> Result<String, PreviewError> {
+    let page = workspace
+        .get_cached_page(&url)
+        .await?
+        .ok_or(PreviewError::NotFound(url))?;
+
+    let mut document = Document::parse_document(&page.html);
+
+    if clean {
+        document.hide_scripts();
+    }
+
+    document.absolutize_urls(&page.url);
+
+    Ok(document.html())
+}
+
+async fn index(State(workspace): State<Workspace>) -> Result<Html<String>, PreviewError> {
+    let pages = workspace.select_cached_pages().await?;
+
+    let mut rows = String::new();
+
+    for page in &pages {
+        let query = encode_query(&page.url);
+        let url = escape_html(page.url.as_str());
+        let _ = write!(
+            rows,
+            "<tr><td><a href=\"/clean?url={query}\">{url}</a></td><td>{}</td><td class=\"size\">{}</td>\
+             <td><a href=\"/raw?url={query}\">raw</a> · <a href=\"/compare?url={query}\">compare</a></td></tr>",
+            escape_html(&page.created_at),
+            format_size(page.size),
+        );
+    }
+
+    Ok(Html(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>RentMap Page Cache</title>\
+         <style>body{{font-family:sans-serif;margin:2em}}table{{border-collapse:collapse}}\
+         td,th{{padding:.3em .8em;border-bottom:1px solid #ddd;text-align:left}}\
+         .size{{text-align:right}}td:first-child{{word-break:break-all}}</style></head>\
+         <body><h1>Page Cache</h1><p>{} cached pages</p>\
+         <table><tr><th>URL</th><th>Cached At</th><th>Size</th><th></th></tr>{rows}</table></body></html>",
+        pages.len()
+    )))
+}
+
+async fn raw(
+    State(workspace): State<Workspace>,
+    Query(query): Query<PageQuery>,
+) -> Result<Html<String>, PreviewError> {
+    load_document(&workspace, query.url, false).await.map(Html)
+}
+
+async fn clean(
+    State(workspace): State<Workspace>,
+    Query(query): Query<PageQuery>,
+) -> Result<Html<String>, PreviewError> {
+    load_document(&workspace, query.url, true).await.map(Html)
+}
+
+async fn compare(Query(query): Query<PageQuery>) -> Html<String> {
+    let encoded = encode_query(&query.url);
+    let url = escape_html(query.url.as_str());
+
+    Html(format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{url}</title>\
+         <style>body{{margin:0;font-family:sans-serif;display:flex;flex-direction:column;height:100vh}}\
+         header{{padding:.5em 1em;border-bottom:1px solid #ddd}}main{{flex:1;display:flex}}\
+         section{{flex:1;display:flex;flex-direction:column}}h2{{margin:.3em 1em;font-size:1em}}\
+         iframe{{flex:1;border:0;border-left:1px solid #ddd}}</style></head>\
+         <body><header><a href=\"/\">← index</a> {url}</header><main>\
+         <section><h2>Raw</h2><iframe src=\"/raw?url={encoded}\"></iframe></section>\
+         <section><h2>Cleaned</h2><iframe src=\"/clean?url={encoded}\"></iframe></section>\
+         </main></body></html>"
+    ))
+}
+
+pub async fn run(args: Args) -> Result<()> {
+    debug!(?args);
+
+    let mut workspace = args.workspace.build_read_only().await?;
+    // only cached pages are read, writers may update them while the server runs
+    workspace.unlock();
+
+    let app = Router::new()
+        .route("/", get(index))
+        .route("/raw", get(raw))
+        .route("/clean", get(clean))
+        .route("/compare", get(compare))
+        .with_state(workspace);
+
+    serve(app, ([127, 0, 0, 1], args.port)).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(
+            escape_html(r#"<a href="x">&'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&#39;&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,115 @@
+//! Query command implementation
+
+use std::io::{self, Write};
+
+use clap::Parser;
+use colored::Colorize;
+use miette::Result;
+use tracing::{debug, info};
+use url::Url;
+
+use super::error::{Error, OutputError};
+use super::output::{OutputFormat, write_json};
+use crate::pretty::ToPrettyString;
+use crate::sites::rent591::{ListingFilter, SortKey};
+use crate::url::UrlExt;
+use crate::workspace::WorkspaceArgs;
+
+/// Filter and sort stored listings
+///
+/// Listings combine the latest list page summary with the scraped item page, if any.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Only query the latest snapshot of this rent.591.com.tw list [default: all lists]
+    pub url: Option<Url>,
+
+    #[clap(flatten)]
+    pub filter: ListingFilter,
+
+    /// Field to sort by
+    #[arg(long, short, value_enum, default_value_t = Default::default())]
+    pub sort: SortKey,
+
+    /// Point of interest to sort by with `--sort distance` or `--sort commute`
+    /// [default: the nearest or quickest]
+    #[arg(long, value_name = "NAME")]
+    pub poi: Option<String>,
+
+    /// Sort in descending order
+    #[arg(long)]
+    pub desc: bool,
+
+    /// Maximum number of listings to show
+    #[arg(long, short)]
+    pub limit: Option<usize>,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = Default::default())]
+    pub format: OutputFormat,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+pub async fn run(mut args: Args) -> Result<()> {
+    if let Some(url) = &mut args.url {
+        url.normalize();
+    }
+
+    debug!(?args);
+
+    let workspace = args.workspace.build_read_only().await?;
+
+    if let Some(url) = &args.url {
+        miette::ensure!(workspace.list_exists(url).await?, Error::NoRentList);
+    }
+
+    let mut listings = workspace
+        .select_listings(args.url.as_ref(), args.filter.posted_after)
+        .await?;
+
+    let total = listings.len();
+
+    listings.retain(|listing| args.filter.matches(listing));
+    args.sort
+        .sort(&mut listings, args.desc, args.poi.as_deref());
+
+    if let Some(limit) = args.limit {
+        listings.truncate(limit);
+    }
+
+    info!(total, count = listings.len(), "query listings");
+
+    let mut stdout = io::stdout().lock();
+
+    match args.format {
+        OutputFormat::Table => {
+            let summary = match listings.len() {
+                0 => "No listings found".red(),
+                1 => "Found 1 listing".bright_green(),
+                n => format!("Found {n} listings").bright_green(),
+            };
+            writeln!(stdout, "{}\n{summary}", listings.to_pretty_string())
+                .map_err(OutputError::from)?;
+        }
+        OutputFormat::Text => {
+            for listing in &listings {
+                writeln!(
+                    stdout,
+                    "{}\t{}\t{}\t{}",
+                    listing.url.as_str(),
+                    listing.title.as_deref().unwrap_or_default(),
+                    listing
+                        .price
+                        .map(|price| price.to_string())
+                        .unwrap_or_default(),
+                    listing.district.as_deref().unwrap_or_default()
+                )
+                .map_err(OutputError::from)?;
+            }
+        }
+        OutputFormat::Json => write_json(&mut stdout, &listings)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! Search command implementation
+
+use std::io::{self, Write};
+
+use clap::Parser;
+use colored::Colorize;
+use comfy_table::{Cell, ContentArrangement, Table, presets};
+use miette::Result;
+use serde_json::json;
+use tracing::{debug, info};
+
+use super::error::{Error, OutputError};
+use super::output::{OutputFormat, write_json};
+use crate::sites::rent591::{SearchHit, SearchQuery};
+use crate::workspace::WorkspaceArgs;
+
+/// Full-text search the title, labels and description of scraped items
+///
+/// Terms separated by spaces must all match. Terms of three or more characters use the
+/// full-text index and are ranked, shorter ones are matched as plain substrings.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Search terms (e.g., "可養寵物 近捷運")
+    pub query: String,
+
+    /// Maximum number of results
+    #[arg(long, short, default_value_t = 20)]
+    pub limit: u32,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = Default::default())]
+    pub format: OutputFormat,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+fn format_hits(hits: &[SearchHit]) -> String {
+    let mut table = Table::new();
+
+    table
+        .load_preset(presets::UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("URL".bold().dimmed()),
+            Cell::new("Title".bold().dimmed()),
+            Cell::new("Snippet".bold().dimmed()),
+        ]);
+
+    for hit in hits {
+        let snippet: String = hit
+            .fragments()
+            .map(|(fragment, matched)| {
+                if matched {
+                    fragment.bold().yellow().to_string()
+                } else {
+                    fragment.replace('\n', " ")
+                }
+            })
+            .collect();
+
+        table.add_row(vec![
+            Cell::new(hit.url.as_str().bright_blue()),
+            Cell::new(hit.title.as_deref().unwrap_or_default().white()),
+            Cell::new(snippet),
+        ]);
+    }
+
+    let summary = match hits.len() {
+        0 => "No items found".red(),
+        1 => "Found 1 item".bright_green(),
+        n => format!("Found {n} items").bright_green(),
+    };
+
+    format!("{table}\n{summary}")
+}
+
+pub async fn run(args: Args) -> Result<()> {
+    debug!(?args);
+
+    let query = SearchQuery::parse(&args.query);
+
+    miette::ensure!(!query.is_empty(), Error::EmptySearch);
+
+    let workspace = args.workspace.build_read_only().await?;
+
+    let hits = workspace.search_items(&query, args.limit).await?;
+
+    info!(count = hits.len(), "search items");
+
+    let mut stdout = io::stdout().lock();
+
+    match args.format {
+        OutputFormat::Table => {
+            writeln!(stdout, "{}", format_hits(&hits)).map_err(OutputError::from)?;
+        }
+        OutputFormat::Text => {
+            for hit in &hits {
+                writeln!(
+                    stdout,
+                    "{}\t{}\t{}",
+                    hit.url.as_str(),
+                    hit.title.as_deref().unwrap_or_default(),
+                    hit.snippet_with("", "")
+                )
+                .map_err(OutputError::from)?;
+            }
+        }
+        OutputFormat::Json => {
+            let values: Vec<_> = hits
+                .iter()
+                .map(|hit| {
+                    json!({
+                        "url": hit.url.as_str(),
+                        "title": hit.title,
+                        "snippet": hit.snippet_with("<mark>", "</mark>"),
+                        "rank": hit.rank,
+                    })
+                })
+                .collect();
+            write_json(&mut stdout, &values)?;
+        }
+    }
+
+    Ok(())
+}
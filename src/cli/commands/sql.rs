@@ -0,0 +1,360 @@
+//! SQL command implementation
+
+use std::io::{self, BufRead, Write};
+
+use clap::{Parser, ValueEnum};
+use colored::Colorize;
+use comfy_table::{Cell, ContentArrangement, Table, presets};
+use miette::{Report, Result};
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Column, Row, SqlitePool, TypeInfo, ValueRef};
+use tracing::debug;
+
+use super::error::{Error, OutputError};
+use crate::workspace::{Workspace, WorkspaceArgs, WorkspaceError};
+
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum SqlFormat {
+    /// A table for reading in the terminal
+    #[default]
+    Table,
+    /// A JSON array of objects, one per row
+    Json,
+    /// Comma-separated values with a header row
+    Csv,
+}
+
+/// Run read-only SQL against the workspace database
+///
+/// Without a query, starts an interactive console reading statements terminated by `;`.
+/// JSON-encoded columns such as URLs and label lists are decoded in the output.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// SQL query to run (e.g., "SELECT url, title FROM rent_item LIMIT 5")
+    pub query: Option<String>,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = Default::default())]
+    pub format: SqlFormat,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+/// Result rows of a query with their column names
+#[derive(Debug, Default)]
+struct QueryResult {
+    columns: Vec<String>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// Decode a text value, unwrapping JSON strings, arrays and objects stored by `Json<T>`
+fn decode_text(text: String) -> Value {
+    if text.starts_with(['"', '[', '{'])
+        && let Ok(value) = serde_json::from_str(&text)
+    {
+        return value;
+    }
+
+    Value::String(text)
+}
+
+fn decode_value(row: &SqliteRow, index: usize) -> Value {
+    let Ok(raw) = row.try_get_raw(index) else {
+        return Value::Null;
+    };
+
+    if raw.is_null() {
+        return Value::Null;
+    }
+
+    match raw.type_info().name() {
+        "INTEGER" => row
+            .try_get::<i64, _>(index)
+            .map_or(Value::Null, Value::from),
+        "REAL" => row
+            .try_get::<f64, _>(index)
+            .map_or(Value::Null, Value::from),
+        "BLOB" => row
+            .try_get::<Vec<u8>, _>(index)
+            .map_or(Value::Null, |blob| format!("<{} bytes>", blob.len()).into()),
+        _ => row
+            .try_get::<String, _>(index)
+            .map_or(Value::Null, decode_text),
+    }
+}
+
+async fn execute(pool: &SqlitePool, query: &str) -> Result<QueryResult, Error> {
+    let rows = sqlx::query(query)
+        .fetch_all(pool)
+        .await
+        .map_err(Error::Sql)?;
+
+    let Some(first) = rows.first() else {
+        return Ok(QueryResult::default());
+    };
+
+    let columns = first
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect();
+
+    let rows = rows
+        .iter()
+        .map(|row| (0..row.len()).map(|i| decode_value(row, i)).collect())
+        .collect();
+
+    Ok(QueryResult { columns, rows })
+}
+
+/// Render a value as plain text, joining lists with `; `
+fn to_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(values) => values.iter().map(to_field).collect::<Vec<_>>().join("; "),
+        value => value.to_string(),
+    }
+}
+
+fn format_table(result: &QueryResult) -> String {
+    let mut table = Table::new();
+
+    table
+        .load_preset(presets::UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(
+            result
+                .columns
+                .iter()
+                .map(|column| Cell::new(column.bold().dimmed())),
+        );
+
+    for row in &result.rows {
+        table.add_row(row.iter().map(|value| match value {
+            Value::Null => Cell::new("NULL".dimmed()),
+            Value::Number(n) => Cell::new(n.to_string().bright_cyan()),
+            value => Cell::new(to_field(value)),
+        }));
+    }
+
+    let summary = match result.rows.len() {
+        1 => "1 row".bright_green(),
+        n => format!("{n} rows").bright_green(),
+    };
+
+    if result.columns.is_empty() {
+        summary.to_string()
+    } else {
+        format!("{table}\n{summary}")
+    }
+}
+
+fn write_result<W>(
+    mut writer: W,
+    result: &QueryResult,
+    format: SqlFormat,
+) -> Result<(), OutputError>
+where
+    W: Write,
+{
+    match format {
+        SqlFormat::Table => writeln!(writer, "{}", format_table(result))?,
+        SqlFormat::Json => {
+            let objects: Vec<_> = result
+                .rows
+                .iter()
+                .map(|row| {
+                    let map: Map<_, _> = result.columns.iter().cloned().zip(row.clone()).collect();
+                    Value::Object(map)
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut writer, &objects)?;
+            writeln!(writer)?;
+        }
+        SqlFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(&mut writer);
+            writer.write_record(&result.columns)?;
+            for row in &result.rows {
+                writer.write_record(row.iter().map(to_field))?;
+            }
+            writer.flush()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Byte offsets of the `;` ending each statement, skipping those inside quoted strings or
+/// identifiers and comments, like `sqlite3_complete`
+fn statement_ends(sql: &str) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut chars = sql.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            ';' => ends.push(i),
+            // a doubled quote inside a string is an escaped quote, read as closing and
+            // reopening it
+            '\'' | '"' | '`' | '[' => {
+                let close = if c == '[' { ']' } else { c };
+                for (_, c) in chars.by_ref() {
+                    if c == close {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.next_if(|&(_, c)| c == '-').is_some() => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.next_if(|&(_, c)| c == '*').is_some() => {
+                while let Some((_, c)) = chars.next() {
+                    if c == '*' && chars.next_if(|&(_, c)| c == '/').is_some() {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ends
+}
+
+/// Split complete `;`-terminated statements off the buffer, leaving any partial one
+fn take_statements(buffer: &mut String) -> Vec<String> {
+    let ends = statement_ends(buffer);
+    let Some(&end) = ends.last() else {
+        return Vec::new();
+    };
+
+    let rest = buffer.split_off(end + 1);
+    let complete = std::mem::replace(buffer, rest);
+
+    let mut start = 0;
+    ends.into_iter()
+        .map(|end| {
+            let statement = complete[start..end].trim().to_string();
+            start = end + 1;
+            statement
+        })
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+async fn repl(pool: &SqlitePool, format: SqlFormat) -> Result<()> {
+    println!(
+        "{}",
+        "Read-only SQL console, end statements with `;`. Type .tables to list tables, .quit to exit."
+            .dimmed()
+    );
+
+    let mut stdin = io::stdin().lock();
+    let mut buffer = String::new();
+
+    loop {
+        let prompt = if buffer.trim().is_empty() {
+            "rentmap> "
+        } else {
+            "    ...> "
+        };
+        print!("{prompt}");
+        io::stdout().flush().map_err(OutputError::from)?;
+
+        let mut line = String::new();
+        if stdin.read_line(&mut line).map_err(Error::Stdin)? == 0 {
+            println!();
+            return Ok(());
+        }
+
+        let statements = match line.trim() {
+            ".quit" | ".exit" => return Ok(()),
+            ".tables" if buffer.trim().is_empty() => vec![
+                "SELECT name, type FROM sqlite_schema WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' AND name NOT LIKE '%_fts_%' ORDER BY type, name"
+                    .to_string(),
+            ],
+            _ => {
+                buffer.push_str(&line);
+                take_statements(&mut buffer)
+            }
+        };
+
+        for statement in statements {
+            match execute(pool, &statement).await {
+                Ok(result) => write_result(io::stdout().lock(), &result, format)?,
+                Err(err) => eprintln!("{:?}", Report::new(err)),
+            }
+        }
+    }
+}
+
+pub async fn run(args: Args) -> Result<()> {
+    debug!(?args);
+
+    miette::ensure!(
+        args.workspace.database_url.is_none(),
+        WorkspaceError::SqliteOnly("sql")
+    );
+
+    let workspace = Workspace::new(args.workspace.workspace);
+
+    let path = workspace.database_path();
+    miette::ensure!(path.exists(), Error::NoWorkspace(path));
+
+    // read-only and without taking the workspace lock, so it can run alongside scraping
+    let pool = workspace.read_only_pool();
+
+    match &args.query {
+        Some(query) => {
+            let result = execute(&pool, query).await?;
+            write_result(io::stdout().lock(), &result, args.format)?;
+        }
+        None => repl(&pool, args.format).await?,
+    }
+
+    pool.close().await;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(
+            decode_text(r#""https://rent.591.com.tw/1""#.to_string()),
+            Value::from("https://rent.591.com.tw/1")
+        );
+        assert_eq!(
+            decode_text(r#"["近捷運"]"#.to_string()),
+            Value::from(vec!["近捷運"])
+        );
+        assert_eq!(decode_text("[broken".to_string()), Value::from("[broken"));
+    }
+
+    #[test]
+    fn test_take_statements() {
+        let mut buffer = "SELECT 1; SELECT\n2;\nSELECT".to_string();
+        assert_eq!(take_statements(&mut buffer), ["SELECT 1", "SELECT\n2"]);
+        assert_eq!(buffer, "\nSELECT");
+        assert!(take_statements(&mut buffer).is_empty());
+
+        let mut buffer =
+            "SELECT * FROM t WHERE title LIKE '%;%' AND \"a;b\" = 'it''s;'; -- done;\nSELECT 'x;"
+                .to_string();
+        assert_eq!(
+            take_statements(&mut buffer),
+            ["SELECT * FROM t WHERE title LIKE '%;%' AND \"a;b\" = 'it''s;'"]
+        );
+        assert_eq!(buffer, " -- done;\nSELECT 'x;");
+        assert!(take_statements(&mut buffer).is_empty());
+    }
+}
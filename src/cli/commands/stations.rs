@@ -0,0 +1,138 @@
+//! Stations command implementation
+
+use std::fs::File;
+use std::path::PathBuf;
+
+use clap::Parser;
+use colored::Colorize;
+use comfy_table::{Cell, ContentArrangement, Table, presets};
+use miette::Result;
+use tracing::{debug, info};
+use url::Url;
+
+use super::error::Error;
+use super::located::{Located, no_location_note};
+use crate::file::PathError;
+use crate::geocode::{NearbyStation, Station, nearest_stations};
+use crate::url::UrlExt;
+use crate::workspace::WorkspaceArgs;
+
+/// Find the nearest MRT and light rail stations of every geocoded listing
+///
+/// For each listing located by `rentmap geocode-items`, the nearest station of each line
+/// within `--max-walk` is saved with its estimated walking distance, a quarter longer than
+/// the straight line at 80 m per minute. They are used by `rentmap query --max-walk` and
+/// `--sort station` and the `stations` export column.
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Target URL for rent.591.com.tw search results
+    pub url: Url,
+
+    /// Station CSV with `code,line,name,name_en,lat,lng` columns
+    /// [default: bundled Taipei Metro, Taoyuan Airport MRT and light rail stations]
+    #[arg(long)]
+    pub stations: Option<PathBuf>,
+
+    /// Maximum walking meters to a station
+    #[arg(long, default_value_t = 1500)]
+    pub max_walk: u32,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+fn load_stations(path: Option<&PathBuf>) -> Result<Vec<Station>> {
+    let Some(path) = path else {
+        return Ok(Station::bundled());
+    };
+
+    let file = File::open(path).map_err(|source| PathError::new(path, source))?;
+
+    let stations = Station::load(file).map_err(|source| Error::InvalidStations {
+        path: path.clone(),
+        source,
+    })?;
+
+    Ok(stations)
+}
+
+fn format_results(
+    results: &[(Url, Vec<NearbyStation>)],
+    none_nearby: usize,
+    no_location: usize,
+) -> String {
+    let mut table = Table::new();
+
+    table
+        .load_preset(presets::UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(["URL", "Stations"].map(|header| Cell::new(header.bold().dimmed())));
+
+    for (url, stations) in results {
+        let stations: Vec<_> = stations.iter().map(ToString::to_string).collect();
+
+        table.add_row(vec![
+            Cell::new(url.as_str().bright_blue()),
+            Cell::new(stations.join("\n").bright_cyan()),
+        ]);
+    }
+
+    let title = "Nearby Stations:".bold().underline();
+
+    let mut summary = format!("Found stations near {} listings", results.len())
+        .bright_green()
+        .to_string();
+
+    if none_nearby > 0 {
+        summary += &format!(", {none_nearby} with none in walking distance")
+            .yellow()
+            .to_string();
+    }
+
+    let summary = no_location_note(summary, no_location);
+
+    format!("{title}\n{table}\n{summary}")
+}
+
+pub async fn run(mut args: Args) -> Result<()> {
+    args.url.normalize();
+
+    debug!(?args);
+
+    let stations = load_stations(args.stations.as_ref())?;
+
+    let workspace = args.workspace.build().await?;
+
+    miette::ensure!(workspace.list_exists(&args.url).await?, Error::NoRentList);
+
+    let located = Located::select(&workspace, &args.url).await?;
+
+    let mut none_nearby = 0;
+    let mut results = Vec::new();
+
+    for (url, location) in located.listings {
+        let nearby = nearest_stations(&stations, location, args.max_walk);
+        workspace.save_item_stations(&url, &nearby).await?;
+
+        if nearby.is_empty() {
+            none_nearby += 1;
+            continue;
+        }
+
+        results.push((url, nearby));
+    }
+
+    info!(
+        found = results.len(),
+        none_nearby,
+        no_location = located.no_location,
+        "find nearby stations"
+    );
+
+    println!(
+        "\n{}",
+        format_results(&results, none_nearby, located.no_location)
+    );
+
+    Ok(())
+}
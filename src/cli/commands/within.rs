@@ -0,0 +1,224 @@
+//! Within command implementation
+
+use std::cmp::Ordering;
+use std::fs;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::{ArgGroup, Parser};
+use colored::Colorize;
+use comfy_table::{Cell, ContentArrangement, Table, presets};
+use miette::Result;
+use serde::Serialize;
+use tracing::{debug, info};
+use url::Url;
+
+use super::error::{Error, OutputError};
+use super::output::{OutputFormat, write_json};
+use crate::file::PathError;
+use crate::geocode::{MultiPolygon, Radius};
+use crate::sites::rent591::Listing;
+use crate::url::UrlExt;
+use crate::workspace::WorkspaceArgs;
+
+/// Find geocoded listings inside a circle or a GeoJSON area
+///
+/// Candidates are read with a bounding box range over the geocoded coordinates, then tested
+/// exactly: great-circle distance for `--within-radius`, point in polygon for
+/// `--within-polygon`, where holes are excluded and any polygon of a MultiPolygon or
+/// FeatureCollection matches. Given both, listings must be in both.
+#[derive(Debug, Parser)]
+#[command(group(
+    ArgGroup::new("area")
+        .required(true)
+        .multiple(true)
+        .args(["within_radius", "within_polygon"])
+))]
+pub struct Args {
+    /// Only search the latest snapshot of this rent.591.com.tw list [default: all lists]
+    pub url: Option<Url>,
+
+    /// Circle of KM kilometers around a coordinate (e.g., 25.033,121.565,2)
+    #[arg(long, value_name = "LAT,LNG,KM")]
+    pub within_radius: Option<Radius>,
+
+    /// GeoJSON file of a Polygon or MultiPolygon, or Features of them
+    #[arg(long, value_name = "FILE")]
+    pub within_polygon: Option<PathBuf>,
+
+    /// Maximum number of listings to show
+    #[arg(long, short)]
+    pub limit: Option<usize>,
+
+    /// Output format
+    #[arg(long, short, value_enum, default_value_t = Default::default())]
+    pub format: OutputFormat,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+/// A listing inside the searched area
+#[derive(Debug, Serialize)]
+struct WithinListing {
+    #[serde(flatten)]
+    listing: Listing,
+    lat: f64,
+    lng: f64,
+    /// Kilometers from the center of `--within-radius`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    distance_km: Option<f64>,
+}
+
+fn load_polygon(path: &PathBuf) -> Result<MultiPolygon> {
+    let text = fs::read_to_string(path).map_err(|source| PathError::new(path, source))?;
+
+    Ok(MultiPolygon::from_geojson(&text)?)
+}
+
+fn format_results(results: &[WithinListing]) -> String {
+    let mut table = Table::new();
+
+    table
+        .load_preset(presets::UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(
+            ["URL", "Title", "Price", "District", "Location", "Distance"]
+                .map(|header| Cell::new(header.bold().dimmed())),
+        );
+
+    for result in results {
+        let listing = &result.listing;
+
+        table.add_row(vec![
+            Cell::new(listing.url.as_str().bright_blue()),
+            Cell::new(listing.title.as_deref().unwrap_or_default().white()),
+            Cell::new(
+                listing
+                    .price
+                    .map(|price| price.to_string())
+                    .unwrap_or_default()
+                    .bright_cyan(),
+            ),
+            Cell::new(listing.district.as_deref().unwrap_or_default()),
+            Cell::new(format!("{:.6},{:.6}", result.lat, result.lng).dimmed()),
+            Cell::new(
+                result
+                    .distance_km
+                    .map(|km| format!("{km:.2} km"))
+                    .unwrap_or_default()
+                    .bright_cyan(),
+            ),
+        ]);
+    }
+
+    let summary = match results.len() {
+        0 => "No listings found".red(),
+        1 => "Found 1 listing".bright_green(),
+        n => format!("Found {n} listings").bright_green(),
+    };
+
+    format!("{table}\n{summary}")
+}
+
+pub async fn run(mut args: Args) -> Result<()> {
+    if let Some(url) = &mut args.url {
+        url.normalize();
+    }
+
+    debug!(?args);
+
+    let polygon = args.within_polygon.as_ref().map(load_polygon).transpose()?;
+
+    let bounds = match (
+        args.within_radius.map(|radius| radius.bounding_box()),
+        polygon.as_ref().map(MultiPolygon::bounding_box),
+    ) {
+        (Some(circle), Some(area)) => circle.intersect(&area),
+        (circle, area) => circle.or(area),
+    };
+
+    let workspace = args.workspace.build_read_only().await?;
+
+    if let Some(url) = &args.url {
+        miette::ensure!(workspace.list_exists(url).await?, Error::NoRentList);
+    }
+
+    let mut locations = match &bounds {
+        Some(bounds) => {
+            workspace
+                .select_item_locations(args.url.as_ref(), Some(bounds))
+                .await?
+        }
+        // the circle and the polygons do not overlap
+        None => Default::default(),
+    };
+
+    locations.retain(|_, &mut location| {
+        args.within_radius
+            .is_none_or(|radius| radius.contains(location))
+            && polygon
+                .as_ref()
+                .is_none_or(|polygon| polygon.contains(location))
+    });
+
+    let listings = workspace.select_listings(args.url.as_ref(), None).await?;
+    let total = listings.len();
+
+    let mut results: Vec<WithinListing> = listings
+        .into_iter()
+        .filter_map(|listing| {
+            let (lat, lng) = locations.get(&listing.url).copied()?;
+
+            Some(WithinListing {
+                distance_km: args
+                    .within_radius
+                    .map(|radius| radius.distance_km((lat, lng))),
+                listing,
+                lat,
+                lng,
+            })
+        })
+        .collect();
+
+    // nearest to the center first, if any
+    results.sort_by(|a, b| {
+        a.distance_km
+            .partial_cmp(&b.distance_km)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| a.listing.url.cmp(&b.listing.url))
+    });
+
+    if let Some(limit) = args.limit {
+        results.truncate(limit);
+    }
+
+    info!(total, count = results.len(), "find listings within area");
+
+    let mut stdout = io::stdout().lock();
+
+    match args.format {
+        OutputFormat::Table => {
+            writeln!(stdout, "{}", format_results(&results)).map_err(OutputError::from)?;
+        }
+        OutputFormat::Text => {
+            for result in &results {
+                writeln!(
+                    stdout,
+                    "{}\t{}\t{}\t{}",
+                    result.listing.url.as_str(),
+                    result.lat,
+                    result.lng,
+                    result
+                        .distance_km
+                        .map(|km| format!("{km:.3}"))
+                        .unwrap_or_default()
+                )
+                .map_err(OutputError::from)?;
+            }
+        }
+        OutputFormat::Json => write_json(&mut stdout, &results)?,
+    }
+
+    Ok(())
+}
@@ -0,0 +1,277 @@
+//! Workspace command implementation
+
+use std::path::PathBuf;
+
+use clap::{Args as ClapArgs, Parser, Subcommand};
+use colored::Colorize;
+use comfy_table::{Cell, ContentArrangement, Table, presets};
+use miette::Result;
+use tracing::debug;
+
+use super::error::Error;
+use crate::pretty::format_size;
+use crate::workspace::{
+    LockMode, MergeReport, PrunePolicy, PruneReport, Workspace, WorkspaceArgs, WorkspaceStats,
+};
+
+/// Inspect and maintain the workspace database
+#[derive(Debug, Parser)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Show row counts, cache size and list snapshots
+    Stats(StatsArgs),
+    /// Remove old snapshots, cached pages and orphan items, then reclaim space
+    Prune(PruneArgs),
+    /// Merge other workspaces into this one, newest `created_at` wins
+    Merge(MergeArgs),
+}
+
+#[derive(Debug, ClapArgs)]
+pub struct StatsArgs {
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+#[derive(Debug, ClapArgs)]
+pub struct PruneArgs {
+    /// Keep only the newest N snapshots of each list
+    #[arg(long, value_name = "N")]
+    pub keep_snapshots: Option<u32>,
+
+    /// Drop cached pages older than this many days
+    #[arg(long, value_name = "DAYS")]
+    pub max_cache_days: Option<u32>,
+
+    /// Drop items not referenced by any list snapshot
+    #[arg(long)]
+    pub orphan_items: bool,
+
+    /// Show what would be removed without removing anything
+    #[arg(long)]
+    pub dry_run: bool,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+#[derive(Debug, ClapArgs)]
+pub struct MergeArgs {
+    /// Root directories of the workspaces to merge from
+    #[arg(required = true)]
+    pub sources: Vec<PathBuf>,
+
+    #[clap(flatten)]
+    pub workspace: WorkspaceArgs,
+}
+
+impl From<&PruneArgs> for PrunePolicy {
+    fn from(args: &PruneArgs) -> Self {
+        Self {
+            keep_snapshots: args.keep_snapshots,
+            max_cache_days: args.max_cache_days,
+            orphan_items: args.orphan_items,
+        }
+    }
+}
+
+fn format_stats(stats: &WorkspaceStats) -> String {
+    let mut counts = Table::new();
+
+    counts
+        .load_preset(presets::NOTHING)
+        .set_content_arrangement(ContentArrangement::Disabled);
+
+    for (name, value) in [
+        ("List snapshots", stats.list_snapshots.to_string()),
+        ("Item summaries", stats.item_summaries.to_string()),
+        ("Items", stats.items.to_string()),
+        ("Cached pages", stats.cached_pages.to_string()),
+        ("Cache size", format_size(stats.cache_size)),
+        ("Cookies", stats.cookies.to_string()),
+        ("Database size", format_size(stats.database_size)),
+    ] {
+        counts.add_row(vec![
+            Cell::new(name.dimmed()),
+            Cell::new(value.bright_cyan()),
+        ]);
+    }
+
+    let mut lists = Table::new();
+
+    lists
+        .load_preset(presets::UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec![
+            Cell::new("List URL".bold().dimmed()),
+            Cell::new("Snapshots".bold().dimmed()),
+            Cell::new("Oldest".bold().dimmed()),
+            Cell::new("Newest".bold().dimmed()),
+        ]);
+
+    for list in &stats.lists {
+        lists.add_row(vec![
+            Cell::new(list.url.as_str().bright_blue()),
+            Cell::new(list.count.to_string().bright_cyan()),
+            Cell::new(&list.oldest),
+            Cell::new(&list.newest),
+        ]);
+    }
+
+    format!(
+        "{}\n{counts}\n\n{}\n{lists}",
+        "Workspace:".bold().underline(),
+        "Lists:".bold().underline()
+    )
+}
+
+fn format_report(report: &PruneReport, dry_run: bool) -> String {
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+
+    let lines = [
+        (report.list_snapshots, "list snapshots"),
+        (report.item_summaries, "item summaries"),
+        (report.items, "items"),
+        (report.cached_pages, "cached pages"),
+    ]
+    .map(|(count, name)| format!("{verb} {} {name}", count.to_string().bright_cyan()));
+
+    lines.join("\n")
+}
+
+fn format_merge_reports(reports: &[(PathBuf, MergeReport)]) -> String {
+    let mut table = Table::new();
+
+    table
+        .load_preset(presets::UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(
+            [
+                "Source",
+                "List snapshots",
+                "Item summaries",
+                "Items",
+                "Cached pages",
+                "Geocodes",
+            ]
+            .map(|header| Cell::new(header.bold().dimmed())),
+        );
+
+    let mut total = MergeReport::default();
+
+    for (source, report) in reports {
+        total.list_snapshots += report.list_snapshots;
+        total.item_summaries += report.item_summaries;
+        total.items += report.items;
+        total.cached_pages += report.cached_pages;
+        total.geocodes += report.geocodes;
+
+        table.add_row(merge_row(source.display().to_string(), report));
+    }
+
+    if reports.len() > 1 {
+        table.add_row(merge_row("Total".bold().to_string(), &total));
+    }
+
+    table.to_string()
+}
+
+fn merge_row(source: String, report: &MergeReport) -> Vec<Cell> {
+    let mut row = vec![Cell::new(source)];
+    row.extend(
+        [
+            report.list_snapshots,
+            report.item_summaries,
+            report.items,
+            report.cached_pages,
+            report.geocodes,
+        ]
+        .map(|count| Cell::new(count.to_string().bright_cyan())),
+    );
+    row
+}
+
+async fn stats(args: StatsArgs) -> Result<()> {
+    let workspace = args.workspace.build_read_only().await?;
+
+    let stats = workspace.stats().await?;
+
+    println!("\n{}", format_stats(&stats));
+
+    Ok(())
+}
+
+async fn prune(args: PruneArgs) -> Result<()> {
+    let policy = PrunePolicy::from(&args);
+
+    if policy.keep_snapshots.is_none() && policy.max_cache_days.is_none() && !policy.orphan_items {
+        println!(
+            "{}",
+            "Nothing to prune, give --keep-snapshots, --max-cache-days or --orphan-items".yellow()
+        );
+        return Ok(());
+    }
+
+    let workspace = args.workspace.build().await?;
+
+    let report = workspace.prune(policy, args.dry_run).await?;
+
+    println!("{}", format_report(&report, args.dry_run));
+
+    Ok(())
+}
+
+async fn merge(args: MergeArgs) -> Result<()> {
+    let wait = args.workspace.wait;
+    let workspace = args.workspace.build().await?;
+
+    let mut reports = Vec::new();
+
+    for source in args.sources {
+        let mut other = Workspace::new(source.clone());
+
+        let path = other.database_path();
+        miette::ensure!(path.exists(), Error::NoWorkspace(path));
+
+        // bring the source up to date and keep it locked while merging from it
+        other.init(LockMode::Exclusive, wait).await?;
+        other.close().await;
+
+        let report = workspace.merge_database(&path).await?;
+        reports.push((source, report));
+    }
+
+    println!("{}", format_merge_reports(&reports));
+
+    Ok(())
+}
+
+pub async fn run(args: Args) -> Result<()> {
+    debug!(?args);
+
+    match args.command {
+        Command::Stats(args) => stats(args).await,
+        Command::Prune(args) => prune(args).await,
+        Command::Merge(args) => merge(args).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_report_dry_run() {
+        colored::control::set_override(false);
+        let report = PruneReport {
+            list_snapshots: 2,
+            ..Default::default()
+        };
+        let text = format_report(&report, true);
+        assert!(text.starts_with("Would remove 2 list snapshots\n"));
+    }
+}
@@ -0,0 +1,9 @@
+use serde::Deserialize;
+
+use crate::web::SpiderChromeArgs;
+
+/// Fetcher configuration, loaded from the `[fetcher]` section of `rentmap.toml`
+#[derive(Debug, Deserialize)]
+pub struct FetcherConfig {
+    pub spider_chrome: Option<SpiderChromeArgs>,
+}
@@ -1,10 +1,22 @@
 use clap::Args;
 use google_maps::prelude::*;
 use serde::Deserialize;
+use url::Url;
 
-#[derive(Debug, Deserialize, Args)]
+use crate::geocode::GeocoderType;
+
+#[derive(Debug, Default, Deserialize, Args)]
 #[command(next_help_heading = "Geocoding")]
 pub struct GeocodingConfig {
+    /// Geocoding service to use [default: google]
+    #[arg(long, value_enum)]
+    pub provider: Option<GeocoderType>,
+
+    /// Base URL of the Nominatim-compatible server for `--provider nominatim`
+    /// [default: https://nominatim.openstreetmap.org]
+    #[arg(long, env = "RENTMAP_NOMINATIM_URL")]
+    pub nominatim_url: Option<Url>,
+
     /// Language for geocoding results
     ///
     /// See: https://developers.google.com/maps/faq#languagesupport
@@ -17,3 +29,14 @@ pub struct GeocodingConfig {
     #[arg(short, long)]
     pub region: Option<Region>,
 }
+
+impl GeocodingConfig {
+    /// Fill unset options from the `[geocoding]` section of `rentmap.toml`
+    pub fn merge(mut self, config: GeocodingConfig) -> Self {
+        self.provider = self.provider.or(config.provider);
+        self.nominatim_url = self.nominatim_url.or(config.nominatim_url);
+        self.language = self.language.or(config.language);
+        self.region = self.region.or(config.region);
+        self
+    }
+}
@@ -1,5 +1,7 @@
 pub mod error;
+pub mod fetcher;
 pub mod geocoding;
 pub mod google;
 pub mod model;
 pub mod ocr;
+pub mod poi;
@@ -4,9 +4,11 @@ use std::path::{Path, PathBuf};
 use miette::IntoDiagnostic;
 use serde::Deserialize;
 
+use crate::config::fetcher::FetcherConfig;
 use crate::config::geocoding::GeocodingConfig;
 use crate::config::google::GoogleConfig;
 use crate::config::ocr::OcrConfig;
+use crate::config::poi::PoiConfig;
 use crate::error::TraceReport;
 use crate::file::load_toml;
 
@@ -18,6 +20,11 @@ pub struct Config {
     pub geocoding: Option<GeocodingConfig>,
 
     pub ocr: Option<OcrConfig>,
+
+    pub fetcher: Option<FetcherConfig>,
+
+    /// Points of interest from `[[poi]]` tables
+    pub poi: Option<Vec<PoiConfig>>,
 }
 
 pub fn find_config<P>(file_name: P) -> Option<PathBuf>
@@ -0,0 +1,22 @@
+use serde::Deserialize;
+
+/// A `[[poi]]` entry of `rentmap.toml`, located by coordinates or by an address to geocode
+#[derive(Clone, Debug, Deserialize)]
+pub struct PoiConfig {
+    /// Name used in filters, sort keys and exports (e.g., office)
+    pub name: String,
+
+    pub lat: Option<f64>,
+
+    pub lng: Option<f64>,
+
+    /// Address geocoded when `lat` and `lng` are not both given
+    pub address: Option<String>,
+}
+
+impl PoiConfig {
+    /// The configured coordinates, if both are given
+    pub fn location(&self) -> Option<(f64, f64)> {
+        self.lat.zip(self.lng)
+    }
+}
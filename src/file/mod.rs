@@ -1,7 +1,12 @@
 mod error;
 mod ops;
+#[cfg(test)]
+mod temp;
 
 pub use error::{FileError, PathError};
 pub use ops::{
-    exists_and_non_empty, load_image, load_json, load_toml, make_directory, save_html, save_json,
+    exists_and_non_empty, load_image, load_json, load_text, load_toml, make_directory, save_html,
+    save_json,
 };
+#[cfg(test)]
+pub use temp::TempDir;
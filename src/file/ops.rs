@@ -46,6 +46,16 @@ where
     Ok(value)
 }
 
+pub fn load_text<P>(path: P) -> Result<String, FileError>
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let content = fs::read_to_string(path).map_err(|source| PathError::new(path, source))?;
+    info!(path = %path.display(), length = content.len(), "load text file");
+    Ok(content)
+}
+
 pub fn load_toml<P, T>(path: P) -> Result<T, FileError>
 where
     P: AsRef<Path>,
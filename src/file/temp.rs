@@ -0,0 +1,30 @@
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A directory for a test, removed with its contents when dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create an empty `rentmap-{name}-{pid}` directory in the system temp directory
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rentmap-{name}-{}", std::process::id()));
+        fs::remove_dir_all(&path).ok();
+        fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        fs::remove_dir_all(&self.0).ok();
+    }
+}
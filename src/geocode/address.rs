@@ -0,0 +1,536 @@
+use std::fmt::{self, Display, Formatter};
+
+use serde::Serialize;
+
+/// Cities and counties, as written since the 2014 reorganization
+const CITIES: [&str; 22] = [
+    "臺北市",
+    "新北市",
+    "桃園市",
+    "臺中市",
+    "臺南市",
+    "高雄市",
+    "基隆市",
+    "新竹市",
+    "嘉義市",
+    "新竹縣",
+    "苗栗縣",
+    "彰化縣",
+    "南投縣",
+    "雲林縣",
+    "嘉義縣",
+    "屏東縣",
+    "宜蘭縣",
+    "花蓮縣",
+    "臺東縣",
+    "澎湖縣",
+    "金門縣",
+    "連江縣",
+];
+
+/// Former counties merged into the cities that replaced them
+const FORMER_CITIES: [(&str, &str); 5] = [
+    ("臺北縣", "新北市"),
+    ("桃園縣", "桃園市"),
+    ("臺中縣", "臺中市"),
+    ("臺南縣", "臺南市"),
+    ("高雄縣", "高雄市"),
+];
+
+/// A Taiwanese address split into its components, from the largest division down
+///
+/// Numbers are kept as written in arabic digits, with `之` for sub-numbers (`3之1`) and a
+/// `B` prefix for basement floors (`B1`). Text that follows the floor, or that could not be
+/// parsed at all, is kept in `rest`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Address {
+    /// City or county (e.g., `臺北市`, `新竹縣`)
+    pub city: Option<String>,
+    /// District, township or county-administered city (e.g., `大安區`, `竹北市`)
+    pub district: Option<String>,
+    /// Village (e.g., `福安里`)
+    pub village: Option<String>,
+    pub neighborhood: Option<u32>,
+    /// Road or street (e.g., `民生東路`, `永康街`, `市民大道`)
+    pub road: Option<String>,
+    pub section: Option<u32>,
+    pub lane: Option<String>,
+    pub alley: Option<String>,
+    pub number: Option<String>,
+    pub floor: Option<String>,
+    pub rest: Option<String>,
+}
+
+impl Address {
+    /// Parse an address, leaving out the components it does not have
+    ///
+    /// Parsing never fails, an address that is not Taiwanese ends up in `rest` as a whole.
+    pub fn parse(address: &str) -> Self {
+        let text = normalize_chars(address);
+        let mut s = text.as_str();
+        let mut address = Self::default();
+
+        // a postal code is only told apart from a house number by the city after it
+        let postal = s.trim_start_matches(|c: char| c.is_ascii_digit());
+        if postal.starts_with("臺灣") || CITIES.iter().any(|city| postal.starts_with(city)) {
+            s = postal;
+        }
+
+        s = s
+            .strip_prefix("臺灣省")
+            .or(s.strip_prefix("臺灣"))
+            .unwrap_or(s);
+
+        let cities = CITIES
+            .iter()
+            .map(|city| (*city, *city, false))
+            .chain(FORMER_CITIES.map(|(name, city)| (name, city, true)));
+        let mut former = false;
+        for (name, city, is_former) in cities {
+            if let Some(rest) = s.strip_prefix(name) {
+                address.city = Some(city.to_string());
+                former = is_former;
+                s = rest;
+                break;
+            }
+        }
+
+        if let Some((district, rest)) = take_division(s, &['區', '鄉', '鎮', '市']) {
+            // the cities and townships of merged counties became districts
+            address.district = Some(match district.strip_suffix(['市', '鎮', '鄉']) {
+                Some(name) if former => format!("{name}區"),
+                _ => district.to_string(),
+            });
+            s = rest;
+        }
+
+        // list pages write the district and road as `大安區-復興南路二段`
+        s = s.trim_start_matches('-');
+
+        if let Some((village, rest)) = take_division(s, &['里', '村']) {
+            address.village = Some(village.to_string());
+            s = rest;
+        }
+
+        if let Some((neighborhood, rest)) = take_number(s)
+            && let Some(rest) = rest.strip_prefix('鄰')
+        {
+            address.neighborhood = Some(neighborhood);
+            s = rest;
+        }
+
+        if let Some((road, rest)) = take_road(s) {
+            address.road = Some(road.to_string());
+            s = rest;
+        }
+
+        if let Some((section, rest)) = take_number(s)
+            && let Some(rest) = rest.strip_prefix('段')
+        {
+            address.section = Some(section);
+            s = rest;
+        }
+
+        if let Some((lane, rest)) = take_numbered(s, '巷') {
+            address.lane = Some(lane);
+            s = rest;
+        }
+
+        if let Some((alley, rest)) = take_numbered(s, '弄') {
+            address.alley = Some(alley);
+            s = rest;
+        }
+
+        if let Some((number, rest)) = take_numbered(s, '號') {
+            address.number = Some(number);
+            s = rest;
+        } else if address.road.is_some()
+            && let Some((number, "")) = take_sub_numbered(s)
+        {
+            // the house number without `號`, as the last component
+            address.number = Some(number);
+            s = "";
+        }
+
+        if let Some((floor, rest)) = take_floor(s) {
+            address.floor = Some(floor);
+            s = rest;
+        }
+
+        if !s.is_empty() {
+            address.rest = Some(s.to_string());
+        }
+
+        address
+    }
+
+    /// The address of the whole building, without the floor
+    pub fn building(&self) -> Self {
+        Self {
+            floor: None,
+            ..self.clone()
+        }
+    }
+}
+
+impl Display for Address {
+    /// Write the canonical form, e.g. `臺北市中山區民生東路三段100巷5弄3之1號地下1樓`
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for part in [&self.city, &self.district, &self.village]
+            .into_iter()
+            .flatten()
+        {
+            f.write_str(part)?;
+        }
+
+        if let Some(neighborhood) = self.neighborhood {
+            write!(f, "{neighborhood}鄰")?;
+        }
+
+        if let Some(road) = &self.road {
+            f.write_str(road)?;
+        }
+
+        if let Some(section) = self.section {
+            write!(f, "{}段", to_chinese_number(section))?;
+        }
+
+        if let Some(lane) = &self.lane {
+            write!(f, "{lane}巷")?;
+        }
+
+        if let Some(alley) = &self.alley {
+            write!(f, "{alley}弄")?;
+        }
+
+        if let Some(number) = &self.number {
+            write!(f, "{number}號")?;
+        }
+
+        if let Some(floor) = &self.floor {
+            match floor.strip_prefix('B') {
+                Some(basement) => write!(f, "地下{basement}樓")?,
+                None => match floor.split_once('之') {
+                    Some((floor, sub)) => write!(f, "{floor}樓之{sub}")?,
+                    None => write!(f, "{floor}樓")?,
+                },
+            }
+        }
+
+        if let Some(rest) = &self.rest {
+            f.write_str(rest)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Normalize an address for use as a cache key
+///
+/// The address is parsed and written back in its canonical form, so that one address
+/// written differently on two listings (`台`/`臺`, full-width digits, `三段`/`3段`, with or
+/// without `號`) is only geocoded once. The floor is left out as it does not change the
+/// location.
+pub fn normalize_address(address: &str) -> String {
+    Address::parse(address).building().to_string()
+}
+
+/// Fold full-width letters, digits and punctuation to half-width, remove whitespace and
+/// write `台` as `臺` as in official names
+fn normalize_chars(address: &str) -> String {
+    address
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+            '台' => '臺',
+            c => c,
+        })
+        .collect()
+}
+
+fn is_han(c: char) -> bool {
+    ('\u{4E00}'..='\u{9FFF}').contains(&c)
+}
+
+/// Take a division of 2 to 4 characters ending with one of `suffixes`
+///
+/// Suffixes are tried in order, and for each the longest candidate is preferred, since the
+/// name can itself contain a suffix (e.g., `平鎮區`, `新市區`, `中村里`). A candidate
+/// followed by a road suffix is the start of a road name instead (e.g., `中村路`).
+fn take_division<'a>(s: &'a str, suffixes: &[char]) -> Option<(&'a str, &'a str)> {
+    let candidates: Vec<(usize, char)> = s.char_indices().skip(1).take(3).collect();
+
+    suffixes.iter().find_map(|suffix| {
+        candidates
+            .iter()
+            .rev()
+            .filter(|(_, c)| c == suffix)
+            .map(|&(i, c)| s.split_at(i + c.len_utf8()))
+            .find(|(name, rest)| {
+                name.chars().all(is_han) && !rest.starts_with(['路', '街', '道', '巷'])
+            })
+    })
+}
+
+/// Take the road, up to its last `路`, `街` or `道` before the first number or numbered
+/// component
+///
+/// Without such a suffix, everything before the first number is taken as the road.
+fn take_road(s: &str) -> Option<(&str, &str)> {
+    let stop = s
+        .find(|c: char| c.is_ascii_digit() || "段巷弄號樓".contains(c))
+        .unwrap_or(s.len());
+
+    let end = match s[..stop].rfind(['路', '街', '道']) {
+        Some(i) => i + '路'.len_utf8(),
+        None if stop < s.len() && s[..stop].chars().all(is_han) => stop,
+        None => return None,
+    };
+
+    match end {
+        0 => None,
+        end => Some(s.split_at(end)),
+    }
+}
+
+/// Take a number in arabic digits or Chinese numerals
+fn take_number(s: &str) -> Option<(u32, &str)> {
+    let digits = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+
+    if digits > 0 {
+        let (number, rest) = s.split_at(digits);
+        return Some((number.parse().ok()?, rest));
+    }
+
+    let numerals = s.len() - s.trim_start_matches(|c| chinese_digit(c).is_some()).len();
+    let (number, rest) = s.split_at(numerals);
+
+    Some((parse_chinese_number(number)?, rest))
+}
+
+/// Take a number with an optional sub-number, as `12之1` or `12-1`
+fn take_sub_numbered(s: &str) -> Option<(String, &str)> {
+    let (number, rest) = take_number(s)?;
+
+    let sub = rest.strip_prefix('之').or(rest.strip_prefix('-'));
+    if let Some(sub) = sub
+        && let Some((sub, rest)) = take_number(sub)
+    {
+        return Some((format!("{number}之{sub}"), rest));
+    }
+
+    Some((number.to_string(), rest))
+}
+
+/// Take a number with an optional sub-number followed by `marker`
+fn take_numbered(s: &str, marker: char) -> Option<(String, &str)> {
+    let (number, rest) = take_sub_numbered(s)?;
+    Some((number, rest.strip_prefix(marker)?))
+}
+
+/// Take a floor as `5樓`, `5F`, `5樓之2`, `B1`, `B1F` or `地下1樓`
+fn take_floor(s: &str) -> Option<(String, &str)> {
+    if let Some(basement) = s.strip_prefix('B').or(s.strip_prefix("地下")) {
+        let (floor, rest) = take_number(basement)?;
+        let rest = rest.strip_prefix(['樓', 'F', 'f', '層']).unwrap_or(rest);
+        return Some((format!("B{floor}"), rest));
+    }
+
+    let (floor, rest) = take_number(s)?;
+    let rest = rest.strip_prefix(['樓', 'F', 'f', '層'])?;
+
+    let sub = rest.strip_prefix('之').or(rest.strip_prefix('-'));
+    if let Some(sub) = sub
+        && let Some((sub, rest)) = take_number(sub)
+    {
+        return Some((format!("{floor}之{sub}"), rest));
+    }
+
+    Some((floor.to_string(), rest))
+}
+
+fn chinese_digit(c: char) -> Option<u32> {
+    match c {
+        '零' | '〇' => Some(0),
+        '一' => Some(1),
+        '二' | '兩' => Some(2),
+        '三' => Some(3),
+        '四' => Some(4),
+        '五' => Some(5),
+        '六' => Some(6),
+        '七' => Some(7),
+        '八' => Some(8),
+        '九' => Some(9),
+        '十' => Some(10),
+        '百' => Some(100),
+        '千' => Some(1000),
+        _ => None,
+    }
+}
+
+/// Parse Chinese numerals such as `三`, `十二` or `一百零五`
+fn parse_chinese_number(s: &str) -> Option<u32> {
+    if s.is_empty() {
+        return None;
+    }
+
+    let mut total = 0;
+    let mut digit = 0;
+
+    for value in s.chars().map(chinese_digit) {
+        match value? {
+            unit @ (10 | 100 | 1000) => {
+                // `十二` is twelve, with an implied one before the unit
+                total += digit.max(1) * unit;
+                digit = 0;
+            }
+            value => digit = value,
+        }
+    }
+
+    Some(total + digit)
+}
+
+/// Write a section number in Chinese numerals as in official addresses, up to 99
+fn to_chinese_number(n: u32) -> String {
+    const DIGITS: [char; 10] = ['零', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+    match (n / 10, n % 10) {
+        (0, ones) => DIGITS[ones as usize].to_string(),
+        (1, 0) => "十".to_string(),
+        (1, ones) => format!("十{}", DIGITS[ones as usize]),
+        (tens @ 2..=9, 0) => format!("{}十", DIGITS[tens as usize]),
+        (tens @ 2..=9, ones) => format!("{}十{}", DIGITS[tens as usize], DIGITS[ones as usize]),
+        _ => n.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(
+            normalize_address("台北市 大安區復興南路二段１２３號"),
+            "臺北市大安區復興南路二段123號"
+        );
+        assert_eq!(
+            normalize_address("臺北市大安區　復興南路二段123號"),
+            normalize_address("台北市大安區復興南路二段１２３號")
+        );
+        assert_eq!(
+            normalize_address("中山區民生東路三段"),
+            normalize_address("中山區民生東路3段")
+        );
+        assert_eq!(
+            normalize_address("新北市板橋區文化路一段100號5樓"),
+            normalize_address("新北市板橋區文化路1段100")
+        );
+    }
+
+    #[test]
+    fn test_parse_address() {
+        let address = Address::parse("100臺灣台北市中山區民生東路３段100巷5弄3-1號B1");
+
+        assert_eq!(
+            address,
+            Address {
+                city: Some("臺北市".to_string()),
+                district: Some("中山區".to_string()),
+                road: Some("民生東路".to_string()),
+                section: Some(3),
+                lane: Some("100".to_string()),
+                alley: Some("5".to_string()),
+                number: Some("3之1".to_string()),
+                floor: Some("B1".to_string()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            address.to_string(),
+            "臺北市中山區民生東路三段100巷5弄3之1號地下1樓"
+        );
+    }
+
+    #[test]
+    fn test_parse_district_containing_suffix() {
+        let address = Address::parse("桃園市平鎮區中豐路100號");
+        assert_eq!(address.district.as_deref(), Some("平鎮區"));
+        assert_eq!(address.road.as_deref(), Some("中豐路"));
+
+        let address = Address::parse("臺南市新市區中山路1號");
+        assert_eq!(address.district.as_deref(), Some("新市區"));
+        assert_eq!(address.road.as_deref(), Some("中山路"));
+
+        let address = Address::parse("臺北市信義區市府路1號");
+        assert_eq!(address.district.as_deref(), Some("信義區"));
+        assert_eq!(address.road.as_deref(), Some("市府路"));
+
+        let address = Address::parse("苗栗縣竹南鎮中村里中村路1號");
+        assert_eq!(address.district.as_deref(), Some("竹南鎮"));
+        assert_eq!(address.village.as_deref(), Some("中村里"));
+        assert_eq!(address.road.as_deref(), Some("中村路"));
+    }
+
+    #[test]
+    fn test_parse_village_and_county_city() {
+        let address = Address::parse("新竹縣竹北市光明里5鄰光明六路100號10樓之2");
+
+        assert_eq!(address.city.as_deref(), Some("新竹縣"));
+        assert_eq!(address.district.as_deref(), Some("竹北市"));
+        assert_eq!(address.village.as_deref(), Some("光明里"));
+        assert_eq!(address.neighborhood, Some(5));
+        assert_eq!(address.road.as_deref(), Some("光明六路"));
+        assert_eq!(address.number.as_deref(), Some("100"));
+        assert_eq!(address.floor.as_deref(), Some("10之2"));
+        assert_eq!(address.rest, None);
+    }
+
+    #[test]
+    fn test_parse_road_names() {
+        let address = Address::parse("臺北市中正區市民大道一段100號");
+        assert_eq!(address.road.as_deref(), Some("市民大道"));
+        assert_eq!(address.section, Some(1));
+
+        let address = Address::parse("大安區-永康街12巷3號5F");
+        assert_eq!(address.district.as_deref(), Some("大安區"));
+        assert_eq!(address.road.as_deref(), Some("永康街"));
+        assert_eq!(address.lane.as_deref(), Some("12"));
+        assert_eq!(address.floor.as_deref(), Some("5"));
+
+        // a village-like name followed by a road suffix is the road
+        let address = Address::parse("中村路8號");
+        assert_eq!(address.village, None);
+        assert_eq!(address.road.as_deref(), Some("中村路"));
+    }
+
+    #[test]
+    fn test_parse_former_county() {
+        let address = Address::parse("台北縣板橋市文化路二段");
+        assert_eq!(address.city.as_deref(), Some("新北市"));
+        assert_eq!(address.district.as_deref(), Some("板橋區"));
+        assert_eq!(address.section, Some(2));
+        assert_eq!(address, Address::parse("新北市板橋區文化路二段"));
+    }
+
+    #[test]
+    fn test_parse_unknown_address() {
+        let address = Address::parse("臺北車站");
+        assert_eq!(address.rest.as_deref(), Some("臺北車站"));
+        assert_eq!(address.to_string(), "臺北車站");
+    }
+
+    #[test]
+    fn test_chinese_numbers() {
+        assert_eq!(parse_chinese_number("三"), Some(3));
+        assert_eq!(parse_chinese_number("十二"), Some(12));
+        assert_eq!(parse_chinese_number("二十"), Some(20));
+        assert_eq!(parse_chinese_number("一百零五"), Some(105));
+        assert_eq!(parse_chinese_number(""), None);
+        assert_eq!(to_chinese_number(3), "三");
+        assert_eq!(to_chinese_number(12), "十二");
+        assert_eq!(to_chinese_number(40), "四十");
+    }
+}
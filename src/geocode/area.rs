@@ -0,0 +1,324 @@
+use std::f64::consts::PI;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use super::distance::EARTH_RADIUS_KM;
+use super::{GeocodeError, haversine_km, parse_coordinate};
+
+/// Kilometers per degree of latitude
+const KM_PER_DEGREE: f64 = EARTH_RADIUS_KM * PI / 180.0;
+
+/// A latitude and longitude range, inclusive
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub max_lat: f64,
+    pub min_lng: f64,
+    pub max_lng: f64,
+}
+
+impl BoundingBox {
+    /// The box of a set of `(lat, lng)` points, `None` if there are none
+    fn around(points: impl IntoIterator<Item = (f64, f64)>) -> Option<Self> {
+        points.into_iter().fold(None, |bounds, (lat, lng)| {
+            Some(match bounds {
+                None => Self {
+                    min_lat: lat,
+                    max_lat: lat,
+                    min_lng: lng,
+                    max_lng: lng,
+                },
+                Some(b) => Self {
+                    min_lat: b.min_lat.min(lat),
+                    max_lat: b.max_lat.max(lat),
+                    min_lng: b.min_lng.min(lng),
+                    max_lng: b.max_lng.max(lng),
+                },
+            })
+        })
+    }
+
+    /// The overlap of two boxes, `None` if they are disjoint
+    pub fn intersect(&self, other: &Self) -> Option<Self> {
+        let bounds = Self {
+            min_lat: self.min_lat.max(other.min_lat),
+            max_lat: self.max_lat.min(other.max_lat),
+            min_lng: self.min_lng.max(other.min_lng),
+            max_lng: self.max_lng.min(other.max_lng),
+        };
+
+        (bounds.min_lat <= bounds.max_lat && bounds.min_lng <= bounds.max_lng).then_some(bounds)
+    }
+
+    pub fn contains(&self, (lat, lng): (f64, f64)) -> bool {
+        (self.min_lat..=self.max_lat).contains(&lat) && (self.min_lng..=self.max_lng).contains(&lng)
+    }
+}
+
+/// A circle of `km` great-circle kilometers around a center
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Radius {
+    pub lat: f64,
+    pub lng: f64,
+    pub km: f64,
+}
+
+impl FromStr for Radius {
+    type Err = String;
+
+    /// Parse `lat,lng,km` (e.g., `25.033,121.565,2`)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let expected = || format!("expected LAT,LNG,KM, got {s:?}");
+        let (center, km) = s.rsplit_once(',').ok_or_else(expected)?;
+        if !center.contains(',') {
+            return Err(expected());
+        }
+        let (lat, lng) = parse_coordinate(center).map_err(|err| err.to_string())?;
+        let km: f64 = km
+            .trim()
+            .parse()
+            .ok()
+            .filter(|km: &f64| *km > 0.0)
+            .ok_or_else(|| format!("invalid radius {km:?}"))?;
+
+        Ok(Self { lat, lng, km })
+    }
+}
+
+impl Radius {
+    /// A box around the circle, widened in longitude towards the poles
+    pub fn bounding_box(&self) -> BoundingBox {
+        let dlat = self.km / KM_PER_DEGREE;
+        let dlng = (self.km / (KM_PER_DEGREE * self.lat.to_radians().cos())).min(180.0);
+
+        BoundingBox {
+            min_lat: self.lat - dlat,
+            max_lat: self.lat + dlat,
+            min_lng: self.lng - dlng,
+            max_lng: self.lng + dlng,
+        }
+    }
+
+    /// Kilometers from the center to a `(lat, lng)` location
+    pub fn distance_km(&self, location: (f64, f64)) -> f64 {
+        haversine_km((self.lat, self.lng), location)
+    }
+
+    pub fn contains(&self, location: (f64, f64)) -> bool {
+        self.distance_km(location) <= self.km
+    }
+}
+
+/// A closed ring of `(lat, lng)` vertices, the last joined back to the first
+type Ring = Vec<(f64, f64)>;
+
+/// Polygons with holes, as read from GeoJSON
+///
+/// Each polygon is an outer ring followed by the rings of its holes. A point is in the area
+/// when it is inside the outer ring of any polygon and outside all of its holes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MultiPolygon {
+    polygons: Vec<Vec<Ring>>,
+}
+
+/// GeoJSON objects holding polygons, other geometries are rejected
+#[derive(Deserialize)]
+#[serde(tag = "type")]
+enum GeoJson {
+    Polygon {
+        coordinates: Vec<Vec<Vec<f64>>>,
+    },
+    MultiPolygon {
+        coordinates: Vec<Vec<Vec<Vec<f64>>>>,
+    },
+    GeometryCollection {
+        geometries: Vec<GeoJson>,
+    },
+    Feature {
+        geometry: Option<Box<GeoJson>>,
+    },
+    FeatureCollection {
+        features: Vec<GeoJson>,
+    },
+}
+
+impl GeoJson {
+    fn into_polygons(self, polygons: &mut Vec<Vec<Vec<Vec<f64>>>>) {
+        match self {
+            Self::Polygon { coordinates } => polygons.push(coordinates),
+            Self::MultiPolygon { coordinates } => polygons.extend(coordinates),
+            Self::GeometryCollection {
+                geometries: objects,
+            }
+            | Self::FeatureCollection { features: objects } => {
+                for object in objects {
+                    object.into_polygons(polygons);
+                }
+            }
+            Self::Feature { geometry } => {
+                if let Some(geometry) = geometry {
+                    geometry.into_polygons(polygons);
+                }
+            }
+        }
+    }
+}
+
+impl MultiPolygon {
+    /// Read the polygons of a GeoJSON Polygon, MultiPolygon, GeometryCollection, Feature or
+    /// FeatureCollection, merged into one area
+    pub fn from_geojson(text: &str) -> Result<Self, GeocodeError> {
+        let invalid = |message: String| GeocodeError::InvalidGeoJson(message);
+
+        let geojson: GeoJson =
+            serde_json::from_str(text).map_err(|err| invalid(err.to_string()))?;
+
+        let mut coordinates = Vec::new();
+        geojson.into_polygons(&mut coordinates);
+
+        let polygons = coordinates
+            .into_iter()
+            .map(|rings| {
+                if rings.is_empty() {
+                    return Err(invalid("polygon without rings".to_string()));
+                }
+
+                rings
+                    .into_iter()
+                    .map(|ring| {
+                        let ring = ring
+                            .into_iter()
+                            // GeoJSON positions are [lng, lat, altitude?]
+                            .map(|position| match position[..] {
+                                [lng, lat, ..] => Ok((lat, lng)),
+                                _ => Err(invalid(format!("position {position:?}"))),
+                            })
+                            .collect::<Result<Ring, _>>()?;
+
+                        match ring.len() {
+                            ..3 => Err(invalid("ring with fewer than 3 positions".to_string())),
+                            _ => Ok(ring),
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if polygons.is_empty() {
+            return Err(invalid("no polygons".to_string()));
+        }
+
+        Ok(Self { polygons })
+    }
+
+    /// The box of all outer rings
+    pub fn bounding_box(&self) -> BoundingBox {
+        BoundingBox::around(
+            self.polygons
+                .iter()
+                .flat_map(|rings| rings[0].iter().copied()),
+        )
+        .expect("polygons are not empty")
+    }
+
+    pub fn contains(&self, location: (f64, f64)) -> bool {
+        self.polygons.iter().any(|rings| {
+            let (outer, holes) = rings.split_first().expect("rings are not empty");
+            ring_contains(outer, location)
+                && !holes.iter().any(|hole| ring_contains(hole, location))
+        })
+    }
+}
+
+/// Whether a point is inside a ring by casting a ray towards increasing longitude and
+/// counting edge crossings
+fn ring_contains(ring: &[(f64, f64)], (lat, lng): (f64, f64)) -> bool {
+    let mut inside = false;
+    let mut previous = ring[ring.len() - 1];
+
+    for &vertex in ring {
+        let ((lat1, lng1), (lat2, lng2)) = (previous, vertex);
+
+        if (lat1 > lat) != (lat2 > lat) {
+            let crossing = lng1 + (lat - lat1) / (lat2 - lat1) * (lng2 - lng1);
+            if lng < crossing {
+                inside = !inside;
+            }
+        }
+
+        previous = vertex;
+    }
+
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_radius() {
+        let radius: Radius = "25.033, 121.565, 2".parse().unwrap();
+        assert_eq!(radius.km, 2.0);
+
+        let bounds = radius.bounding_box();
+        // a box corner is outside the circle but in the box
+        let corner = (bounds.max_lat - 0.001, bounds.max_lng - 0.001);
+        assert!(bounds.contains(corner) && !radius.contains(corner));
+        assert!(radius.contains((25.040, 121.565)));
+        assert!(!bounds.contains((25.060, 121.565)));
+
+        assert!("25.033,121.565".parse::<Radius>().is_err());
+        assert!("25.033,121.565,-1".parse::<Radius>().is_err());
+    }
+
+    #[test]
+    fn test_multi_polygon() {
+        // a square with a square hole, and a triangle to the east
+        let area = MultiPolygon::from_geojson(
+            r#"{
+                "type": "FeatureCollection",
+                "features": [
+                    {
+                        "type": "Feature",
+                        "properties": {"name": "大安"},
+                        "geometry": {
+                            "type": "Polygon",
+                            "coordinates": [
+                                [[121.50, 25.00], [121.54, 25.00], [121.54, 25.04], [121.50, 25.04], [121.50, 25.00]],
+                                [[121.51, 25.01], [121.53, 25.01], [121.53, 25.03], [121.51, 25.03], [121.51, 25.01]]
+                            ]
+                        }
+                    },
+                    {
+                        "type": "Feature",
+                        "properties": null,
+                        "geometry": {
+                            "type": "MultiPolygon",
+                            "coordinates": [[[[121.60, 25.00], [121.64, 25.00], [121.60, 25.04]]]]
+                        }
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        assert!(area.contains((25.005, 121.52)));
+        assert!(!area.contains((25.02, 121.52)));
+        assert!(area.contains((25.01, 121.61)));
+        assert!(!area.contains((25.03, 121.63)));
+        assert!(!area.contains((25.02, 121.57)));
+
+        let bounds = area.bounding_box();
+        assert_eq!((bounds.min_lng, bounds.max_lng), (121.50, 121.64));
+
+        assert!(
+            MultiPolygon::from_geojson(r#"{"type": "Point", "coordinates": [121.5, 25.0]}"#)
+                .is_err()
+        );
+        assert!(
+            MultiPolygon::from_geojson(r#"{"type": "FeatureCollection", "features": []}"#).is_err()
+        );
+    }
+}
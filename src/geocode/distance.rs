@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+
+/// Distances in kilometers from a listing to each point of interest, by POI name
+pub type Distances = BTreeMap<String, f64>;
+
+/// Mean radius of the Earth in kilometers
+pub(super) const EARTH_RADIUS_KM: f64 = 6371.0088;
+
+/// Walking routes follow streets, about a quarter longer than the straight line
+const DETOUR_FACTOR: f64 = 1.25;
+
+/// Meters walked per minute, about 4.8 km/h
+pub const WALKING_SPEED: f64 = 80.0;
+
+/// Great-circle distance in kilometers between two `(lat, lng)` locations
+pub fn haversine_km((lat1, lng1): (f64, f64), (lat2, lng2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let dlat = lat2 - lat1;
+    let dlng = (lng2 - lng1).to_radians();
+
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlng / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Estimated meters walked along streets between locations `km` apart in a straight line
+pub fn walking_meters(km: f64) -> f64 {
+    km * 1000.0 * DETOUR_FACTOR
+}
+
+/// A named place that listings are measured against
+#[derive(Clone, Debug)]
+pub struct Poi {
+    pub name: String,
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl Poi {
+    /// Distance in kilometers from a `(lat, lng)` location
+    pub fn distance_km(&self, location: (f64, f64)) -> f64 {
+        haversine_km((self.lat, self.lng), location)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine() {
+        assert_eq!(haversine_km((25.0, 121.5), (25.0, 121.5)), 0.0);
+
+        // Taipei Main Station to Taipei 101
+        let km = haversine_km((25.0478, 121.5170), (25.0340, 121.5645));
+        assert!((km - 5.02).abs() < 0.05, "{km}");
+
+        // a degree of latitude is about 111 km anywhere
+        let km = haversine_km((0.0, 0.0), (1.0, 0.0));
+        assert!((km - 111.2).abs() < 0.1, "{km}");
+    }
+}
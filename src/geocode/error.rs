@@ -0,0 +1,43 @@
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::config::error::NoApiKey;
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum GeocodeError {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    NoApiKey(#[from] NoApiKey),
+
+    #[error(transparent)]
+    #[diagnostic(
+        code(geocode::google),
+        help("check your Google API key is valid and has the Geocoding API enabled")
+    )]
+    Google(#[from] google_maps::Error),
+
+    #[error(transparent)]
+    #[diagnostic(
+        code(geocode::nominatim),
+        help(
+            "check `--nominatim-url` points to a Nominatim-compatible server, the public server allows at most 1 request per second"
+        )
+    )]
+    Nominatim(#[from] reqwest::Error),
+
+    #[error("invalid coordinate {0:?}")]
+    #[diagnostic(
+        code(geocode::invalid_coordinate),
+        help("give latitude and longitude separated by a comma, e.g. 25.033,121.565")
+    )]
+    InvalidCoordinate(String),
+
+    #[error("invalid GeoJSON: {0}")]
+    #[diagnostic(
+        code(geocode::invalid_geojson),
+        help(
+            "give a Polygon or MultiPolygon geometry, or a Feature or FeatureCollection of them, in lng,lat order"
+        )
+    )]
+    InvalidGeoJson(String),
+}
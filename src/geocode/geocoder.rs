@@ -0,0 +1,213 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+use super::{Geocode, GeocodeError, GoogleGeocoder, NominatimGeocoder};
+use crate::config::geocoding::GeocodingConfig;
+use crate::config::google::GoogleConfig;
+
+/// A geocoding service that finds the locations of addresses and the addresses at locations
+pub trait Geocoder: Sync {
+    /// The service queried
+    fn provider(&self) -> GeocoderType;
+
+    /// Find the locations matching an address, best match first
+    fn search(
+        &self,
+        address: &str,
+    ) -> impl Future<Output = Result<Vec<Geocode>, GeocodeError>> + Send;
+
+    /// Find the addresses at a location, most specific first
+    fn search_reverse(
+        &self,
+        lat: f64,
+        lng: f64,
+    ) -> impl Future<Output = Result<Vec<Geocode>, GeocodeError>> + Send;
+
+    /// Geocode an address, keeping the best match
+    ///
+    /// An address that matches nothing is not an error, it gives a `Geocode` without location
+    /// so that it is cached and not queried again.
+    fn geocode(&self, address: &str) -> impl Future<Output = Result<Geocode, GeocodeError>> + Send {
+        async move {
+            let results = self.search(address).await?;
+            Ok(best_or_not_found(
+                results,
+                address.to_string(),
+                self.provider(),
+            ))
+        }
+    }
+
+    /// Reverse geocode a location, keeping the most specific address
+    fn reverse(
+        &self,
+        lat: f64,
+        lng: f64,
+    ) -> impl Future<Output = Result<Geocode, GeocodeError>> + Send {
+        async move {
+            let results = self.search_reverse(lat, lng).await?;
+            Ok(best_or_not_found(
+                results,
+                format!("{lat},{lng}"),
+                self.provider(),
+            ))
+        }
+    }
+}
+
+fn best_or_not_found(results: Vec<Geocode>, query: String, provider: GeocoderType) -> Geocode {
+    results.into_iter().next().unwrap_or_else(|| Geocode {
+        provider: Some(provider.as_str().to_string()),
+        ..Geocode::not_found(query)
+    })
+}
+
+/// Parse a `lat,lng` coordinate
+pub fn parse_coordinate(text: &str) -> Result<(f64, f64), GeocodeError> {
+    let invalid = || GeocodeError::InvalidCoordinate(text.to_string());
+
+    let (lat, lng) = text.split_once(',').ok_or_else(invalid)?;
+    let lat: f64 = lat.trim().parse().map_err(|_| invalid())?;
+    let lng: f64 = lng.trim().parse().map_err(|_| invalid())?;
+
+    match (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lng) {
+        true => Ok((lat, lng)),
+        false => Err(invalid()),
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum GeocoderType {
+    #[default]
+    Google,
+    /// OpenStreetMap Nominatim, or a compatible server at `--nominatim-url`
+    Nominatim,
+}
+
+impl GeocoderType {
+    /// Name stored with cached results
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Google => "google",
+            Self::Nominatim => "nominatim",
+        }
+    }
+
+    /// Requests per second within the usage policy of the public service
+    pub fn default_rate(self) -> u32 {
+        match self {
+            Self::Google => 25,
+            Self::Nominatim => 1,
+        }
+    }
+}
+
+/// Spaces requests made one after another to at most `rate` per second
+#[derive(Debug)]
+pub struct Throttle {
+    interval: Duration,
+    next: Option<Instant>,
+}
+
+impl Throttle {
+    pub fn new(rate: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / rate.max(1),
+            next: None,
+        }
+    }
+
+    /// Wait until the next request may be made
+    pub async fn wait(&mut self) {
+        if let Some(next) = self.next {
+            tokio::time::sleep_until(next).await;
+        }
+
+        self.next = Some(Instant::now() + self.interval);
+    }
+}
+
+/// Geocoder instance selected by `--provider`
+pub enum GeocoderBackend {
+    Google(GoogleGeocoder),
+    Nominatim(NominatimGeocoder),
+}
+
+impl GeocoderBackend {
+    /// Create the geocoder selected by the configuration
+    ///
+    /// Only the Google geocoder needs an API key.
+    pub fn new(config: &GeocodingConfig, google: GoogleConfig) -> Result<Self, GeocodeError> {
+        let backend = match config.provider.unwrap_or_default() {
+            GeocoderType::Google => {
+                GoogleGeocoder::new(&google.get_api_key()?, config.language, config.region)?.into()
+            }
+            GeocoderType::Nominatim => NominatimGeocoder::new(
+                config.nominatim_url.clone(),
+                config.language,
+                config.region,
+            )?
+            .into(),
+        };
+
+        Ok(backend)
+    }
+}
+
+impl From<GoogleGeocoder> for GeocoderBackend {
+    fn from(geocoder: GoogleGeocoder) -> Self {
+        Self::Google(geocoder)
+    }
+}
+
+impl From<NominatimGeocoder> for GeocoderBackend {
+    fn from(geocoder: NominatimGeocoder) -> Self {
+        Self::Nominatim(geocoder)
+    }
+}
+
+impl Geocoder for GeocoderBackend {
+    fn provider(&self) -> GeocoderType {
+        match self {
+            Self::Google(geocoder) => geocoder.provider(),
+            Self::Nominatim(geocoder) => geocoder.provider(),
+        }
+    }
+
+    async fn search(&self, address: &str) -> Result<Vec<Geocode>, GeocodeError> {
+        match self {
+            Self::Google(geocoder) => geocoder.search(address).await,
+            Self::Nominatim(geocoder) => geocoder.search(address).await,
+        }
+    }
+
+    async fn search_reverse(&self, lat: f64, lng: f64) -> Result<Vec<Geocode>, GeocodeError> {
+        match self {
+            Self::Google(geocoder) => geocoder.search_reverse(lat, lng).await,
+            Self::Nominatim(geocoder) => geocoder.search_reverse(lat, lng).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_coordinate() {
+        assert_eq!(
+            parse_coordinate("25.033, 121.565").unwrap(),
+            (25.033, 121.565)
+        );
+        assert_eq!(parse_coordinate("-33.8,151.2").unwrap(), (-33.8, 151.2));
+        assert!(parse_coordinate("25.033").is_err());
+        assert!(parse_coordinate("121.565,25.033,1").is_err());
+        assert!(parse_coordinate("91,0").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use google_maps::prelude::*;
+
+use super::{Geocode, GeocodeError, Geocoder, GeocoderType};
+
+/// Geocoder backed by the Google Geocoding API
+pub struct GoogleGeocoder {
+    client: Client,
+    language: Option<Language>,
+    region: Option<Region>,
+}
+
+impl GoogleGeocoder {
+    pub fn new(
+        api_key: &str,
+        language: Option<Language>,
+        region: Option<Region>,
+    ) -> Result<Self, GeocodeError> {
+        Ok(Self {
+            client: Client::try_new(api_key)?,
+            language,
+            region,
+        })
+    }
+}
+
+impl Geocoder for GoogleGeocoder {
+    fn provider(&self) -> GeocoderType {
+        GeocoderType::Google
+    }
+
+    async fn search(&self, address: &str) -> Result<Vec<Geocode>, GeocodeError> {
+        let mut builder = self.client.geocoding().with_address(address);
+
+        if let Some(language) = self.language {
+            builder = builder.with_language(language);
+        }
+
+        if let Some(region) = self.region {
+            builder = builder.with_region(region);
+        }
+
+        to_geocodes(address, builder.execute().await)
+    }
+
+    /// The reverse endpoint has no region bias, only the language applies
+    async fn search_reverse(&self, lat: f64, lng: f64) -> Result<Vec<Geocode>, GeocodeError> {
+        let location = LatLng::try_from_f64(lat, lng)?;
+
+        let mut builder = self.client.reverse_geocoding(location);
+
+        if let Some(language) = self.language {
+            builder = builder.with_language(language);
+        }
+
+        to_geocodes(&format!("{lat},{lng}"), builder.execute().await)
+    }
+}
+
+fn to_geocodes(
+    query: &str,
+    response: Result<GeocodingResponse, Error>,
+) -> Result<Vec<Geocode>, GeocodeError> {
+    let response = match response {
+        Ok(response) => response,
+        Err(Error::Geocoding(GeocodingError::ZeroResults)) => return Ok(Vec::new()),
+        Err(err) => return Err(err.into()),
+    };
+
+    let geocodes = response
+        .results
+        .into_iter()
+        .map(|result| {
+            let LatLng { lat, lng } = result.geometry.location;
+
+            Geocode {
+                address: query.to_string(),
+                lat: f64::try_from(lat).ok(),
+                lng: f64::try_from(lng).ok(),
+                formatted_address: Some(result.formatted_address),
+                location_type: result.geometry.location_type.as_ref().map(String::from),
+                place_id: Some(result.place_id),
+                provider: Some(GeocoderType::Google.as_str().to_string()),
+            }
+        })
+        .collect();
+
+    Ok(geocodes)
+}
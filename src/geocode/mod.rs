@@ -0,0 +1,19 @@
+mod address;
+mod area;
+mod distance;
+mod error;
+mod geocoder;
+mod google;
+mod model;
+mod nominatim;
+mod station;
+
+pub use address::{Address, normalize_address};
+pub use area::{BoundingBox, MultiPolygon, Radius};
+pub use distance::{Distances, Poi, WALKING_SPEED, haversine_km, walking_meters};
+pub use error::GeocodeError;
+pub use geocoder::{Geocoder, GeocoderBackend, GeocoderType, Throttle, parse_coordinate};
+pub use google::GoogleGeocoder;
+pub use model::{AddressSource, Geocode, ItemAddress};
+pub use nominatim::NominatimGeocoder;
+pub use station::{NearbyStation, Station, line_code, nearest_stations};
@@ -0,0 +1,98 @@
+use serde::Serialize;
+use sqlx::FromRow;
+use url::Url;
+
+use super::{Address, GeocoderType, normalize_address};
+
+/// Where the address of a listing was read from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressSource {
+    /// Text detected in the address image of the item page
+    Ocr,
+    /// Location shown on the list page, usually only the district and road
+    Summary,
+}
+
+impl AddressSource {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ocr => "ocr",
+            Self::Summary => "summary",
+        }
+    }
+
+    /// Parse a source as stored by `as_str`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ocr" => Some(Self::Ocr),
+            "summary" => Some(Self::Summary),
+            _ => None,
+        }
+    }
+}
+
+/// The address of a listing, with the normalized form it is geocoded by
+#[derive(Clone, Debug, Serialize)]
+pub struct ItemAddress {
+    pub url: Url,
+    pub address: String,
+    pub normalized: String,
+    pub source: AddressSource,
+}
+
+impl ItemAddress {
+    pub fn new(url: Url, address: String, source: AddressSource) -> Self {
+        Self {
+            url,
+            normalized: normalize_address(&address),
+            address,
+            source,
+        }
+    }
+
+    /// Parse the address into its components
+    pub fn components(&self) -> Address {
+        Address::parse(&self.address)
+    }
+}
+
+/// Result of geocoding an address, without a location if nothing matched
+#[derive(Clone, Debug, Serialize, FromRow)]
+pub struct Geocode {
+    /// Normalized address, or `lat,lng` coordinate when reverse geocoded
+    pub address: String,
+    pub lat: Option<f64>,
+    pub lng: Option<f64>,
+    pub formatted_address: Option<String>,
+    /// How precise the location is (e.g., `ROOFTOP`, `APPROXIMATE`)
+    pub location_type: Option<String>,
+    pub place_id: Option<String>,
+    /// Geocoding service the result is from (e.g., `google`, `nominatim`)
+    pub provider: Option<String>,
+}
+
+impl Geocode {
+    /// A result for an address that matched nothing
+    pub fn not_found(address: String) -> Self {
+        Self {
+            address,
+            lat: None,
+            lng: None,
+            formatted_address: None,
+            location_type: None,
+            place_id: None,
+            provider: None,
+        }
+    }
+
+    /// Whether the result is from `provider`, as a cached result must be to be reused
+    pub fn is_from(&self, provider: GeocoderType) -> bool {
+        self.provider.as_deref() == Some(provider.as_str())
+    }
+
+    /// The location, if the address matched
+    pub fn location(&self) -> Option<(f64, f64)> {
+        self.lat.zip(self.lng)
+    }
+}
@@ -0,0 +1,220 @@
+use google_maps::prelude::{Language, Region};
+use serde::Deserialize;
+use tracing::debug;
+use url::Url;
+
+use super::{Geocode, GeocodeError, Geocoder, GeocoderType};
+
+/// Public OpenStreetMap server, limited to 1 request per second
+const DEFAULT_BASE_URL: &str = "https://nominatim.openstreetmap.org/";
+
+/// Nominatim asks clients to identify themselves
+const USER_AGENT: &str = concat!("rentmap/", env!("CARGO_PKG_VERSION"));
+
+/// Geocoder backed by an OpenStreetMap Nominatim-compatible server
+pub struct NominatimGeocoder {
+    http: reqwest::Client,
+    base_url: Url,
+    language: Option<Language>,
+    region: Option<Region>,
+}
+
+/// A place in a `jsonv2` response
+#[derive(Debug, Deserialize)]
+struct Place {
+    lat: String,
+    lon: String,
+    display_name: String,
+    osm_type: Option<String>,
+    osm_id: Option<i64>,
+    addresstype: Option<String>,
+}
+
+/// A reverse response is a place, or an error when nothing is there
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ReverseResponse {
+    Place(Place),
+    Error { error: String },
+}
+
+impl Place {
+    fn into_geocode(self, query: &str) -> Geocode {
+        Geocode {
+            address: query.to_string(),
+            lat: self.lat.parse().ok(),
+            lng: self.lon.parse().ok(),
+            formatted_address: Some(self.display_name),
+            location_type: self.addresstype,
+            // the same form as openstreetmap.org URLs, e.g. `way/123`
+            place_id: self
+                .osm_type
+                .zip(self.osm_id)
+                .map(|(t, id)| format!("{t}/{id}")),
+            provider: Some(GeocoderType::Nominatim.as_str().to_string()),
+        }
+    }
+}
+
+impl NominatimGeocoder {
+    /// Create a geocoder for the server at `base_url`, the public server if none
+    pub fn new(
+        base_url: Option<Url>,
+        language: Option<Language>,
+        region: Option<Region>,
+    ) -> Result<Self, GeocodeError> {
+        let mut base_url =
+            base_url.unwrap_or_else(|| Url::parse(DEFAULT_BASE_URL).expect("valid URL"));
+
+        // keep the last path segment when joining endpoints to a self-hosted prefix
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        let http = reqwest::Client::builder().user_agent(USER_AGENT).build()?;
+
+        Ok(Self {
+            http,
+            base_url,
+            language,
+            region,
+        })
+    }
+
+    fn request(&self, endpoint: &str) -> reqwest::RequestBuilder {
+        let url = self.base_url.join(endpoint).expect("valid endpoint");
+
+        let mut request = self.http.get(url).query(&[("format", "jsonv2")]);
+
+        if let Some(language) = &self.language {
+            request = request.query(&[("accept-language", String::from(language))]);
+        }
+
+        request
+    }
+}
+
+impl Geocoder for NominatimGeocoder {
+    fn provider(&self) -> GeocoderType {
+        GeocoderType::Nominatim
+    }
+
+    async fn search(&self, address: &str) -> Result<Vec<Geocode>, GeocodeError> {
+        let mut request = self.request("search").query(&[("q", address)]);
+
+        if let Some(region) = &self.region {
+            request = request.query(&[("countrycodes", String::from(region))]);
+        }
+
+        let places: Vec<Place> = request.send().await?.error_for_status()?.json().await?;
+
+        Ok(places
+            .into_iter()
+            .map(|place| place.into_geocode(address))
+            .collect())
+    }
+
+    async fn search_reverse(&self, lat: f64, lng: f64) -> Result<Vec<Geocode>, GeocodeError> {
+        let response: ReverseResponse = self
+            .request("reverse")
+            .query(&[("lat", lat), ("lon", lng)])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        match response {
+            ReverseResponse::Place(place) => Ok(vec![place.into_geocode(&format!("{lat},{lng}"))]),
+            ReverseResponse::Error { error } => {
+                debug!(lat, lng, %error, "no place at location");
+                Ok(Vec::new())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use axum::extract::Query;
+    use axum::routing::get;
+    use axum::{Json, Router};
+    use serde_json::{Value, json};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Serve a Nominatim stub under `/nominatim` and return its base URL
+    async fn serve_stub() -> Url {
+        let search = async |Query(params): Query<HashMap<String, String>>| {
+            assert_eq!(params["format"], "jsonv2");
+            assert_eq!(params["accept-language"], "zh-TW");
+            assert_eq!(params["countrycodes"], "tw");
+
+            let places = match params["q"].as_str() {
+                "臺北車站" => json!([{
+                    "lat": "25.0478",
+                    "lon": "121.5170",
+                    "display_name": "臺北車站, 北平西路, 中正區, 臺北市, 100, 臺灣",
+                    "osm_type": "way",
+                    "osm_id": 123,
+                    "addresstype": "railway",
+                }]),
+                _ => json!([]),
+            };
+
+            Json(places)
+        };
+
+        let reverse = async |Query(params): Query<HashMap<String, String>>| -> Json<Value> {
+            match params["lat"].as_str() {
+                "0" | "0.0" => Json(json!({ "error": "Unable to geocode" })),
+                lat => Json(json!({
+                    "lat": lat,
+                    "lon": params["lon"],
+                    "display_name": "復興南路二段, 大安區, 臺北市, 臺灣",
+                    "addresstype": "road",
+                })),
+            }
+        };
+
+        let app = Router::new()
+            .route("/nominatim/search", get(search))
+            .route("/nominatim/reverse", get(reverse));
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Url::parse(&format!("http://{addr}/nominatim")).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_nominatim_geocoder() {
+        let base_url = serve_stub().await;
+        let geocoder = NominatimGeocoder::new(
+            Some(base_url),
+            Some(Language::ChineseTaiwan),
+            Some(Region::Taiwan),
+        )
+        .unwrap();
+
+        let geocode = geocoder.geocode("臺北車站").await.unwrap();
+        assert_eq!(geocode.location(), Some((25.0478, 121.517)));
+        assert_eq!(geocode.location_type.as_deref(), Some("railway"));
+        assert_eq!(geocode.place_id.as_deref(), Some("way/123"));
+
+        let geocode = geocoder.geocode("nowhere").await.unwrap();
+        assert_eq!(geocode.address, "nowhere");
+        assert_eq!(geocode.location(), None);
+
+        let geocode = geocoder.reverse(25.033, 121.543).await.unwrap();
+        assert_eq!(geocode.address, "25.033,121.543");
+        assert_eq!(geocode.location_type.as_deref(), Some("road"));
+
+        let geocode = geocoder.reverse(0.0, 0.0).await.unwrap();
+        assert_eq!(geocode.location(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use std::fmt::{self, Display, Formatter};
+use std::io::Read;
+
+use serde::{Deserialize, Serialize};
+
+use super::{WALKING_SPEED, haversine_km, walking_meters};
+
+/// Stations of the Taipei Metro, Taoyuan Airport MRT and the Danhai and Ankeng light rails
+///
+/// One row per station and line, so a transfer station has a row for each of its codes.
+/// Coordinates are of the station, not of its exits, and are accurate to about 100 m.
+const BUNDLED_STATIONS: &str = include_str!("../../data/mrt_stations.csv");
+
+/// Line codes with the names they can be given by, in English and Chinese
+const LINES: [(&str, &str, &str); 9] = [
+    ("BR", "brown", "文湖"),
+    ("R", "red", "淡水信義"),
+    ("G", "green", "松山新店"),
+    ("O", "orange", "中和新蘆"),
+    ("BL", "blue", "板南"),
+    ("Y", "yellow", "環狀"),
+    ("A", "airport", "機場"),
+    ("V", "danhai", "淡海"),
+    ("K", "ankeng", "安坑"),
+];
+
+/// A station of a metro or light rail line
+#[derive(Clone, Debug, Deserialize)]
+pub struct Station {
+    /// Station code on its line (e.g., R05)
+    pub code: String,
+    /// Line code (e.g., R for the red Tamsui-Xinyi line)
+    pub line: String,
+    pub name: String,
+    pub name_en: String,
+    pub lat: f64,
+    pub lng: f64,
+}
+
+impl Station {
+    /// Load stations from CSV with `code,line,name,name_en,lat,lng` columns
+    pub fn load<R: Read>(reader: R) -> Result<Vec<Self>, csv::Error> {
+        csv::Reader::from_reader(reader).deserialize().collect()
+    }
+
+    /// The bundled stations
+    pub fn bundled() -> Vec<Self> {
+        Self::load(BUNDLED_STATIONS.as_bytes()).expect("valid bundled stations")
+    }
+}
+
+/// Get the line code for a code or line name (e.g., `R`, `red`, `淡水信義`), ignoring case
+///
+/// Other text is taken as a code as is, for lines of a custom station file.
+pub fn line_code(text: &str) -> String {
+    let text = text.trim();
+
+    LINES
+        .iter()
+        .find(|(code, en, zh)| {
+            code.eq_ignore_ascii_case(text) || en.eq_ignore_ascii_case(text) || *zh == text
+        })
+        .map_or_else(|| text.to_string(), |(code, ..)| code.to_string())
+}
+
+/// The nearest station of a line to a listing
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct NearbyStation {
+    pub line: String,
+    pub code: String,
+    pub name: String,
+    /// Straight-line meters
+    pub distance_m: u32,
+    /// Estimated walking meters along streets
+    pub walk_m: u32,
+    pub walk_minutes: u32,
+}
+
+impl NearbyStation {
+    fn new(station: &Station, distance_km: f64) -> Self {
+        let distance_m = distance_km * 1000.0;
+        let walk_m = walking_meters(distance_km);
+
+        Self {
+            line: station.line.clone(),
+            code: station.code.clone(),
+            name: station.name.clone(),
+            distance_m: distance_m.round() as u32,
+            walk_m: walk_m.round() as u32,
+            walk_minutes: (walk_m / WALKING_SPEED).ceil() as u32,
+        }
+    }
+}
+
+impl Display for NearbyStation {
+    /// Write e.g. `R05 大安 650m 9min`
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {}m {}min",
+            self.code, self.name, self.walk_m, self.walk_minutes
+        )
+    }
+}
+
+/// Find the nearest station of each line within `max_walk_m` walking meters, nearest first
+pub fn nearest_stations(
+    stations: &[Station],
+    location: (f64, f64),
+    max_walk_m: u32,
+) -> Vec<NearbyStation> {
+    let mut nearest: Vec<NearbyStation> = Vec::new();
+
+    for station in stations {
+        let nearby =
+            NearbyStation::new(station, haversine_km((station.lat, station.lng), location));
+
+        if nearby.walk_m > max_walk_m {
+            continue;
+        }
+
+        match nearest.iter_mut().find(|n| n.line == nearby.line) {
+            Some(n) if n.walk_m <= nearby.walk_m => {}
+            Some(n) => *n = nearby,
+            None => nearest.push(nearby),
+        }
+    }
+
+    nearest.sort_by_key(|n| n.walk_m);
+    nearest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_stations() {
+        let stations = Station::bundled();
+        assert!(stations.len() > 150);
+        assert!(
+            stations
+                .iter()
+                .all(|s| LINES.iter().any(|(code, ..)| *code == s.line))
+        );
+    }
+
+    #[test]
+    fn test_nearest_stations() {
+        let stations = Station::bundled();
+
+        // 復興南路二段, between Daan and Technology Building
+        let nearest = nearest_stations(&stations, (25.0290, 121.5436), 1000);
+        let codes: Vec<_> = nearest.iter().map(|n| n.code.as_str()).collect();
+        assert_eq!(codes, ["BR08", "R05"]);
+        assert!(nearest[0].walk_m > nearest[0].distance_m);
+        assert_eq!(nearest[0].to_string(), "BR08 科技大樓 390m 5min");
+
+        assert!(nearest_stations(&stations, (24.0, 121.0), 1000).is_empty());
+    }
+
+    #[test]
+    fn test_line_code() {
+        assert_eq!(line_code("red"), "R");
+        assert_eq!(line_code("bl"), "BL");
+        assert_eq!(line_code("文湖"), "BR");
+        assert_eq!(line_code("LG"), "LG");
+    }
+}
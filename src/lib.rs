@@ -3,9 +3,11 @@ pub mod cli;
 pub mod config;
 pub mod error;
 pub mod file;
+pub mod geocode;
 pub mod pretty;
 pub mod scraper;
 pub mod sites;
+pub mod transit;
 pub mod url;
 pub mod web;
 pub mod workspace;
@@ -1,8 +1,14 @@
 //! Main entry point for RentMap CLI
 
+use std::env;
+use std::io::{self, IsTerminal};
+
 use clap::{Parser, Subcommand};
 use miette::Result;
-use rentmap::cli::commands::{fetch, geocoding, item, list, ocr};
+use rentmap::cli::commands::{
+    commute, distances, export, fetch, geocode_items, geocoding, import, item, list, ocr, preview,
+    query, search, sql, stations, within, workspace,
+};
 use rentmap::error::TraceReport;
 use tracing_subscriber::{self, EnvFilter};
 
@@ -20,7 +26,28 @@ enum Commands {
     Item(item::Args),
     Fetch(fetch::Args),
     Geocoding(geocoding::Args),
+    GeocodeItems(geocode_items::Args),
+    Distances(distances::Args),
+    Stations(stations::Args),
+    Commute(commute::Args),
+    Within(within::Args),
     Ocr(ocr::Args),
+    Preview(preview::Args),
+    Export(export::Args),
+    Import(import::Args),
+    Query(query::Args),
+    Search(search::Args),
+    Sql(sql::Args),
+    Workspace(workspace::Args),
+}
+
+/// Disable colors when stdout is not a terminal, unless forced with `CLICOLOR_FORCE`
+pub fn setup_colors() {
+    let forced = env::var_os("CLICOLOR_FORCE").is_some_and(|value| value != "0");
+
+    if !forced && !io::stdout().is_terminal() {
+        colored::control::set_override(false);
+    }
 }
 
 /// Initialize tracing for logging
@@ -39,6 +66,7 @@ pub fn setup_tracing() {
 #[tokio::main(flavor = "multi_thread")]
 async fn main() -> Result<()> {
     setup_tracing();
+    setup_colors();
 
     let cli = Cli::parse();
 
@@ -47,7 +75,19 @@ async fn main() -> Result<()> {
         Commands::Item(args) => item::run(args).await,
         Commands::Fetch(args) => fetch::run(args).await,
         Commands::Geocoding(args) => geocoding::run(args).await,
+        Commands::GeocodeItems(args) => geocode_items::run(args).await,
+        Commands::Distances(args) => distances::run(args).await,
+        Commands::Stations(args) => stations::run(args).await,
+        Commands::Commute(args) => commute::run(args).await,
+        Commands::Within(args) => within::run(args).await,
         Commands::Ocr(args) => ocr::run(args).await,
+        Commands::Preview(args) => preview::run(args).await,
+        Commands::Export(args) => export::run(args).await,
+        Commands::Import(args) => import::run(args).await,
+        Commands::Query(args) => query::run(args).await,
+        Commands::Search(args) => search::run(args).await,
+        Commands::Sql(args) => sql::run(args).await,
+        Commands::Workspace(args) => workspace::run(args).await,
     }
     .trace()
 }
@@ -2,9 +2,10 @@ use std::time::Duration;
 
 use colored::Colorize;
 use comfy_table::{Cell, ContentArrangement, Table, presets};
-use google_maps::prelude::{GeocodingResponse, LatLng};
 
 use crate::apis::vision::model::OcrString;
+use crate::geocode::{Address, Geocode, ItemAddress};
+use crate::sites::rent591::{Listing, RentItem, RentItemSummary};
 
 /// Trait for types that can be pretty-printed to a String.
 pub trait ToPrettyString {
@@ -12,6 +13,15 @@ pub trait ToPrettyString {
     fn to_pretty_string(&self) -> String;
 }
 
+/// Format a size in bytes with a binary unit (e.g. `2.0 KiB`)
+pub fn format_size(bytes: i64) -> String {
+    match bytes {
+        b if b < 1024 => format!("{b} B"),
+        b if b < 1024 * 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        b => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+    }
+}
+
 impl ToPrettyString for Duration {
     fn to_pretty_string(&self) -> String {
         let s = self.as_secs_f64();
@@ -23,25 +33,42 @@ impl ToPrettyString for Duration {
     }
 }
 
-impl ToPrettyString for GeocodingResponse {
+impl ToPrettyString for [Geocode] {
     fn to_pretty_string(&self) -> String {
         let mut table = Table::new();
 
         table
-            .load_preset(comfy_table::presets::UTF8_FULL)
-            .set_content_arrangement(comfy_table::ContentArrangement::Dynamic)
-            .set_header(vec![
-                Cell::new("Formatted Address".bold().dimmed()),
-                Cell::new("Latitude".bold().dimmed()),
-                Cell::new("Longitude".bold().dimmed()),
-            ]);
+            .load_preset(presets::UTF8_FULL)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(
+                ["Formatted Address", "Type", "Latitude", "Longitude"]
+                    .map(|header| Cell::new(header.bold().dimmed())),
+            );
+
+        for geocode in self {
+            let (lat, lng) = geocode
+                .location()
+                .map_or_else(Default::default, |(lat, lng)| {
+                    (lat.to_string(), lng.to_string())
+                });
 
-        for result in &self.results {
-            let LatLng { lat, lng } = result.geometry.location;
             table.add_row(vec![
-                Cell::new(result.formatted_address.white()),
-                Cell::new(lat.to_string().bright_cyan()),
-                Cell::new(lng.to_string().bright_cyan()),
+                Cell::new(
+                    geocode
+                        .formatted_address
+                        .as_deref()
+                        .unwrap_or_default()
+                        .white(),
+                ),
+                Cell::new(
+                    geocode
+                        .location_type
+                        .as_deref()
+                        .unwrap_or_default()
+                        .dimmed(),
+                ),
+                Cell::new(lat.bright_cyan()),
+                Cell::new(lng.bright_cyan()),
             ]);
         }
 
@@ -67,3 +94,202 @@ impl ToPrettyString for OcrString {
         table.to_string()
     }
 }
+
+impl ToPrettyString for [Listing] {
+    fn to_pretty_string(&self) -> String {
+        let mut table = Table::new();
+
+        table
+            .load_preset(presets::UTF8_FULL)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(
+                [
+                    "URL",
+                    "Title",
+                    "Price",
+                    "Area",
+                    "Layout",
+                    "Floor",
+                    "District",
+                    "Labels",
+                    "Nearest",
+                    "Station",
+                    "Commute",
+                    "First Seen",
+                ]
+                .map(|header| Cell::new(header.bold().dimmed())),
+            );
+
+        for listing in self {
+            let floor = match (listing.floor, listing.total_floors) {
+                (Some(floor), Some(total)) => format!("{floor}/{total}"),
+                (Some(floor), None) => floor.to_string(),
+                _ => String::new(),
+            };
+
+            let nearest = listing
+                .distances
+                .iter()
+                .min_by(|a, b| a.1.total_cmp(b.1))
+                .map(|(poi, km)| format!("{poi} {km:.1}km"))
+                .unwrap_or_default();
+
+            let commute = listing
+                .commutes
+                .iter()
+                .min_by_key(|(_, minutes)| **minutes)
+                .map(|(poi, minutes)| format!("{poi} {minutes}min"))
+                .unwrap_or_default();
+
+            table.add_row(vec![
+                Cell::new(listing.url.as_str().bright_blue()),
+                Cell::new(listing.title.as_deref().unwrap_or_default().white()),
+                Cell::new(
+                    listing
+                        .price
+                        .map(|price| price.to_string())
+                        .unwrap_or_default()
+                        .bright_cyan(),
+                ),
+                Cell::new(
+                    listing
+                        .area
+                        .map(|area| format!("{area}坪"))
+                        .unwrap_or_default()
+                        .bright_cyan(),
+                ),
+                Cell::new(listing.layout.as_deref().unwrap_or_default()),
+                Cell::new(floor),
+                Cell::new(listing.district.as_deref().unwrap_or_default()),
+                Cell::new(listing.labels.join(", ").dimmed()),
+                Cell::new(nearest.bright_cyan()),
+                Cell::new(
+                    listing
+                        .stations
+                        .first()
+                        .map(|station| format!("{} {}min", station.name, station.walk_minutes))
+                        .unwrap_or_default()
+                        .bright_cyan(),
+                ),
+                Cell::new(commute.bright_cyan()),
+                Cell::new(listing.first_seen.dimmed()),
+            ]);
+        }
+
+        table.to_string()
+    }
+}
+
+impl ToPrettyString for [RentItemSummary] {
+    fn to_pretty_string(&self) -> String {
+        let mut table = Table::new();
+
+        table
+            .load_preset(presets::UTF8_FULL)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(
+                ["URL", "Title", "Price", "Location", "Tags"]
+                    .map(|header| Cell::new(header.bold().dimmed())),
+            );
+
+        for summary in self {
+            table.add_row(vec![
+                Cell::new(summary.url.as_str().bright_blue()),
+                Cell::new(summary.title.as_deref().unwrap_or_default().white()),
+                Cell::new(summary.price.as_deref().unwrap_or_default().bright_cyan()),
+                Cell::new(summary.location().unwrap_or_default()),
+                Cell::new(summary.tags.join(", ").dimmed()),
+            ]);
+        }
+
+        table.to_string()
+    }
+}
+
+impl ToPrettyString for [RentItem] {
+    fn to_pretty_string(&self) -> String {
+        let mut table = Table::new();
+
+        table
+            .load_preset(presets::UTF8_FULL)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(
+                ["URL", "Title", "Layout", "Labels", "Phone"]
+                    .map(|header| Cell::new(header.bold().dimmed())),
+            );
+
+        for item in self {
+            table.add_row(vec![
+                Cell::new(item.url.as_str().bright_blue()),
+                Cell::new(item.title.as_deref().unwrap_or_default().white()),
+                Cell::new(item.patterns.join(" ")),
+                Cell::new(item.labels.join(", ").dimmed()),
+                Cell::new(item.phone.as_deref().unwrap_or_default()),
+            ]);
+        }
+
+        table.to_string()
+    }
+}
+
+impl ToPrettyString for [(ItemAddress, Geocode)] {
+    fn to_pretty_string(&self) -> String {
+        let mut table = Table::new();
+
+        table
+            .load_preset(presets::UTF8_FULL)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_header(
+                [
+                    "URL",
+                    "Address",
+                    "Source",
+                    "District",
+                    "Road",
+                    "Formatted Address",
+                    "Latitude",
+                    "Longitude",
+                ]
+                .map(|header| Cell::new(header.bold().dimmed())),
+            );
+
+        for (address, geocode) in self {
+            let components = address.components();
+            let road = Address {
+                road: components.road,
+                section: components.section,
+                ..Default::default()
+            };
+
+            let (lat, lng) = match geocode.location() {
+                Some((lat, lng)) => (lat.to_string(), lng.to_string()),
+                None => ("not found".red().to_string(), String::new()),
+            };
+
+            table.add_row(vec![
+                Cell::new(address.url.as_str().bright_blue()),
+                Cell::new(address.address.as_str().white()),
+                Cell::new(address.source.as_str().dimmed()),
+                Cell::new(components.district.unwrap_or_default()),
+                Cell::new(road),
+                Cell::new(geocode.formatted_address.as_deref().unwrap_or_default()),
+                Cell::new(lat.bright_cyan()),
+                Cell::new(lng.bright_cyan()),
+            ]);
+        }
+
+        table.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_size() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(2048), "2.0 KiB");
+        assert_eq!(format_size(3 * 1024 * 1024), "3.0 MiB");
+    }
+}
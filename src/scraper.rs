@@ -35,6 +35,9 @@ pub trait HtmlExt {
     fn hide_scripts(&mut self) {
         self.hide_elements(&SELECTORS.script);
     }
+
+    /// Rewrite relative resource and link URLs to absolute ones against `base`
+    fn absolutize_urls(&mut self, base: &Url);
 }
 
 impl HtmlExt for Html {
@@ -52,6 +55,62 @@ impl HtmlExt for Html {
             }
         }
     }
+
+    fn absolutize_urls(&mut self, base: &Url) {
+        let ids: Vec<_> = self
+            .tree
+            .nodes()
+            .filter(|node| node.value().is_element())
+            .map(|node| node.id())
+            .collect();
+
+        for id in ids {
+            let Some(mut node) = self.tree.get_mut(id) else {
+                continue;
+            };
+
+            let Node::Element(element) = node.value() else {
+                continue;
+            };
+
+            for (name, value) in element.attrs.iter_mut() {
+                let rewritten = match &*name.local {
+                    // in-page anchors keep pointing into the cached page
+                    "href" if value.starts_with('#') => None,
+                    "href" | "src" | "data-src" | "action" | "poster" => {
+                        base.join(value).ok().map(String::from)
+                    }
+                    "srcset" => Some(absolutize_srcset(value, base)),
+                    _ => None,
+                };
+
+                if let Some(rewritten) = rewritten {
+                    *value = rewritten.into();
+                }
+            }
+        }
+    }
+}
+
+/// Rewrite each candidate URL of a `srcset` attribute, keeping its descriptor
+fn absolutize_srcset(srcset: &str, base: &Url) -> String {
+    srcset
+        .split(',')
+        .map(str::trim)
+        .filter(|candidate| !candidate.is_empty())
+        .map(
+            |candidate| match candidate.split_once(char::is_whitespace) {
+                Some((url, descriptor)) => match base.join(url) {
+                    Ok(url) => format!("{url} {}", descriptor.trim()),
+                    Err(_) => candidate.to_string(),
+                },
+                None => base
+                    .join(candidate)
+                    .map_or_else(|_| candidate.to_string(), String::from),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join(", ")
 }
 
 pub trait ElementExt {
@@ -269,6 +328,23 @@ mod tests {
         assert_extract_text("hello\tworld", "hello\tworld");
     }
 
+    #[test]
+    fn test_absolutize_urls() {
+        let base = Url::parse("https://rent.591.com.tw/list?region=1").unwrap();
+        let mut document = Html::parse_document(
+            r##"<a href="/123">item</a><img src="img/a.png" srcset="a.png 1x, /b.png 2x"><a href="https://example.com/">ext</a><a href="#top">top</a>"##,
+        );
+        document.absolutize_urls(&base);
+        let html = document.html();
+        assert!(html.contains(r#"href="https://rent.591.com.tw/123""#));
+        assert!(html.contains(r#"src="https://rent.591.com.tw/img/a.png""#));
+        assert!(html.contains(
+            r#"srcset="https://rent.591.com.tw/a.png 1x, https://rent.591.com.tw/b.png 2x""#
+        ));
+        assert!(html.contains(r#"href="https://example.com/""#));
+        assert!(html.contains(r##"href="#top""##));
+    }
+
     #[test]
     fn test_mixed_elements() {
         assert_extract_text(
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use serde_json::{Map, Value, json};
+use url::Url;
+
+use crate::geocode::{Distances, NearbyStation};
+use crate::sites::rent591::{RentItem, RentItemSummary};
+use crate::transit::Commutes;
+
+/// Columns of the flat export schema, one row per item in the latest list snapshot
+///
+/// Summary columns come from the list page, detail columns from the item page and are
+/// empty until `rentmap item` has scraped the item.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum Column {
+    /// Item page URL
+    Url,
+    /// Item title, from the item page if scraped, otherwise from the list page
+    Title,
+    /// Price text shown on the list page
+    Price,
+    /// Tags shown on the list page
+    Tags,
+    /// Info lines shown on the list page (layout, area, floor, location)
+    Txts,
+    /// Thumbnail image URLs from the list page
+    Images,
+    /// Whether the item page has been scraped
+    Scraped,
+    /// House labels from the item page
+    Labels,
+    /// Layout patterns from the item page
+    Patterns,
+    /// Full description from the item page
+    Content,
+    /// Contact phone from the item page
+    Phone,
+    /// Photo album URLs from the item page
+    Album,
+    /// Image URL of the obfuscated area
+    AreaImage,
+    /// Image URL of the obfuscated floor
+    FloorImage,
+    /// Image URL of the obfuscated price
+    PriceImage,
+    /// Image URL of the obfuscated address
+    AddressImage,
+    /// Kilometers to each point of interest, computed by `rentmap distances`
+    Distances,
+    /// Nearest station of each line with walking meters and minutes, from `rentmap stations`
+    Stations,
+    /// Public transit minutes to each destination, computed by `rentmap commute`
+    Commutes,
+}
+
+impl Column {
+    /// Header of the column, the same as its `--columns` value
+    pub fn name(self) -> String {
+        self.to_possible_value()
+            .expect("no skipped columns")
+            .get_name()
+            .to_string()
+    }
+}
+
+/// An item summary joined with its scraped details, if any
+#[derive(Clone, Debug)]
+pub struct ExportRecord {
+    pub summary: RentItemSummary,
+    pub item: Option<RentItem>,
+    /// Empty until set with `set_by_url`
+    pub distances: Distances,
+    /// Nearest first, empty until set with `set_by_url`
+    pub stations: Vec<NearbyStation>,
+    /// Empty until set with `set_by_url`
+    pub commutes: Commutes,
+}
+
+impl ExportRecord {
+    /// Join summaries with items by URL, keeping the order of the summaries
+    pub fn join(summaries: Vec<RentItemSummary>, items: Vec<RentItem>) -> Vec<Self> {
+        let mut items: HashMap<Url, RentItem> = items
+            .into_iter()
+            .map(|item| (item.url.0.clone(), item))
+            .collect();
+
+        summaries
+            .into_iter()
+            .map(|summary| {
+                let item = items.remove(&summary.url.0);
+                Self {
+                    summary,
+                    item,
+                    distances: Distances::new(),
+                    stations: Vec::new(),
+                    commutes: Commutes::new(),
+                }
+            })
+            .collect()
+    }
+
+    /// Set a field of each record by URL, e.g. the distances with `|record| &mut record.distances`
+    pub fn set_by_url<T>(
+        records: &mut [Self],
+        mut values: HashMap<Url, T>,
+        field: impl Fn(&mut Self) -> &mut T,
+    ) {
+        for record in records {
+            if let Some(value) = values.remove(&record.summary.url.0) {
+                *field(record) = value;
+            }
+        }
+    }
+
+    /// Get the value of a column
+    pub fn value(&self, column: Column) -> Value {
+        let item = self.item.as_ref();
+
+        match column {
+            Column::Url => json!(self.summary.url.as_str()),
+            Column::Title => json!(
+                item.and_then(|item| item.title.as_ref())
+                    .or(self.summary.title.as_ref())
+            ),
+            Column::Price => json!(self.summary.price),
+            Column::Tags => json!(self.summary.tags.0),
+            Column::Txts => json!(self.summary.txts.0),
+            Column::Images => json!(self.summary.images.0),
+            Column::Scraped => json!(item.is_some()),
+            Column::Labels => json!(item.map(|item| &item.labels.0)),
+            Column::Patterns => json!(item.map(|item| &item.patterns.0)),
+            Column::Content => json!(item.map(|item| &item.content)),
+            Column::Phone => json!(item.and_then(|item| item.phone.as_ref())),
+            Column::Album => json!(item.map(|item| &item.album.0)),
+            Column::AreaImage => json!(item.and_then(|item| item.area.as_deref())),
+            Column::FloorImage => json!(item.and_then(|item| item.floor.as_deref())),
+            Column::PriceImage => json!(item.and_then(|item| item.price.as_deref())),
+            Column::AddressImage => json!(item.and_then(|item| item.address.as_deref())),
+            Column::Distances => json!(self.distances),
+            Column::Commutes => json!(self.commutes),
+            Column::Stations => json!(
+                self.stations
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+            ),
+        }
+    }
+
+    /// Build a JSON object with the given columns
+    pub fn to_json(&self, columns: &[Column]) -> Value {
+        let map: Map<_, _> = columns
+            .iter()
+            .map(|&column| (column.name(), self.value(column)))
+            .collect();
+
+        Value::Object(map)
+    }
+
+    /// Build a CSV row with the given columns, joining lists with `; ` and maps as `key=value`
+    pub fn to_csv_row(&self, columns: &[Column]) -> Vec<String> {
+        columns
+            .iter()
+            .map(|&column| to_csv_field(self.value(column)))
+            .collect()
+    }
+}
+
+fn to_csv_field(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s,
+        Value::Array(values) => values
+            .into_iter()
+            .map(to_csv_field)
+            .collect::<Vec<_>>()
+            .join("; "),
+        Value::Object(map) => map
+            .into_iter()
+            .map(|(key, value)| format!("{key}={}", to_csv_field(value)))
+            .collect::<Vec<_>>()
+            .join("; "),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(url: &str) -> RentItemSummary {
+        RentItemSummary::new(
+            Url::parse(url).unwrap(),
+            Some("summary title".to_string()),
+            Some("12,000 元/月".to_string()),
+            vec!["近捷運".to_string(), "可養寵物".to_string()],
+            vec![],
+            vec![],
+        )
+    }
+
+    fn item(url: &str) -> RentItem {
+        RentItem::new(
+            Url::parse(url).unwrap(),
+            Some("item title".to_string()),
+            vec![],
+            vec![],
+            "content".to_string(),
+            None,
+            vec![],
+            None,
+            None,
+            None,
+            None,
+        )
+    }
+
+    #[test]
+    fn test_join_keeps_summary_order() {
+        let records = ExportRecord::join(
+            vec![
+                summary("https://rent.591.com.tw/2"),
+                summary("https://rent.591.com.tw/1"),
+            ],
+            vec![item("https://rent.591.com.tw/1")],
+        );
+        assert_eq!(records.len(), 2);
+        assert!(records[0].item.is_none());
+        assert!(records[1].item.is_some());
+    }
+
+    #[test]
+    fn test_title_prefers_item() {
+        let records = ExportRecord::join(
+            vec![summary("https://rent.591.com.tw/1")],
+            vec![item("https://rent.591.com.tw/1")],
+        );
+        assert_eq!(records[0].value(Column::Title), json!("item title"));
+    }
+
+    #[test]
+    fn test_csv_row() {
+        let mut records = ExportRecord::join(vec![summary("https://rent.591.com.tw/1")], vec![]);
+        let row = records[0].to_csv_row(&[Column::Url, Column::Tags, Column::Content]);
+        assert_eq!(row, ["https://rent.591.com.tw/1", "近捷運; 可養寵物", ""]);
+
+        let url = Url::parse("https://rent.591.com.tw/1").unwrap();
+        let distances = Distances::from([("gym".to_string(), 0.5), ("office".to_string(), 2.25)]);
+        ExportRecord::set_by_url(&mut records, HashMap::from([(url, distances)]), |record| {
+            &mut record.distances
+        });
+        let row = records[0].to_csv_row(&[Column::Distances]);
+        assert_eq!(row, ["gym=0.5; office=2.25"]);
+    }
+
+    #[test]
+    fn test_column_names() {
+        assert_eq!(Column::AddressImage.name(), "address_image");
+        assert_eq!(
+            Column::from_str("address_image", false),
+            Ok(Column::AddressImage)
+        );
+    }
+}
@@ -1,9 +1,15 @@
+mod export;
 mod model;
+mod query;
 mod scrape;
+mod search;
 mod url;
 mod view;
 
-pub use model::{RentItem, RentItemSummary, RentList, RentListPage};
+pub use export::{Column, ExportRecord};
+pub use model::{RentItem, RentItemSummary, RentList, RentListPage, RentRecord, Stamped};
+pub use query::{Listing, ListingFilter, NamedLimit, SortKey, WalkLimit};
 pub use scrape::{scrape_item, scrape_items, scrape_list_and_pages};
+pub use search::{MATCH_END, MATCH_START, SearchHit, SearchQuery};
 pub use url::{ListUrlExt, Rent591Url, UrlError};
 pub use view::{ItemView, ListView, ViewError};
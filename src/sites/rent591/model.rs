@@ -5,6 +5,9 @@ use sqlx::FromRow;
 use sqlx::types::Json;
 use url::Url;
 
+use super::query::parse_district;
+use crate::geocode::Address;
+
 #[derive(Clone, Debug, Deserialize, Serialize, FromRow)]
 pub struct RentList {
     pub url: Json<Url>,
@@ -58,7 +61,7 @@ impl RentListPage {
     }
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, FromRow)]
 pub struct RentItemSummary {
     pub url: Json<Url>,
     pub title: Option<String>,
@@ -87,6 +90,22 @@ impl RentItemSummary {
             images: Json(images),
         }
     }
+
+    /// The location shown on the list page, like `大安區-復興南路二段`, or failing that the
+    /// first token that parses as an address with a district and road, like `大安區復興南路二段`
+    pub fn location(&self) -> Option<&str> {
+        let mut tokens = self.txts.iter().flat_map(|txt| txt.split_whitespace());
+
+        tokens
+            .clone()
+            .find(|token| parse_district(token).is_some())
+            .or_else(|| {
+                tokens.find(|token| {
+                    let address = Address::parse(token);
+                    address.district.is_some() && address.road.is_some()
+                })
+            })
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize, FromRow)]
@@ -135,3 +154,21 @@ impl RentItem {
         }
     }
 }
+
+/// A record with the `created_at` timestamp it was taken at, if known
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Stamped<T> {
+    #[serde(flatten)]
+    pub value: T,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<String>,
+}
+
+/// A list snapshot or an item, as written by `rentmap export --records` and read by
+/// `rentmap import`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum RentRecord {
+    List(Stamped<RentList>),
+    Item(Box<Stamped<RentItem>>),
+}
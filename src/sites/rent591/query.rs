@@ -0,0 +1,617 @@
+use std::cmp::Ordering;
+use std::str::FromStr;
+
+use chrono::NaiveDate;
+use clap::{Args, ValueEnum};
+use serde::Serialize;
+use url::Url;
+
+use crate::geocode::{Address, Distances, NearbyStation, line_code};
+use crate::sites::rent591::ExportRecord;
+use crate::transit::Commutes;
+
+/// A stored listing with the facts parsed from its list page text
+///
+/// Price, area, layout, floor and district are only shown as text on 591, so they are
+/// parsed from the summary price and info lines (e.g. `2房1廳 28.5坪 5F/7F`,
+/// `大安區-復興南路二段`) and are `None` when the text does not match. The layout falls back
+/// to the patterns of the item page once it is scraped.
+#[derive(Clone, Debug, Serialize)]
+pub struct Listing {
+    pub url: Url,
+    pub title: Option<String>,
+    /// Monthly rent in NTD
+    pub price: Option<u32>,
+    /// Floor area in ping (坪)
+    pub area: Option<f64>,
+    pub layout: Option<String>,
+    /// Floor number, negative for basements
+    pub floor: Option<i32>,
+    pub total_floors: Option<i32>,
+    pub district: Option<String>,
+    /// House labels from the item page and tags from the list page
+    pub labels: Vec<String>,
+    /// When the listing first appeared in a stored list snapshot (UTC)
+    pub first_seen: String,
+    /// Whether the item page has been scraped
+    pub scraped: bool,
+    /// Kilometers to each point of interest, empty until `rentmap distances` has run
+    pub distances: Distances,
+    /// Nearest station of each line in walking distance, nearest first, empty until
+    /// `rentmap stations` has run
+    pub stations: Vec<NearbyStation>,
+    /// Public transit minutes to each destination, empty until `rentmap commute` has run
+    pub commutes: Commutes,
+}
+
+impl Listing {
+    pub fn new(record: ExportRecord, first_seen: String) -> Self {
+        let ExportRecord {
+            summary,
+            item,
+            distances,
+            stations,
+            commutes,
+        } = record;
+
+        let tokens: Vec<&str> = summary
+            .txts
+            .iter()
+            .flat_map(|txt| txt.split_whitespace())
+            .collect();
+
+        let (floor, total_floors) = tokens.iter().find_map(|token| parse_floor(token)).unzip();
+
+        let layout = tokens
+            .iter()
+            .copied()
+            .chain(
+                item.iter()
+                    .flat_map(|item| item.patterns.iter().map(String::as_str)),
+            )
+            .find(|token| is_layout(token))
+            .map(str::to_string);
+
+        let district = tokens
+            .iter()
+            .find_map(|token| parse_district(token))
+            .or_else(|| Address::parse(summary.location()?).district);
+
+        let mut labels: Vec<String> = item
+            .as_ref()
+            .map(|item| item.labels.0.clone())
+            .unwrap_or_default();
+        for tag in summary.tags.0 {
+            if !labels.contains(&tag) {
+                labels.push(tag);
+            }
+        }
+
+        Self {
+            url: summary.url.0,
+            title: item
+                .as_ref()
+                .and_then(|item| item.title.clone())
+                .or(summary.title),
+            price: summary.price.as_deref().and_then(parse_price),
+            area: tokens.iter().find_map(|token| parse_area(token)),
+            layout,
+            floor,
+            total_floors,
+            district,
+            labels,
+            first_seen,
+            scraped: item.is_some(),
+            distances,
+            stations,
+            commutes,
+        }
+    }
+
+    /// Kilometers to a point of interest, or to the nearest one if `None`
+    pub fn distance(&self, poi: Option<&str>) -> Option<f64> {
+        match poi {
+            Some(poi) => self.distances.get(poi).copied(),
+            None => self.distances.values().copied().reduce(f64::min),
+        }
+    }
+
+    /// Commute minutes to a destination, or to the quickest one if `None`
+    pub fn commute(&self, poi: Option<&str>) -> Option<u32> {
+        match poi {
+            Some(poi) => self.commutes.get(poi).copied(),
+            None => self.commutes.values().copied().min(),
+        }
+    }
+
+    /// Walking meters to the nearest station of a line, or of any line if `None`
+    pub fn walk_to_station(&self, line: Option<&str>) -> Option<u32> {
+        self.stations
+            .iter()
+            .filter(|station| line.is_none_or(|line| station.line == line))
+            .map(|station| station.walk_m)
+            .min()
+    }
+}
+
+/// Parse the monthly rent from price text like `12,000 元/月`
+fn parse_price(text: &str) -> Option<u32> {
+    let digits: String = text
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit() || *c == ',')
+        .filter(char::is_ascii_digit)
+        .collect();
+
+    digits.parse().ok()
+}
+
+/// Parse the area from a token like `28.5坪`
+fn parse_area(token: &str) -> Option<f64> {
+    token.strip_suffix('坪')?.parse().ok()
+}
+
+/// Whether a token describes the layout, like `2房1廳1衛` or `開放格局`
+///
+/// A room count must precede `房`, so kinds like `獨立套房` and `分租套房` are not layouts.
+fn is_layout(token: &str) -> bool {
+    let has_rooms = token
+        .match_indices('房')
+        .any(|(i, _)| token[..i].ends_with(|c: char| c.is_ascii_digit()));
+
+    has_rooms || token.contains("格局")
+}
+
+/// Parse a floor and total floor count from a token like `5F/7F` or `B1/12F`
+fn parse_floor(token: &str) -> Option<(i32, i32)> {
+    let (floor, total) = token.split_once('/')?;
+
+    let floor = match floor.strip_prefix('B') {
+        Some(basement) => -basement
+            .strip_suffix('F')
+            .unwrap_or(basement)
+            .parse::<i32>()
+            .ok()?,
+        None => floor.strip_suffix('F')?.parse().ok()?,
+    };
+
+    let total = total.strip_suffix('F')?.parse().ok()?;
+
+    Some((floor, total))
+}
+
+/// Parse the district from a location token like `大安區-復興南路二段`
+pub(super) fn parse_district(token: &str) -> Option<String> {
+    let (district, _) = token.split_once('-')?;
+
+    district
+        .ends_with(['區', '鄉', '鎮', '市'])
+        .then(|| district.to_string())
+}
+
+/// Filters over stored listings, all of which must match
+#[derive(Clone, Debug, Default, Args)]
+#[command(next_help_heading = "Filters")]
+pub struct ListingFilter {
+    /// Minimum monthly rent in NTD
+    #[arg(long)]
+    pub min_price: Option<u32>,
+
+    /// Maximum monthly rent in NTD
+    #[arg(long)]
+    pub max_price: Option<u32>,
+
+    /// Minimum area in ping
+    #[arg(long)]
+    pub min_area: Option<f64>,
+
+    /// Maximum area in ping
+    #[arg(long)]
+    pub max_area: Option<f64>,
+
+    /// Layout containing this text (e.g., 2房, 開放格局)
+    #[arg(long)]
+    pub layout: Option<String>,
+
+    /// Minimum floor, negative for basements
+    #[arg(long, allow_negative_numbers = true)]
+    pub min_floor: Option<i32>,
+
+    /// Maximum floor, negative for basements
+    #[arg(long, allow_negative_numbers = true)]
+    pub max_floor: Option<i32>,
+
+    /// Label or tag the listing must have (e.g., 可養寵物), repeat to require several
+    #[arg(long = "label")]
+    pub labels: Vec<String>,
+
+    /// District containing this text (e.g., 大安)
+    #[arg(long)]
+    pub district: Option<String>,
+
+    /// Only listings first seen on or after this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub posted_after: Option<NaiveDate>,
+
+    /// Maximum kilometers to a point of interest as NAME=KM (e.g., office=3), repeat to
+    /// limit several
+    #[arg(long = "max-distance", value_name = "NAME=KM")]
+    pub max_distances: Vec<NamedLimit<f64>>,
+
+    /// Maximum walking meters to a station as METERS, or LINE=METERS for a line by code or
+    /// name (e.g., R=600, red=600, 文湖=800), repeat to limit several
+    #[arg(long = "max-walk", value_name = "[LINE=]METERS")]
+    pub max_walks: Vec<WalkLimit>,
+
+    /// Maximum public transit minutes to a destination as NAME=MINUTES (e.g., office=40),
+    /// repeat to limit several
+    #[arg(long = "max-commute", value_name = "NAME=MINUTES")]
+    pub max_commutes: Vec<NamedLimit<u32>>,
+}
+
+/// A maximum walking distance to a station, of a line or of any line
+#[derive(Clone, Debug, PartialEq)]
+pub struct WalkLimit {
+    /// Line code, any line if `None`
+    pub line: Option<String>,
+    pub meters: u32,
+}
+
+impl FromStr for WalkLimit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (line, meters) = match s.rsplit_once('=') {
+            Some((line, meters)) => (Some(line_code(line)), meters),
+            None => (None, s),
+        };
+        let meters = meters
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid walking distance {meters:?}"))?;
+
+        Ok(Self { line, meters })
+    }
+}
+
+/// A maximum value for a named point of interest, like kilometers or commute minutes to it
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamedLimit<T> {
+    pub name: String,
+    pub max: T,
+}
+
+impl<T: FromStr> FromStr for NamedLimit<T> {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, max) = s
+            .rsplit_once('=')
+            .ok_or_else(|| format!("expected NAME=VALUE, got {s:?}"))?;
+        let max = max
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid value {max:?} for {name:?}"))?;
+
+        Ok(Self {
+            name: name.trim().to_string(),
+            max,
+        })
+    }
+}
+
+impl ListingFilter {
+    /// Whether a listing passes all filters, a listing missing a filtered fact never does
+    pub fn matches(&self, listing: &Listing) -> bool {
+        fn in_range<T: PartialOrd>(value: Option<T>, min: Option<T>, max: Option<T>) -> bool {
+            if min.is_none() && max.is_none() {
+                return true;
+            }
+            value.is_some_and(|value| {
+                min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+            })
+        }
+
+        fn contains(value: Option<&str>, pattern: Option<&str>) -> bool {
+            pattern.is_none_or(|pattern| value.is_some_and(|value| value.contains(pattern)))
+        }
+
+        in_range(listing.price, self.min_price, self.max_price)
+            && in_range(listing.area, self.min_area, self.max_area)
+            && in_range(listing.floor, self.min_floor, self.max_floor)
+            && contains(listing.layout.as_deref(), self.layout.as_deref())
+            && contains(listing.district.as_deref(), self.district.as_deref())
+            && self
+                .labels
+                .iter()
+                .all(|label| listing.labels.iter().any(|l| l.contains(label.as_str())))
+            && self.max_distances.iter().all(|limit| {
+                listing
+                    .distance(Some(&limit.name))
+                    .is_some_and(|km| km <= limit.max)
+            })
+            && self.max_walks.iter().all(|limit| {
+                listing
+                    .walk_to_station(limit.line.as_deref())
+                    .is_some_and(|meters| meters <= limit.meters)
+            })
+            && self.max_commutes.iter().all(|limit| {
+                listing
+                    .commute(Some(&limit.name))
+                    .is_some_and(|minutes| minutes <= limit.max)
+            })
+    }
+}
+
+/// Field to sort listings by
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+#[value(rename_all = "snake_case")]
+pub enum SortKey {
+    /// Item page URL
+    #[default]
+    Url,
+    /// Monthly rent
+    Price,
+    /// Area in ping
+    Area,
+    /// Floor number
+    Floor,
+    /// First time seen in a list snapshot
+    FirstSeen,
+    /// Kilometers to the point of interest given by `--poi`, or to the nearest one
+    Distance,
+    /// Walking meters to the nearest station
+    Station,
+    /// Commute minutes to the destination given by `--poi`, or to the quickest one
+    Commute,
+}
+
+impl SortKey {
+    /// Sort listings by this key, keeping listings without the value last
+    ///
+    /// `poi` names the point of interest to sort by distance or commute to, the nearest or
+    /// quickest one if `None`.
+    pub fn sort(self, listings: &mut [Listing], descending: bool, poi: Option<&str>) {
+        fn by<T: PartialOrd>(a: Option<T>, b: Option<T>, descending: bool) -> Ordering {
+            match (a, b) {
+                (Some(a), Some(b)) => {
+                    let ordering = a.partial_cmp(&b).unwrap_or(Ordering::Equal);
+                    if descending {
+                        ordering.reverse()
+                    } else {
+                        ordering
+                    }
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            }
+        }
+
+        listings.sort_by(|a, b| match self {
+            Self::Url => by(Some(a.url.as_str()), Some(b.url.as_str()), descending),
+            Self::Price => by(a.price, b.price, descending),
+            Self::Area => by(a.area, b.area, descending),
+            Self::Floor => by(a.floor, b.floor, descending),
+            Self::FirstSeen => by(Some(&a.first_seen), Some(&b.first_seen), descending),
+            Self::Distance => by(a.distance(poi), b.distance(poi), descending),
+            Self::Station => by(a.walk_to_station(None), b.walk_to_station(None), descending),
+            Self::Commute => by(a.commute(poi), b.commute(poi), descending),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::sites::rent591::{RentItem, RentItemSummary};
+
+    fn listing(price: &str, txts: &[&str]) -> Listing {
+        let summary = RentItemSummary::new(
+            Url::parse("https://rent.591.com.tw/1").unwrap(),
+            Some("title".to_string()),
+            Some(price.to_string()),
+            vec!["近捷運".to_string()],
+            txts.iter().map(|txt| txt.to_string()).collect(),
+            vec![],
+        );
+        let record = ExportRecord {
+            summary,
+            item: None,
+            distances: Distances::new(),
+            stations: Vec::new(),
+            commutes: Commutes::new(),
+        };
+        Listing::new(record, "2025-01-01 00:00:00".to_string())
+    }
+
+    #[test]
+    fn test_parse_listing() {
+        let listing = listing(
+            "12,000 元/月",
+            &["整層住家 2房1廳 28.5坪 5F/7F", "大安區-復興南路二段"],
+        );
+        assert_eq!(listing.price, Some(12000));
+        assert_eq!(listing.area, Some(28.5));
+        assert_eq!(listing.layout.as_deref(), Some("2房1廳"));
+        assert_eq!(listing.floor, Some(5));
+        assert_eq!(listing.total_floors, Some(7));
+        assert_eq!(listing.district.as_deref(), Some("大安區"));
+    }
+
+    #[test]
+    fn test_summary_location() {
+        let summary = RentItemSummary::new(
+            Url::parse("https://rent.591.com.tw/1").unwrap(),
+            None,
+            None,
+            vec![],
+            vec![
+                "2房1廳 28.5坪".to_string(),
+                "大安區-復興南路二段".to_string(),
+            ],
+            vec![],
+        );
+        assert_eq!(summary.location(), Some("大安區-復興南路二段"));
+    }
+
+    #[test]
+    fn test_layout_needs_room_count() {
+        let studio = listing("", &["獨立套房 8坪 3F/5F"]);
+        assert_eq!(studio.layout, None);
+
+        let shared = listing("", &["分租套房 3房1廳 30坪"]);
+        assert_eq!(shared.layout.as_deref(), Some("3房1廳"));
+        assert!(is_layout("開放格局"));
+    }
+
+    #[test]
+    fn test_fall_back_to_item_and_address() {
+        let unscraped = listing("", &["整層住家 28.5坪", "大安區復興南路二段"]);
+        assert_eq!(unscraped.layout, None);
+        assert_eq!(unscraped.district.as_deref(), Some("大安區"));
+
+        let summary =
+            RentItemSummary::new(unscraped.url.clone(), None, None, vec![], vec![], vec![]);
+        let item = RentItem::new(
+            unscraped.url.clone(),
+            None,
+            vec![],
+            vec!["1房1廳".to_string()],
+            String::new(),
+            None,
+            vec![],
+            None,
+            None,
+            None,
+            None,
+        );
+        let scraped = Listing::new(
+            ExportRecord {
+                summary,
+                item: Some(item),
+                distances: Distances::new(),
+                stations: Vec::new(),
+                commutes: Commutes::new(),
+            },
+            unscraped.first_seen,
+        );
+        assert_eq!(scraped.layout.as_deref(), Some("1房1廳"));
+        assert!(
+            ListingFilter {
+                layout: Some("1房".to_string()),
+                ..Default::default()
+            }
+            .matches(&scraped)
+        );
+    }
+
+    #[test]
+    fn test_parse_basement_floor() {
+        assert_eq!(parse_floor("B1/12F"), Some((-1, 12)));
+        assert_eq!(parse_floor("頂樓加蓋"), None);
+    }
+
+    #[test]
+    fn test_filter() {
+        let listing = listing("9,500 元/月", &["獨立套房 8坪 3F/5F", "中山區-林森北路"]);
+
+        let filter = ListingFilter {
+            max_price: Some(10000),
+            min_area: Some(6.0),
+            district: Some("中山".to_string()),
+            labels: vec!["捷運".to_string()],
+            ..Default::default()
+        };
+        assert!(filter.matches(&listing));
+
+        let filter = ListingFilter {
+            layout: Some("2房".to_string()),
+            ..Default::default()
+        };
+        assert!(!filter.matches(&listing));
+    }
+
+    #[test]
+    fn test_sort_missing_last() {
+        let mut listings = vec![
+            listing("", &[]),
+            listing("8,000 元/月", &[]),
+            listing("12,000 元/月", &[]),
+        ];
+        SortKey::Price.sort(&mut listings, true, None);
+        let prices: Vec<_> = listings.iter().map(|listing| listing.price).collect();
+        assert_eq!(prices, [Some(12000), Some(8000), None]);
+    }
+
+    #[derive(Parser)]
+    #[command(no_binary_name = true)]
+    struct Filter {
+        #[command(flatten)]
+        filter: ListingFilter,
+    }
+
+    #[test]
+    fn test_location_filter_and_sort() {
+        let station = |line: &str, walk_m| NearbyStation {
+            line: line.to_string(),
+            code: format!("{line}01"),
+            name: "station".to_string(),
+            distance_m: walk_m * 4 / 5,
+            walk_m,
+            walk_minutes: walk_m.div_ceil(80),
+        };
+
+        // near the office and by the red line, near the gym and by the blue line, unknown
+        let mut listings: Vec<_> = (0..3)
+            .map(|i| {
+                let mut listing = listing("", &[]);
+                listing.url = Url::parse(&format!("https://rent.591.com.tw/{i}")).unwrap();
+                listing
+            })
+            .collect();
+        listings[0].distances =
+            Distances::from([("office".to_string(), 1.0), ("gym".to_string(), 4.0)]);
+        listings[0].stations = vec![station("BL", 300), station("R", 550)];
+        listings[0].commutes =
+            Commutes::from([("office".to_string(), 25), ("gym".to_string(), 40)]);
+        listings[1].distances =
+            Distances::from([("office".to_string(), 6.0), ("gym".to_string(), 0.5)]);
+        listings[1].stations = vec![station("BL", 200)];
+        listings[1].commutes =
+            Commutes::from([("office".to_string(), 55), ("gym".to_string(), 10)]);
+
+        let filters = [
+            ("--max-distance office=3", [true, false, false]),
+            ("--max-walk red=600", [true, false, false]),
+            ("--max-walk 250", [false, true, false]),
+            ("--max-commute office=40", [true, false, false]),
+        ];
+        for (args, expected) in filters {
+            let filter = Filter::parse_from(args.split_whitespace()).filter;
+            let matches: Vec<_> = listings.iter().map(|l| filter.matches(l)).collect();
+            assert_eq!(matches, expected, "{args}");
+        }
+
+        let sorts = [
+            (SortKey::Distance, Some("office"), ["0", "1", "2"]),
+            (SortKey::Distance, None, ["1", "0", "2"]),
+            (SortKey::Station, None, ["1", "0", "2"]),
+            (SortKey::Commute, Some("office"), ["0", "1", "2"]),
+            (SortKey::Commute, None, ["1", "0", "2"]),
+        ];
+        for (key, poi, expected) in sorts {
+            let mut listings = listings.clone();
+            listings.reverse();
+            key.sort(&mut listings, false, poi);
+            let order: Vec<_> = listings.iter().map(|l| &l.url.path()[1..]).collect();
+            assert_eq!(order, expected, "{key:?} {poi:?}");
+        }
+
+        assert!("office".parse::<NamedLimit<f64>>().is_err());
+        assert!("office=soon".parse::<NamedLimit<u32>>().is_err());
+        assert!("R=near".parse::<WalkLimit>().is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use sqlx::FromRow;
+use sqlx::types::Json;
+use url::Url;
+
+/// Marks the start of a highlighted match in a snippet
+pub const MATCH_START: char = '\u{2}';
+
+/// Marks the end of a highlighted match in a snippet
+pub const MATCH_END: char = '\u{3}';
+
+/// The trigram tokenizer only matches terms of at least this many characters
+const MIN_TRIGRAM_CHARS: usize = 3;
+
+/// A full-text search query split into terms, all of which must match
+///
+/// Terms of three or more characters are matched through a trigram index (FTS5 on SQLite,
+/// pg_trgm on PostgreSQL), shorter ones (common for CJK words like `捷運`) fall back to `LIKE`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchQuery {
+    pub indexed: Vec<String>,
+    pub unindexed: Vec<String>,
+}
+
+impl SearchQuery {
+    pub fn parse(query: &str) -> Self {
+        let (indexed, unindexed) = query
+            .split_whitespace()
+            .map(str::to_string)
+            .partition(|term| term.chars().count() >= MIN_TRIGRAM_CHARS);
+
+        Self { indexed, unindexed }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.indexed.is_empty() && self.unindexed.is_empty()
+    }
+
+    /// FTS5 `MATCH` expression for the indexed terms, each quoted as a phrase
+    pub fn match_expr(&self) -> Option<String> {
+        (!self.indexed.is_empty()).then(|| {
+            self.indexed
+                .iter()
+                .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+
+    /// `LIKE` patterns for the unindexed terms
+    pub fn like_patterns(&self) -> impl Iterator<Item = String> {
+        self.unindexed.iter().map(|term| like_pattern(term))
+    }
+
+    /// `LIKE` patterns for the indexed terms, for databases matching them with a trigram
+    /// index over `LIKE` rather than with FTS5
+    pub fn indexed_patterns(&self) -> impl Iterator<Item = String> {
+        self.indexed.iter().map(|term| like_pattern(term))
+    }
+}
+
+/// A `LIKE` pattern matching a term anywhere, escaping its wildcards with `\`
+fn like_pattern(term: &str) -> String {
+    let escaped = term
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{escaped}%")
+}
+
+/// A scraped item matching a full-text search
+///
+/// Matches in `snippet` are wrapped in [`MATCH_START`] and [`MATCH_END`].
+#[derive(Clone, Debug, FromRow)]
+pub struct SearchHit {
+    pub url: Json<Url>,
+    pub title: Option<String>,
+    pub snippet: String,
+    /// BM25 score on SQLite, a weighted count of the fields matching each term on
+    /// PostgreSQL, lower is more relevant either way
+    pub rank: f64,
+}
+
+impl SearchHit {
+    /// Mark terms in the snippet, for those the database cannot highlight itself
+    pub fn highlight(&mut self, terms: &[String]) {
+        for term in terms {
+            self.snippet = self
+                .snippet
+                .replace(term, &format!("{MATCH_START}{term}{MATCH_END}"));
+        }
+    }
+
+    /// Split the snippet into fragments, flagging the highlighted ones
+    pub fn fragments(&self) -> impl Iterator<Item = (&str, bool)> {
+        self.snippet
+            .split(MATCH_START)
+            .enumerate()
+            .flat_map(|(i, part)| match part.split_once(MATCH_END) {
+                Some((matched, rest)) if i > 0 => vec![(matched, true), (rest, false)],
+                _ => vec![(part, false)],
+            })
+            .filter(|(fragment, _)| !fragment.is_empty())
+    }
+
+    /// Render the snippet with the given markers around matches
+    pub fn snippet_with(&self, start: &str, end: &str) -> String {
+        self.fragments()
+            .map(|(fragment, matched)| {
+                if matched {
+                    format!("{start}{fragment}{end}")
+                } else {
+                    fragment.to_string()
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_query() {
+        let query = SearchQuery::parse("可養寵物  近捷運 電梯");
+        assert_eq!(query.indexed, ["可養寵物", "近捷運"]);
+        assert_eq!(query.unindexed, ["電梯"]);
+        assert_eq!(query.match_expr().unwrap(), r#""可養寵物" "近捷運""#);
+    }
+
+    #[test]
+    fn test_like_patterns_escape() {
+        let query = SearchQuery::parse("5% a_b");
+        let patterns: Vec<_> = query.like_patterns().collect();
+        assert_eq!(patterns, [r"%5\%%"]);
+        assert_eq!(query.match_expr().unwrap(), r#""a_b""#);
+        assert_eq!(query.indexed_patterns().collect::<Vec<_>>(), [r"%a\_b%"]);
+    }
+
+    #[test]
+    fn test_snippet_with() {
+        let mut hit = SearchHit {
+            url: Json(Url::parse("https://rent.591.com.tw/1").unwrap()),
+            title: None,
+            snippet: format!("…{MATCH_START}可養寵物{MATCH_END}，有電梯…"),
+            rank: -1.0,
+        };
+        hit.highlight(&SearchQuery::parse("電梯").unindexed);
+        assert_eq!(hit.snippet_with("[", "]"), "…[可養寵物]，有[電梯]…");
+    }
+}
@@ -0,0 +1,50 @@
+use chrono::NaiveDate;
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::file::PathError;
+
+#[derive(Debug, Error, Diagnostic)]
+pub enum TransitError {
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Path(#[from] PathError),
+
+    #[error("invalid GTFS zip")]
+    #[diagnostic(
+        code(transit::zip),
+        help("the feed must be a zip archive with the GTFS files at its root")
+    )]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("no {0} in GTFS feed")]
+    #[diagnostic(
+        code(transit::missing_file),
+        help(
+            "a feed needs stops.txt, trips.txt, stop_times.txt and calendar.txt or calendar_dates.txt"
+        )
+    )]
+    MissingFile(&'static str),
+
+    #[error("invalid {file} in GTFS feed")]
+    #[diagnostic(code(transit::invalid_file))]
+    InvalidFile {
+        file: &'static str,
+        #[source]
+        source: csv::Error,
+    },
+
+    #[error("invalid {field} {value:?} in GTFS feed")]
+    #[diagnostic(
+        code(transit::invalid_value),
+        help("times are HH:MM:SS, past 24:00:00 after midnight, and dates are YYYYMMDD")
+    )]
+    InvalidValue { field: &'static str, value: String },
+
+    #[error("no service runs on {0}")]
+    #[diagnostic(
+        code(transit::no_service),
+        help("choose a `--date` within the calendar of the feed")
+    )]
+    NoService(NaiveDate),
+}
@@ -0,0 +1,493 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use tracing::info;
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+use super::TransitError;
+use crate::file::PathError;
+use crate::geocode::{WALKING_SPEED, haversine_km, walking_meters};
+
+/// Grid cells are about 1 km across, in degrees
+const CELL_DEGREES: f64 = 0.01;
+
+/// Kilometers in a degree of latitude
+const KM_PER_DEGREE: f64 = 111.2;
+
+/// A stop where trips pick up and drop off
+#[derive(Clone, Debug)]
+pub struct Stop {
+    pub id: String,
+    pub lat: f64,
+    pub lng: f64,
+}
+
+/// Trips visiting the same stops in the same order, the routes of RAPTOR
+#[derive(Debug, Default)]
+pub(super) struct Pattern {
+    pub stops: Vec<usize>,
+    /// `(arrival, departure)` seconds after midnight at each stop, by first departure
+    pub trips: Vec<Vec<(u32, u32)>>,
+}
+
+/// The trips of a GTFS feed running on one service date, ready for journey searches
+///
+/// Transfers are footpaths between stops within walking distance, `transfers.txt` is not
+/// read. Trips of a pattern are assumed not to overtake each other.
+#[derive(Debug)]
+pub struct Timetable {
+    pub(super) stops: Vec<Stop>,
+    pub(super) patterns: Vec<Pattern>,
+    /// Patterns serving each stop, with the position of the stop in the pattern
+    pub(super) stop_patterns: Vec<Vec<(usize, usize)>>,
+    /// Footpaths from each stop, with the walking seconds
+    pub(super) footpaths: Vec<Vec<(usize, u32)>>,
+    grid: HashMap<(i64, i64), Vec<usize>>,
+}
+
+#[derive(Deserialize)]
+struct StopRecord {
+    stop_id: String,
+    stop_lat: Option<f64>,
+    stop_lon: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct TripRecord {
+    trip_id: String,
+    service_id: String,
+}
+
+#[derive(Deserialize)]
+struct StopTimeRecord {
+    trip_id: String,
+    arrival_time: Option<String>,
+    departure_time: Option<String>,
+    stop_id: String,
+    stop_sequence: u32,
+}
+
+#[derive(Deserialize)]
+struct CalendarRecord {
+    service_id: String,
+    monday: u8,
+    tuesday: u8,
+    wednesday: u8,
+    thursday: u8,
+    friday: u8,
+    saturday: u8,
+    sunday: u8,
+    start_date: String,
+    end_date: String,
+}
+
+#[derive(Deserialize)]
+struct CalendarDateRecord {
+    service_id: String,
+    date: String,
+    exception_type: u8,
+}
+
+/// Parse a GTFS time like `08:05:00` or `25:10:00` into seconds after midnight
+fn parse_time(text: &str) -> Result<u32, TransitError> {
+    let invalid = || TransitError::InvalidValue {
+        field: "time",
+        value: text.to_string(),
+    };
+
+    let mut parts = text.trim().splitn(3, ':').map(str::parse::<u32>);
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(Ok(h)), Some(Ok(m)), Some(Ok(s))) if m < 60 && s < 60 => Ok(h * 3600 + m * 60 + s),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_date(text: &str) -> Result<NaiveDate, TransitError> {
+    NaiveDate::parse_from_str(text.trim(), "%Y%m%d").map_err(|_| TransitError::InvalidValue {
+        field: "date",
+        value: text.to_string(),
+    })
+}
+
+/// Walking seconds between locations
+pub(super) fn walk_seconds(from: (f64, f64), to: (f64, f64)) -> u32 {
+    let meters = walking_meters(haversine_km(from, to));
+    (meters / WALKING_SPEED * 60.0).round() as u32
+}
+
+fn cell((lat, lng): (f64, f64)) -> (i64, i64) {
+    (
+        (lat / CELL_DEGREES).floor() as i64,
+        (lng / CELL_DEGREES).floor() as i64,
+    )
+}
+
+/// Read every record of a file in the feed, returning whether the file exists
+fn read_records<R, T, F>(
+    archive: &mut ZipArchive<R>,
+    file: &'static str,
+    mut f: F,
+) -> Result<bool, TransitError>
+where
+    R: Read + Seek,
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<(), TransitError>,
+{
+    let entry = match archive.by_name(file) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(false),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .from_reader(entry);
+
+    for record in reader.deserialize() {
+        f(record.map_err(|source| TransitError::InvalidFile { file, source })?)?;
+    }
+
+    Ok(true)
+}
+
+fn require(found: bool, file: &'static str) -> Result<(), TransitError> {
+    match found {
+        true => Ok(()),
+        false => Err(TransitError::MissingFile(file)),
+    }
+}
+
+/// Get the services running on a date from `calendar.txt` and `calendar_dates.txt`
+fn active_services<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    date: NaiveDate,
+) -> Result<HashSet<String>, TransitError> {
+    let mut services = HashSet::new();
+
+    let has_calendar = read_records(archive, "calendar.txt", |record: CalendarRecord| {
+        let runs = match date.weekday() {
+            Weekday::Mon => record.monday,
+            Weekday::Tue => record.tuesday,
+            Weekday::Wed => record.wednesday,
+            Weekday::Thu => record.thursday,
+            Weekday::Fri => record.friday,
+            Weekday::Sat => record.saturday,
+            Weekday::Sun => record.sunday,
+        };
+
+        if runs == 1
+            && parse_date(&record.start_date)? <= date
+            && date <= parse_date(&record.end_date)?
+        {
+            services.insert(record.service_id);
+        }
+
+        Ok(())
+    })?;
+
+    let has_dates = read_records(
+        archive,
+        "calendar_dates.txt",
+        |record: CalendarDateRecord| {
+            if parse_date(&record.date)? == date {
+                match record.exception_type {
+                    1 => services.insert(record.service_id),
+                    _ => services.remove(&record.service_id),
+                };
+            }
+
+            Ok(())
+        },
+    )?;
+
+    require(has_calendar || has_dates, "calendar.txt")?;
+
+    Ok(services)
+}
+
+impl Timetable {
+    /// Load the trips running on `date` from a GTFS zip, with footpaths between stops up to
+    /// `max_transfer_m` walking meters apart
+    pub fn load(path: &Path, date: NaiveDate, max_transfer_m: u32) -> Result<Self, TransitError> {
+        let file = File::open(path).map_err(|source| PathError::new(path, source))?;
+        Self::from_archive(ZipArchive::new(file)?, date, max_transfer_m)
+    }
+
+    pub(super) fn from_archive<R: Read + Seek>(
+        mut archive: ZipArchive<R>,
+        date: NaiveDate,
+        max_transfer_m: u32,
+    ) -> Result<Self, TransitError> {
+        let services = active_services(&mut archive, date)?;
+
+        if services.is_empty() {
+            return Err(TransitError::NoService(date));
+        }
+
+        let mut stops = Vec::new();
+        let mut stop_index = HashMap::new();
+
+        let found = read_records(&mut archive, "stops.txt", |record: StopRecord| {
+            // stations and entrances without a location are never served by stop times
+            if let (Some(lat), Some(lng)) = (record.stop_lat, record.stop_lon) {
+                stop_index.insert(record.stop_id.clone(), stops.len());
+                stops.push(Stop {
+                    id: record.stop_id,
+                    lat,
+                    lng,
+                });
+            }
+            Ok(())
+        })?;
+        require(found, "stops.txt")?;
+
+        let mut trip_index = HashMap::new();
+
+        let found = read_records(&mut archive, "trips.txt", |record: TripRecord| {
+            if services.contains(&record.service_id) {
+                let index = trip_index.len();
+                trip_index.insert(record.trip_id, index);
+            }
+            Ok(())
+        })?;
+        require(found, "trips.txt")?;
+
+        // (stop_sequence, stop, arrival, departure) of each running trip
+        let mut trips: Vec<Vec<(u32, usize, u32, u32)>> = vec![Vec::new(); trip_index.len()];
+
+        let found = read_records(&mut archive, "stop_times.txt", |record: StopTimeRecord| {
+            let (Some(&trip), Some(&stop)) = (
+                trip_index.get(&record.trip_id),
+                stop_index.get(&record.stop_id),
+            ) else {
+                return Ok(());
+            };
+
+            let arrival = record.arrival_time.as_deref().map(parse_time).transpose()?;
+            let departure = record
+                .departure_time
+                .as_deref()
+                .map(parse_time)
+                .transpose()?;
+
+            // stops without times are not timepoints, the trip is not boarded there
+            if let Some((arrival, departure)) = arrival.or(departure).zip(departure.or(arrival)) {
+                trips[trip].push((record.stop_sequence, stop, arrival, departure));
+            }
+            Ok(())
+        })?;
+        require(found, "stop_times.txt")?;
+
+        let mut patterns: Vec<Pattern> = Vec::new();
+        let mut pattern_index: HashMap<Vec<usize>, usize> = HashMap::new();
+
+        for mut trip in trips {
+            if trip.len() < 2 {
+                continue;
+            }
+
+            trip.sort_by_key(|&(sequence, ..)| sequence);
+            let stops: Vec<usize> = trip.iter().map(|&(_, stop, ..)| stop).collect();
+            let times = trip.iter().map(|&(_, _, arr, dep)| (arr, dep)).collect();
+
+            let index = *pattern_index.entry(stops.clone()).or_insert_with(|| {
+                patterns.push(Pattern {
+                    stops,
+                    trips: Vec::new(),
+                });
+                patterns.len() - 1
+            });
+            patterns[index].trips.push(times);
+        }
+
+        let mut stop_patterns = vec![Vec::new(); stops.len()];
+
+        for (index, pattern) in patterns.iter_mut().enumerate() {
+            pattern.trips.sort_by_key(|times| times[0].1);
+
+            for (position, &stop) in pattern.stops.iter().enumerate() {
+                stop_patterns[stop].push((index, position));
+            }
+        }
+
+        let mut grid: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (index, stop) in stops.iter().enumerate() {
+            grid.entry(cell((stop.lat, stop.lng)))
+                .or_default()
+                .push(index);
+        }
+
+        let mut timetable = Self {
+            stops,
+            patterns,
+            stop_patterns,
+            footpaths: Vec::new(),
+            grid,
+        };
+
+        timetable.footpaths = (0..timetable.stops.len())
+            .map(|from| {
+                let stop = &timetable.stops[from];
+                timetable
+                    .stops_near((stop.lat, stop.lng), max_transfer_m)
+                    .into_iter()
+                    .filter(|&(to, _)| to != from)
+                    .collect()
+            })
+            .collect();
+
+        info!(
+            %date,
+            stops = timetable.stops.len(),
+            patterns = timetable.patterns.len(),
+            trips = trip_index.len(),
+            "load timetable"
+        );
+
+        Ok(timetable)
+    }
+
+    /// Get the stops within `max_walk_m` walking meters of a location, with the walking seconds
+    pub fn stops_near(&self, location: (f64, f64), max_walk_m: u32) -> Vec<(usize, u32)> {
+        let max_seconds = (max_walk_m as f64 / WALKING_SPEED * 60.0).round() as u32;
+
+        // the straight line is never longer than the walk
+        let km = max_walk_m as f64 / 1000.0;
+        let lat_cells = (km / KM_PER_DEGREE / CELL_DEGREES).ceil() as i64;
+        let lng_cells =
+            (km / (KM_PER_DEGREE * location.0.to_radians().cos()) / CELL_DEGREES).ceil() as i64;
+
+        let (lat, lng) = cell(location);
+        let mut near = Vec::new();
+
+        for lat in lat - lat_cells..=lat + lat_cells {
+            for lng in lng - lng_cells..=lng + lng_cells {
+                for &index in self.grid.get(&(lat, lng)).into_iter().flatten() {
+                    let stop = &self.stops[index];
+                    let seconds = walk_seconds(location, (stop.lat, stop.lng));
+
+                    if seconds <= max_seconds {
+                        near.push((index, seconds));
+                    }
+                }
+            }
+        }
+
+        near
+    }
+
+    pub fn stops(&self) -> &[Stop] {
+        &self.stops
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::ZipWriter;
+    use zip::write::SimpleFileOptions;
+
+    use super::*;
+
+    /// A line A-B-C every 10 minutes from 08:00 and a line C-D at 08:30 and 09:00 on
+    /// weekdays, with D a short walk from E
+    pub fn feed() -> ZipArchive<Cursor<Vec<u8>>> {
+        let mut stop_times =
+            String::from("trip_id,arrival_time,departure_time,stop_id,stop_sequence\n");
+        let mut trips = String::from("route_id,service_id,trip_id\n");
+
+        for i in 0..6 {
+            let start = 8 * 60 + i * 10;
+            trips += &format!("abc,weekday,abc{i}\n");
+            for (seq, stop) in ["A", "B", "C"].iter().enumerate() {
+                let t = start + seq as u32 * 5;
+                stop_times += &format!(
+                    "abc{i},{:02}:{:02}:00,{:02}:{:02}:00,{stop},{}\n",
+                    t / 60,
+                    t % 60,
+                    t / 60,
+                    t % 60,
+                    seq + 1
+                );
+            }
+        }
+
+        trips += "cd,weekday,cd0\ncd,weekday,cd1\ncd,weekend,cd2\n";
+        stop_times += "cd0,08:30:00,08:30:00,C,1\ncd0,08:45:00,08:45:00,D,2\n";
+        stop_times += "cd1,09:00:00,09:00:00,C,1\ncd1,09:15:00,09:15:00,D,2\n";
+        stop_times += "cd2,08:20:00,08:20:00,C,1\ncd2,08:35:00,08:35:00,D,2\n";
+
+        let files = [
+            (
+                "stops.txt",
+                "\u{feff}stop_id,stop_name,stop_lat,stop_lon\n\
+                 A,A,25.000,121.500\nB,B,25.010,121.500\nC,C,25.020,121.500\n\
+                 D,D,25.050,121.500\nE,E,25.052,121.500\nS,Station,,\n"
+                    .to_string(),
+            ),
+            (
+                "calendar.txt",
+                "service_id,monday,tuesday,wednesday,thursday,friday,saturday,sunday,start_date,end_date\n\
+                 weekday,1,1,1,1,1,0,0,20250101,20251231\n\
+                 weekend,0,0,0,0,0,1,1,20250101,20251231\n"
+                    .to_string(),
+            ),
+            ("trips.txt", trips),
+            ("stop_times.txt", stop_times),
+        ];
+
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer
+                .start_file(name, SimpleFileOptions::default())
+                .unwrap();
+            writer.write_all(content.as_bytes()).unwrap();
+        }
+
+        ZipArchive::new(writer.finish().unwrap()).unwrap()
+    }
+
+    pub fn monday() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()
+    }
+
+    #[test]
+    fn test_load_timetable() {
+        let timetable = Timetable::from_archive(feed(), monday(), 400).unwrap();
+
+        assert_eq!(timetable.stops().len(), 5);
+        assert_eq!(timetable.patterns.len(), 2);
+        assert_eq!(timetable.patterns[0].trips.len(), 6);
+        assert_eq!(timetable.patterns[1].trips.len(), 2);
+
+        // only D and E are within walking distance of each other
+        let footpaths: Vec<usize> = timetable.footpaths.iter().map(Vec::len).collect();
+        assert_eq!(footpaths, [0, 0, 0, 1, 1]);
+
+        let sunday = NaiveDate::from_ymd_opt(2025, 6, 1).unwrap();
+        let timetable = Timetable::from_archive(feed(), sunday, 400).unwrap();
+        assert_eq!(timetable.patterns.len(), 1);
+
+        let past = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+        assert!(matches!(
+            Timetable::from_archive(feed(), past, 400),
+            Err(TransitError::NoService(_))
+        ));
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("08:05:30").unwrap(), 8 * 3600 + 5 * 60 + 30);
+        assert_eq!(parse_time(" 7:00:00").unwrap(), 7 * 3600);
+        assert_eq!(parse_time("25:10:00").unwrap(), 25 * 3600 + 10 * 60);
+        assert!(parse_time("08:65:00").is_err());
+        assert!(parse_time("0800").is_err());
+    }
+}
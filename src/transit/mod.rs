@@ -0,0 +1,7 @@
+mod error;
+mod gtfs;
+mod raptor;
+
+pub use error::TransitError;
+pub use gtfs::{Stop, Timetable};
+pub use raptor::{Arrivals, Commutes};
@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::Timetable;
+use super::gtfs::walk_seconds;
+
+/// Commute minutes from a listing to each destination, by point of interest name
+pub type Commutes = BTreeMap<String, u32>;
+
+/// Not reached
+const UNREACHED: u32 = u32::MAX;
+
+/// Earliest arrival at every stop from one origin and departure time
+#[derive(Debug)]
+pub struct Arrivals<'a> {
+    timetable: &'a Timetable,
+    origin: (f64, f64),
+    departure: u32,
+    max_walk_m: u32,
+    /// Seconds after midnight at each stop, `UNREACHED` if never reached
+    best: Vec<u32>,
+}
+
+impl Timetable {
+    /// Search the earliest arrival at every stop with RAPTOR (round-based public transit
+    /// routing), leaving `origin` at `departure` seconds after midnight
+    ///
+    /// Stops within `max_walk_m` walking meters of the origin are reached on foot. Each
+    /// round rides one more trip, so at most `max_trips` trips are taken.
+    pub fn earliest_arrivals(
+        &self,
+        origin: (f64, f64),
+        departure: u32,
+        max_walk_m: u32,
+        max_trips: usize,
+    ) -> Arrivals<'_> {
+        let mut best = vec![UNREACHED; self.stops.len()];
+        let mut marked = vec![false; self.stops.len()];
+
+        for (stop, seconds) in self.stops_near(origin, max_walk_m) {
+            best[stop] = departure + seconds;
+            marked[stop] = true;
+        }
+
+        // arrivals by the previous round, the earliest trip to board at each stop
+        let mut previous = best.clone();
+
+        for _ in 0..max_trips {
+            // earliest marked position in each pattern serving a marked stop
+            let mut queue: HashMap<usize, usize> = HashMap::new();
+
+            for (stop, marked) in marked.iter_mut().enumerate() {
+                if !std::mem::take(marked) {
+                    continue;
+                }
+
+                for &(pattern, position) in &self.stop_patterns[stop] {
+                    queue
+                        .entry(pattern)
+                        .and_modify(|earliest| *earliest = (*earliest).min(position))
+                        .or_insert(position);
+                }
+            }
+
+            if queue.is_empty() {
+                break;
+            }
+
+            let mut improved = Vec::new();
+
+            for (pattern, start) in queue {
+                let pattern = &self.patterns[pattern];
+                let mut trip: Option<&Vec<(u32, u32)>> = None;
+
+                for (position, &stop) in pattern.stops.iter().enumerate().skip(start) {
+                    if let Some(times) = trip {
+                        let arrival = times[position].0;
+                        if arrival < best[stop] {
+                            best[stop] = arrival;
+                            improved.push(stop);
+                        }
+                    }
+
+                    // board an earlier trip if the stop was reached before it departs
+                    let ready = previous[stop];
+                    if ready != UNREACHED && trip.is_none_or(|times| ready <= times[position].1) {
+                        let index = pattern
+                            .trips
+                            .partition_point(|times| times[position].1 < ready);
+                        if let Some(times) = pattern.trips.get(index) {
+                            trip = Some(times);
+                        }
+                    }
+                }
+            }
+
+            for &stop in &improved {
+                marked[stop] = true;
+
+                for &(to, seconds) in &self.footpaths[stop] {
+                    let arrival = best[stop] + seconds;
+                    if arrival < best[to] {
+                        best[to] = arrival;
+                        marked[to] = true;
+                    }
+                }
+            }
+
+            previous.clone_from(&best);
+        }
+
+        Arrivals {
+            timetable: self,
+            origin,
+            departure,
+            max_walk_m,
+            best,
+        }
+    }
+}
+
+impl Arrivals<'_> {
+    /// Seconds after midnight at a stop, `None` if it cannot be reached
+    pub fn at_stop(&self, stop: usize) -> Option<u32> {
+        Some(self.best[stop]).filter(|&arrival| arrival != UNREACHED)
+    }
+
+    /// Earliest arrival at a location, walking from stops within the walking distance or
+    /// from the origin all the way
+    pub fn at(&self, destination: (f64, f64)) -> u32 {
+        let walk = self.departure + walk_seconds(self.origin, destination);
+
+        self.timetable
+            .stops_near(destination, self.max_walk_m)
+            .into_iter()
+            .filter_map(|(stop, seconds)| Some(self.at_stop(stop)? + seconds))
+            .fold(walk, u32::min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::gtfs::tests::{feed, monday};
+    use super::*;
+
+    const fn time(h: u32, m: u32) -> u32 {
+        h * 3600 + m * 60
+    }
+
+    #[test]
+    fn test_earliest_arrivals() {
+        let timetable = Timetable::from_archive(feed(), monday(), 400).unwrap();
+        let stop = |id: &str| {
+            timetable
+                .stops()
+                .iter()
+                .position(|stop| stop.id == id)
+                .unwrap()
+        };
+
+        // standing at A at 08:12, the 08:20 trip reaches C at 08:30 in time for C-D
+        let arrivals = timetable.earliest_arrivals((25.0, 121.5), time(8, 12), 300, 4);
+        assert_eq!(arrivals.at_stop(stop("A")), Some(time(8, 12)));
+        assert_eq!(arrivals.at_stop(stop("C")), Some(time(8, 30)));
+        assert_eq!(arrivals.at_stop(stop("D")), Some(time(8, 45)));
+        // E is a walk from D, not served by any trip
+        let e = arrivals.at_stop(stop("E")).unwrap();
+        assert!(e > time(8, 45) && e < time(8, 50), "{e}");
+
+        // with a single trip, D is out of reach
+        let arrivals = timetable.earliest_arrivals((25.0, 121.5), time(8, 12), 300, 1);
+        assert_eq!(arrivals.at_stop(stop("C")), Some(time(8, 30)));
+        assert_eq!(arrivals.at_stop(stop("D")), None);
+
+        // leaving at 08:21 misses the C-D trip at 08:30 and waits for 09:00
+        let arrivals = timetable.earliest_arrivals((25.0, 121.5), time(8, 21), 300, 4);
+        assert_eq!(arrivals.at_stop(stop("D")), Some(time(9, 15)));
+
+        // a destination next to the origin is walked to
+        let arrivals = timetable.earliest_arrivals((25.0, 121.5), time(8, 12), 300, 4);
+        assert!(arrivals.at((25.001, 121.5)) < time(8, 14));
+    }
+}
@@ -1,6 +1,8 @@
 use clap::Args;
 
 use super::{BackendType, Fetcher, SpiderChromeArgs, SpiderChromeBackend, WebError};
+use crate::config::fetcher::FetcherConfig;
+use crate::error::TraceReport;
 use crate::web::Backend;
 use crate::workspace::Workspace;
 
@@ -15,6 +17,13 @@ pub struct FetcherArgs {
     #[arg(long = "no-clean", action = clap::ArgAction::SetFalse)]
     pub clean: bool,
 
+    /// Load cookies from the workspace before fetching and save them back afterwards
+    ///
+    /// Session cookies are not kept. With several `--proxy`, cookies are carried from one
+    /// proxy's browser context to the next.
+    #[arg(long)]
+    pub cookies: bool,
+
     /// Web scraping backend to use
     #[arg(long = "backend", value_enum, default_value_t = Default::default())]
     pub backend: BackendType,
@@ -24,16 +33,35 @@ pub struct FetcherArgs {
 }
 
 impl FetcherArgs {
+    /// Fill unset options from the `[fetcher]` section of `rentmap.toml`
+    pub fn merge(mut self, config: FetcherConfig) -> Self {
+        if let Some(spider_chrome) = config.spider_chrome {
+            self.spider_chrome = self.spider_chrome.merge(spider_chrome);
+        }
+        self
+    }
+
     pub async fn build(self, workspace: Workspace) -> Result<Fetcher, WebError> {
         let backend: Backend = match self.backend {
-            BackendType::SpiderChrome => SpiderChromeBackend::new(self.spider_chrome.try_into()?)
-                .await?
-                .into(),
+            BackendType::SpiderChrome => {
+                let profile_dir = match &self.spider_chrome.profile {
+                    Some(name) => Some(workspace.profile_dir(name)?),
+                    None => None,
+                };
+                SpiderChromeBackend::new(self.spider_chrome, profile_dir)
+                    .await?
+                    .into()
+            }
         };
 
         let mut fetcher = Fetcher::new(workspace, backend);
         fetcher.cache = self.cache;
         fetcher.clean = self.clean;
+        fetcher.cookies = self.cookies;
+
+        if fetcher.cookies {
+            fetcher.load_cookies().await.trace_report().ok();
+        }
 
         Ok(fetcher)
     }
@@ -3,7 +3,7 @@ use tracing::error;
 use url::Url;
 
 use super::backends::SpiderChromeBackend;
-use super::{Page, WebError};
+use super::{Cookie, Page, WebError};
 
 #[derive(Clone, Debug, Default, ValueEnum)]
 pub enum BackendType {
@@ -31,6 +31,20 @@ impl Backend {
         }
     }
 
+    /// Get all cookies held by this backend
+    pub async fn get_cookies(&self) -> Result<Vec<Cookie>, WebError> {
+        match self {
+            Self::SpiderChrome(backend) => Ok(backend.get_cookies().await?),
+        }
+    }
+
+    /// Load cookies into this backend
+    pub async fn set_cookies(&self, cookies: Vec<Cookie>) -> Result<(), WebError> {
+        match self {
+            Self::SpiderChrome(backend) => Ok(backend.set_cookies(cookies).await?),
+        }
+    }
+
     /// Shutdown the backend and cleanup resources
     pub async fn shutdown(self) {
         match self {
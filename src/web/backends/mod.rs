@@ -1,3 +1,5 @@
 mod spider_chrome;
 
-pub use spider_chrome::{SpiderChromeArgs, SpiderChromeBackend, SpiderChromeError};
+pub use spider_chrome::{
+    Header, Rotation, SpiderChromeArgs, SpiderChromeBackend, SpiderChromeError,
+};
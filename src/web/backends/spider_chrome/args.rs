@@ -1,33 +1,135 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
 use clap::Args;
+use serde::Deserialize;
 use spider_chrome::browser::BrowserConfig;
+use spider_chrome::handler::HandlerConfig;
 use spider_chrome::handler::viewport::Viewport;
+use url::Url;
 
 use super::error::SpiderChromeError;
+use super::rotation::Rotation;
+
+const DEFAULT_VIEWPORT_WIDTH: u32 = 1920;
+const DEFAULT_VIEWPORT_HEIGHT: u32 = 1080;
 
-#[derive(Debug, Default, Args)]
+#[derive(Clone, Debug, Default, Deserialize, Args)]
 #[command(next_help_heading = "Spider Chrome")]
 pub struct SpiderChromeArgs {
     /// Run browser in head mode (non-headless)
     #[arg(long)]
+    #[serde(default)]
     pub head: bool,
+
+    /// Browser viewport width in pixels [default: 1920]
+    #[arg(long)]
+    pub viewport_width: Option<u32>,
+
+    /// Browser viewport height in pixels [default: 1080]
+    #[arg(long)]
+    pub viewport_height: Option<u32>,
+
+    /// User agent to send, repeat to rotate between several
+    #[arg(long = "user-agent")]
+    pub user_agents: Option<Vec<String>>,
+
+    /// Proxy server (e.g., http://host:port, socks5://host:port), repeat or comma-separate to rotate
+    #[arg(long = "proxy", value_delimiter = ',')]
+    pub proxies: Option<Vec<String>>,
+
+    /// How to pick a user agent and proxy for each request
+    #[arg(long, value_enum)]
+    pub rotation: Option<Rotation>,
+
+    /// Browser locale (e.g., zh-TW), also sent as the Accept-Language header
+    #[arg(long)]
+    pub locale: Option<String>,
+
+    /// Extra HTTP header in `Name: value` form, repeat for several
+    #[arg(long = "header")]
+    pub headers: Option<Vec<Header>>,
+
+    /// Attach to a running browser via its DevTools URL (ws://... or http://host:port)
+    /// instead of launching a new one
+    #[arg(long)]
+    pub browser_ws: Option<Url>,
+
+    /// Keep browser data (cookies, local storage, consent) in a named profile under the
+    /// workspace root
+    #[arg(long)]
+    pub profile: Option<String>,
 }
 
-impl TryFrom<SpiderChromeArgs> for BrowserConfig {
+impl SpiderChromeArgs {
+    /// Fill unset options from another set of options, typically loaded from `rentmap.toml`
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            head: self.head || other.head,
+            viewport_width: self.viewport_width.or(other.viewport_width),
+            viewport_height: self.viewport_height.or(other.viewport_height),
+            user_agents: self.user_agents.or(other.user_agents),
+            proxies: self.proxies.or(other.proxies),
+            rotation: self.rotation.or(other.rotation),
+            locale: self.locale.or(other.locale),
+            headers: self.headers.or(other.headers),
+            browser_ws: self.browser_ws.or(other.browser_ws),
+            profile: self.profile.or(other.profile),
+        }
+    }
+
+    fn viewport(&self) -> Viewport {
+        Viewport {
+            width: self.viewport_width.unwrap_or(DEFAULT_VIEWPORT_WIDTH),
+            height: self.viewport_height.unwrap_or(DEFAULT_VIEWPORT_HEIGHT),
+            ..Default::default()
+        }
+    }
+
+    fn extra_headers(&self) -> Option<HashMap<String, String>> {
+        let mut headers: HashMap<_, _> = self
+            .headers
+            .iter()
+            .flatten()
+            .map(|header| (header.name.clone(), header.value.clone()))
+            .collect();
+
+        if let Some(locale) = &self.locale {
+            let has_accept_language = headers
+                .keys()
+                .any(|name| name.eq_ignore_ascii_case("accept-language"));
+
+            if !has_accept_language {
+                headers.insert("Accept-Language".to_string(), locale.clone());
+            }
+        }
+
+        (!headers.is_empty()).then_some(headers)
+    }
+}
+
+impl TryFrom<&SpiderChromeArgs> for BrowserConfig {
     type Error = SpiderChromeError;
 
-    fn try_from(args: SpiderChromeArgs) -> Result<Self, Self::Error> {
+    fn try_from(args: &SpiderChromeArgs) -> Result<Self, Self::Error> {
         let mut config = BrowserConfig::builder()
-            .viewport(Some(Viewport {
-                width: 1920,
-                height: 1080,
-                ..Default::default()
-            }))
+            .viewport(Some(args.viewport()))
+            .set_extra_headers(args.extra_headers())
             .enable_request_intercept();
 
         if args.head {
             config = config.with_head();
         }
 
+        if let Some(locale) = &args.locale {
+            config = config.arg(format!("--lang={locale}"));
+        }
+
+        // a single proxy applies to the whole browser, several are rotated per request
+        if let Some([proxy]) = args.proxies.as_deref() {
+            config = config.arg(format!("--proxy-server={proxy}"));
+        }
+
         let mut browser_config = config.build().map_err(SpiderChromeError::Config)?;
 
         browser_config.ignore_ads = true;
@@ -39,3 +141,101 @@ impl TryFrom<SpiderChromeArgs> for BrowserConfig {
         Ok(browser_config)
     }
 }
+
+impl From<&SpiderChromeArgs> for HandlerConfig {
+    fn from(args: &SpiderChromeArgs) -> Self {
+        Self {
+            viewport: Some(args.viewport()),
+            extra_headers: args.extra_headers(),
+            request_intercept: true,
+            ignore_ads: true,
+            ignore_analytics: true,
+            ignore_javascript: false,
+            ignore_stylesheets: false,
+            ignore_visuals: false,
+            ..Default::default()
+        }
+    }
+}
+
+/// An extra HTTP header given as `Name: value`
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub struct Header {
+    pub name: String,
+    pub value: String,
+}
+
+impl FromStr for Header {
+    type Err = SpiderChromeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, value) = s
+            .split_once(':')
+            .ok_or_else(|| SpiderChromeError::InvalidHeader(s.to_string()))?;
+
+        let name = name.trim();
+
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(SpiderChromeError::InvalidHeader(s.to_string()));
+        }
+
+        Ok(Self {
+            name: name.to_string(),
+            value: value.trim().to_string(),
+        })
+    }
+}
+
+impl TryFrom<String> for Header {
+    type Error = SpiderChromeError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let header: Header = "Referer: https://rent.591.com.tw/".parse().unwrap();
+        assert_eq!(header.name, "Referer");
+        assert_eq!(header.value, "https://rent.591.com.tw/");
+    }
+
+    #[test]
+    fn test_parse_header_invalid() {
+        assert!("Referer".parse::<Header>().is_err());
+        assert!(": value".parse::<Header>().is_err());
+        assert!("Bad Name: value".parse::<Header>().is_err());
+    }
+
+    #[test]
+    fn test_locale_adds_accept_language() {
+        let args = SpiderChromeArgs {
+            locale: Some("zh-TW".to_string()),
+            ..Default::default()
+        };
+        let headers = args.extra_headers().unwrap();
+        assert_eq!(headers.get("Accept-Language").unwrap(), "zh-TW");
+    }
+
+    #[test]
+    fn test_merge_prefers_self() {
+        let args = SpiderChromeArgs {
+            locale: Some("en-US".to_string()),
+            ..Default::default()
+        };
+        let config = SpiderChromeArgs {
+            locale: Some("zh-TW".to_string()),
+            viewport_width: Some(1280),
+            ..Default::default()
+        };
+        let merged = args.merge(config);
+        assert_eq!(merged.locale.as_deref(), Some("en-US"));
+        assert_eq!(merged.viewport_width, Some(1280));
+    }
+}
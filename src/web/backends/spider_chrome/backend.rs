@@ -1,27 +1,81 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard, PoisonError};
 use std::time::Duration;
 
 use futures::StreamExt;
 use miette::IntoDiagnostic;
+use spider_chrome::Page as ChromiumPage;
 use spider_chrome::browser::{Browser, BrowserConfig};
+use spider_chrome::cdp::browser_protocol::browser::BrowserContextId;
+use spider_chrome::cdp::browser_protocol::network::{
+    Cookie as ChromiumCookie, CookieParam, TimeSinceEpoch,
+};
+use spider_chrome::cdp::browser_protocol::storage::{GetCookiesParams, SetCookiesParams};
+use spider_chrome::cdp::browser_protocol::target::{
+    CreateBrowserContextParams, CreateTargetParams, TargetId,
+};
+use spider_chrome::handler::HandlerConfig;
 use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
 use url::Url;
 
 use super::args::SpiderChromeArgs;
 use super::error::SpiderChromeError;
+use super::rotation::Rotator;
 use super::utils::wait_for_network_idle;
 use crate::error::TraceReport;
-use crate::web::{Backend, Page};
+use crate::web::{Backend, Cookie, Page};
 
 /// Chrome-based web scraping backend using spider_chrome.
 #[must_use = "SpiderChromeBackend holds browser resources that must be shut down with `shutdown()`"]
 pub struct SpiderChromeBackend {
     browser: Browser,
     join_handle: JoinHandle<()>,
+    user_agents: Rotator<String>,
+    proxies: Rotator<String>,
+    /// Whether the browser was attached to rather than launched by us
+    attached: bool,
+    /// Pages and contexts opened by us that are still alive
+    opened: Mutex<Opened>,
+    /// Cookies carried between the browser contexts of rotating proxies, which do not share
+    /// the cookies of the default context
+    jar: Mutex<Vec<Cookie>>,
+}
+
+#[derive(Debug, Default)]
+struct Opened {
+    pages: HashSet<TargetId>,
+    contexts: HashSet<BrowserContextId>,
 }
 
 impl SpiderChromeBackend {
-    pub async fn new(config: BrowserConfig) -> Result<Self, SpiderChromeError> {
-        let (browser, mut handler) = Browser::launch(config).await?;
+    /// Launch or attach to a browser, using `profile_dir` as the user data directory if given
+    pub async fn new(
+        args: SpiderChromeArgs,
+        profile_dir: Option<PathBuf>,
+    ) -> Result<Self, SpiderChromeError> {
+        let (browser, mut handler) = match &args.browser_ws {
+            Some(url) => {
+                info!(%url, "attach to browser");
+                if profile_dir.is_some() {
+                    warn!("profile is ignored when attaching to a running browser");
+                }
+                Browser::connect_with_config(url.as_str(), HandlerConfig::from(&args))
+                    .await
+                    .map_err(|source| SpiderChromeError::Connect {
+                        url: url.clone(),
+                        source,
+                    })?
+            }
+            None => {
+                let mut config = BrowserConfig::try_from(&args)?;
+                config.user_data_dir = profile_dir;
+                Browser::launch(config).await?
+            }
+        };
+
+        let attached = args.browser_ws.is_some();
 
         let join_handle = tokio::spawn(async move {
             while let Some(result) = handler.next().await {
@@ -29,43 +83,252 @@ impl SpiderChromeBackend {
             }
         });
 
+        let rotation = args.rotation.unwrap_or_default();
+
+        // a single proxy is already applied at launch, see `TryFrom<&SpiderChromeArgs>`
+        let proxies = match args.proxies {
+            Some(proxies) if attached || proxies.len() > 1 => proxies,
+            _ => Vec::new(),
+        };
+
         Ok(Self {
             browser,
             join_handle,
+            user_agents: Rotator::new(args.user_agents.unwrap_or_default(), rotation),
+            proxies: Rotator::new(proxies, rotation),
+            attached,
+            opened: Mutex::default(),
+            jar: Mutex::default(),
         })
     }
 
     pub async fn default() -> Result<Self, SpiderChromeError> {
-        let config = SpiderChromeArgs::default()
-            .try_into()
-            .expect("spider-chrome default config should always be valid");
+        Self::new(SpiderChromeArgs::default(), None).await
+    }
+
+    /// Get all cookies of the browser
+    ///
+    /// With rotating proxies, pages are opened in their own browser contexts, so these are
+    /// the cookies collected from those contexts as they were closed.
+    pub async fn get_cookies(&self) -> Result<Vec<Cookie>, SpiderChromeError> {
+        if !self.proxies.is_empty() {
+            return Ok(self.jar().clone());
+        }
 
-        Self::new(config).await
+        let cookies = self.browser.get_cookies().await?;
+
+        Ok(cookies.into_iter().map(from_chromium_cookie).collect())
+    }
+
+    /// Set cookies in the browser, and in the contexts of rotating proxies as they are opened
+    pub async fn set_cookies(&self, cookies: Vec<Cookie>) -> Result<(), SpiderChromeError> {
+        if !self.proxies.is_empty() {
+            *self.jar() = cookies;
+            return Ok(());
+        }
+
+        let cookies = cookies.into_iter().map(to_cookie_param).collect();
+
+        self.browser.set_cookies(cookies).await?;
+
+        Ok(())
+    }
+
+    fn jar(&self) -> MutexGuard<'_, Vec<Cookie>> {
+        self.jar.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Seed a new browser context with the cookies of the jar
+    async fn load_context_cookies(
+        &self,
+        context_id: &BrowserContextId,
+    ) -> Result<(), SpiderChromeError> {
+        let cookies: Vec<_> = self.jar().iter().cloned().map(to_cookie_param).collect();
+
+        if cookies.is_empty() {
+            return Ok(());
+        }
+
+        let mut params = SetCookiesParams::new(cookies);
+        params.browser_context_id = Some(context_id.clone());
+        self.browser.execute(params).await?;
+
+        Ok(())
+    }
+
+    /// Keep the cookies of a browser context in the jar before it is disposed, replacing
+    /// those with the same name, domain and path
+    async fn save_context_cookies(
+        &self,
+        context_id: &BrowserContextId,
+    ) -> Result<(), SpiderChromeError> {
+        let params = GetCookiesParams {
+            browser_context_id: Some(context_id.clone()),
+        };
+        let cookies = self.browser.execute(params).await?.result.cookies;
+
+        let mut jar = self.jar();
+        for cookie in cookies.into_iter().map(from_chromium_cookie) {
+            jar.retain(|c| {
+                (&c.name, &c.domain, &c.path) != (&cookie.name, &cookie.domain, &cookie.path)
+            });
+            jar.push(cookie);
+        }
+
+        Ok(())
+    }
+
+    /// Open a blank page, in a fresh browser context when a rotating proxy is picked
+    async fn new_page(
+        &self,
+    ) -> Result<(ChromiumPage, Option<BrowserContextId>), SpiderChromeError> {
+        let mut params = CreateTargetParams::new("about:blank");
+
+        let context_id = match self.proxies.pick() {
+            Some(proxy) => {
+                debug!(%proxy, "use proxy");
+                let context = CreateBrowserContextParams::builder()
+                    .proxy_server(proxy)
+                    .build();
+                let context_id = self
+                    .browser
+                    .execute(context)
+                    .await?
+                    .result
+                    .browser_context_id;
+                params.browser_context_id = Some(context_id.clone());
+                self.opened().contexts.insert(context_id.clone());
+                Some(context_id)
+            }
+            None => None,
+        };
+
+        let page = async {
+            if let Some(context_id) = &context_id {
+                self.load_context_cookies(context_id).await?;
+            }
+            Ok(self.browser.new_page(params).await?)
+        }
+        .await;
+
+        let page = match page {
+            Ok(page) => page,
+            Err(err) => {
+                if let Some(context_id) = context_id {
+                    self.dispose_context(context_id).await.ok();
+                }
+                return Err(err);
+            }
+        };
+
+        self.opened().pages.insert(page.target_id().clone());
+
+        Ok((page, context_id))
+    }
+
+    /// Dispose a browser context opened by `new_page`
+    async fn dispose_context(&self, context_id: BrowserContextId) -> Result<(), SpiderChromeError> {
+        self.browser
+            .dispose_browser_context(context_id.clone())
+            .await?;
+        self.opened().contexts.remove(&context_id);
+        Ok(())
+    }
+
+    /// Close a page opened by `new_page` and dispose its browser context, keeping its cookies
+    ///
+    /// Every step is attempted even if an earlier one fails, the first error is returned.
+    async fn close_page(
+        &self,
+        page: ChromiumPage,
+        context_id: Option<BrowserContextId>,
+    ) -> Result<(), SpiderChromeError> {
+        let target_id = page.target_id().clone();
+        let closed = page.close().await;
+        if closed.is_ok() {
+            self.opened().pages.remove(&target_id);
+        }
+
+        let (saved, disposed) = match context_id {
+            Some(context_id) => (
+                self.save_context_cookies(&context_id).await,
+                self.dispose_context(context_id).await,
+            ),
+            None => (Ok(()), Ok(())),
+        };
+
+        closed?;
+        saved?;
+        disposed
     }
 
     pub async fn fetch_page(&self, url: &Url) -> Result<Page, SpiderChromeError> {
-        let page = self.browser.new_page(url.as_str()).await?;
+        let (page, context_id) = self.new_page().await?;
+
+        let fetched = async {
+            if let Some(user_agent) = self.user_agents.pick() {
+                debug!(%user_agent, "use user agent");
+                page.set_user_agent(user_agent.as_str()).await?;
+            }
+
+            page.goto(url.as_str()).await?;
 
-        let max_wait_duration = Duration::from_secs(20);
-        let network_idle_duration = Duration::from_millis(500);
+            let max_wait_duration = Duration::from_secs(20);
+            let network_idle_duration = Duration::from_millis(500);
 
-        wait_for_network_idle(&page, network_idle_duration, max_wait_duration).await?;
+            wait_for_network_idle(&page, network_idle_duration, max_wait_duration).await?;
 
-        let final_url: Url = page
-            .url()
-            .await?
-            .ok_or(SpiderChromeError::NoPageUrl)?
-            .parse()?;
+            let final_url: Url = page
+                .url()
+                .await?
+                .ok_or(SpiderChromeError::NoPageUrl)?
+                .parse()?;
 
-        let html = page.content().await?;
+            let html = page.content().await?;
 
-        page.close().await?;
+            Ok::<_, SpiderChromeError>(Page::new(final_url, html))
+        }
+        .await;
 
-        Ok(Page::new(final_url, html))
+        let closed = self.close_page(page, context_id).await;
+
+        let page = fetched?;
+        closed?;
+
+        Ok(page)
+    }
+
+    fn opened(&self) -> MutexGuard<'_, Opened> {
+        self.opened.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Close the pages and contexts we opened, leaving the rest of the browser untouched.
+    async fn close_opened(&self) -> Result<(), SpiderChromeError> {
+        let Opened { pages, contexts } = std::mem::take(&mut *self.opened());
+
+        for target_id in pages {
+            let page = self.browser.get_page(target_id).await?;
+            page.close().await?;
+        }
+
+        for context_id in contexts {
+            self.browser.dispose_browser_context(context_id).await?;
+        }
+
+        Ok(())
     }
 
     /// Gracefully shutdown the browser and cleanup resources.
+    ///
+    /// An attached browser is left running, only the tabs opened by us are closed.
     pub async fn shutdown(mut self) -> Result<(), SpiderChromeError> {
+        if self.attached {
+            self.close_opened().await?;
+            self.join_handle.abort();
+            debug!("detach from browser");
+            return Ok(());
+        }
+
         self.browser.close().await?;
         self.browser.wait().await?;
         self.join_handle.await?;
@@ -73,6 +336,30 @@ impl SpiderChromeBackend {
     }
 }
 
+fn from_chromium_cookie(cookie: ChromiumCookie) -> Cookie {
+    Cookie {
+        name: cookie.name,
+        value: cookie.value,
+        domain: cookie.domain,
+        path: cookie.path,
+        expires: (!cookie.session).then_some(cookie.expires),
+        http_only: cookie.http_only,
+        secure: cookie.secure,
+        same_site: cookie.same_site.map(|s| s.as_ref().to_string()),
+    }
+}
+
+fn to_cookie_param(cookie: Cookie) -> CookieParam {
+    let mut param = CookieParam::new(cookie.name, cookie.value);
+    param.domain = Some(cookie.domain);
+    param.path = Some(cookie.path);
+    param.expires = cookie.expires.map(TimeSinceEpoch::new);
+    param.http_only = Some(cookie.http_only);
+    param.secure = Some(cookie.secure);
+    param.same_site = cookie.same_site.and_then(|s| s.parse().ok());
+    param
+}
+
 impl From<SpiderChromeBackend> for Backend {
     fn from(backend: SpiderChromeBackend) -> Self {
         Self::SpiderChrome(Box::new(backend))
@@ -1,7 +1,7 @@
 use miette::Diagnostic;
 use spider_chrome::error::CdpError;
 use thiserror::Error;
-use url::ParseError;
+use url::{ParseError, Url};
 
 #[derive(Debug, Error, Diagnostic)]
 pub enum SpiderChromeError {
@@ -12,6 +12,26 @@ pub enum SpiderChromeError {
     )]
     Config(String),
 
+    #[error("invalid header {0:?}")]
+    #[diagnostic(
+        code(web::backends::spider_chrome::invalid_header),
+        help("headers must be given as `Name: value`, e.g. `Referer: https://rent.591.com.tw/`")
+    )]
+    InvalidHeader(String),
+
+    #[error("failed to attach to browser at {url}")]
+    #[diagnostic(
+        code(web::backends::spider_chrome::connect),
+        help(
+            "ensure the browser is running with --remote-debugging-port and the DevTools URL is reachable"
+        )
+    )]
+    Connect {
+        url: Url,
+        #[source]
+        source: CdpError,
+    },
+
     #[error(transparent)]
     #[diagnostic(
         code(web::backends::spider_chrome::cdp),
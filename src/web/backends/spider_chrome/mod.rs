@@ -1,8 +1,10 @@
 mod args;
 mod backend;
 mod error;
+mod rotation;
 mod utils;
 
-pub use args::SpiderChromeArgs;
+pub use args::{Header, SpiderChromeArgs};
 pub use backend::SpiderChromeBackend;
 pub use error::SpiderChromeError;
+pub use rotation::Rotation;
@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+/// Strategy for picking a value from a rotating list on each request
+#[derive(Clone, Copy, Debug, Default, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rotation {
+    /// Cycle through the values in order
+    #[default]
+    #[value(name = "round-robin")]
+    RoundRobin,
+
+    /// Pick a value uniformly at random
+    #[value(name = "random")]
+    Random,
+}
+
+/// Thread-safe selector over a list of values using a [`Rotation`] strategy
+#[derive(Debug)]
+pub struct Rotator<T> {
+    values: Vec<T>,
+    rotation: Rotation,
+    next: AtomicUsize,
+}
+
+impl<T> Rotator<T> {
+    pub fn new(values: Vec<T>, rotation: Rotation) -> Self {
+        Self {
+            values,
+            rotation,
+            next: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Pick the value for the next request, or `None` if the list is empty
+    pub fn pick(&self) -> Option<&T> {
+        if self.values.is_empty() {
+            return None;
+        }
+
+        let index = match self.rotation {
+            Rotation::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % self.values.len(),
+            Rotation::Random => fastrand::usize(..self.values.len()),
+        };
+
+        self.values.get(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pick_empty() {
+        let rotator: Rotator<u32> = Rotator::new(vec![], Rotation::RoundRobin);
+        assert_eq!(rotator.pick(), None);
+    }
+
+    #[test]
+    fn test_pick_round_robin() {
+        let rotator = Rotator::new(vec![1, 2, 3], Rotation::RoundRobin);
+        let picked: Vec<_> = (0..5).filter_map(|_| rotator.pick().copied()).collect();
+        assert_eq!(picked, [1, 2, 3, 1, 2]);
+    }
+
+    #[test]
+    fn test_pick_random_in_range() {
+        let rotator = Rotator::new(vec![1, 2, 3], Rotation::Random);
+        assert!((0..20).all(|_| rotator.pick().is_some_and(|v| (1..=3).contains(v))));
+    }
+}
@@ -0,0 +1,15 @@
+use sqlx::prelude::FromRow;
+
+/// A browser cookie, stored in the workspace to persist sessions between runs
+#[derive(Debug, Clone, FromRow)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    pub path: String,
+    /// Expiry in seconds since the Unix epoch, `None` for session cookies
+    pub expires: Option<f64>,
+    pub http_only: bool,
+    pub secure: bool,
+    pub same_site: Option<String>,
+}
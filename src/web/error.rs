@@ -2,10 +2,15 @@ use miette::Diagnostic;
 use thiserror::Error;
 
 use super::backends::SpiderChromeError;
+use crate::workspace::WorkspaceError;
 
 #[derive(Debug, Error, Diagnostic)]
 pub enum WebError {
     #[error("SpiderChrome backend error")]
     #[diagnostic(transparent)]
     SpiderChrome(#[from] SpiderChromeError),
+
+    #[error(transparent)]
+    #[diagnostic(transparent)]
+    Workspace(#[from] WorkspaceError),
 }
@@ -12,6 +12,7 @@ use crate::workspace::Workspace;
 pub struct Fetcher {
     pub cache: bool,
     pub clean: bool,
+    pub cookies: bool,
     pub workspace: Workspace,
     pub backend: Backend,
 }
@@ -24,6 +25,7 @@ impl Fetcher {
         Self {
             cache: false,
             clean: false,
+            cookies: false,
             workspace,
             backend: backend.into(),
         }
@@ -39,16 +41,21 @@ impl Fetcher {
         self
     }
 
+    pub fn with_cookies(mut self) -> Self {
+        self.cookies = true;
+        self
+    }
+
     pub fn with_workspace(mut self, workspace: Workspace) -> Self {
         self.workspace = workspace;
         self
     }
 
     async fn try_fetch_page(&self, url: &Url) -> Result<Page> {
-        if self.cache {
-            if let Some(page) = self.workspace.get_cached_page(url).await? {
-                return Ok(page);
-            }
+        if self.cache
+            && let Some(page) = self.workspace.get_cached_page(url).await?
+        {
+            return Ok(page);
         }
 
         let page = self.backend.fetch_page(url).await?;
@@ -75,9 +82,28 @@ impl Fetcher {
         Ok(document)
     }
 
+    /// Load the cookie jar from the workspace into the backend
+    pub async fn load_cookies(&self) -> Result<()> {
+        let cookies = self.workspace.select_cookies().await?;
+        self.backend.set_cookies(cookies).await?;
+        Ok(())
+    }
+
+    /// Save the backend cookies into the workspace cookie jar, except session cookies which
+    /// end with the browser
+    pub async fn save_cookies(&self) -> Result<()> {
+        let mut cookies = self.backend.get_cookies().await?;
+        cookies.retain(|cookie| cookie.expires.is_some());
+        self.workspace.save_cookies(&cookies).await?;
+        Ok(())
+    }
+
     /// Shutdown the backend and cleanup resources
     /// This should be called when the fetcher is no longer needed
     pub async fn shutdown(self) {
+        if self.cookies {
+            self.save_cookies().await.trace_report().ok();
+        }
         self.backend.shutdown().await;
     }
 }
@@ -1,13 +1,15 @@
 mod args;
 mod backend;
 mod backends;
+mod cookie;
 mod error;
 mod fetcher;
 mod page;
 
 pub use args::FetcherArgs;
 pub use backend::{Backend, BackendType};
-pub use backends::{SpiderChromeArgs, SpiderChromeBackend, SpiderChromeError};
+pub use backends::{Header, Rotation, SpiderChromeArgs, SpiderChromeBackend, SpiderChromeError};
+pub use cookie::Cookie;
 pub use error::WebError;
 pub use fetcher::Fetcher;
-pub use page::Page;
+pub use page::{Page, PageSummary};
@@ -16,3 +16,11 @@ impl Page {
         }
     }
 }
+
+/// A cached page without its HTML, for listing the page cache
+#[derive(Debug, Clone, FromRow)]
+pub struct PageSummary {
+    pub url: Json<Url>,
+    pub created_at: String,
+    pub size: i64,
+}
@@ -2,19 +2,41 @@ use std::path::PathBuf;
 
 use clap::Args;
 
-use super::{Workspace, WorkspaceError};
+use super::{LockMode, Workspace, WorkspaceError};
 
 #[derive(Debug, Args)]
 pub struct WorkspaceArgs {
     /// The root directory of the workspace
     #[arg(long, short, default_value = ".rentmap")]
     pub workspace: PathBuf,
+
+    /// PostgreSQL database URL to store data in instead of the workspace's SQLite database
+    /// (e.g., postgres://user@localhost/rentmap)
+    #[arg(long, env = "RENTMAP_DATABASE_URL")]
+    pub database_url: Option<String>,
+
+    /// Wait for other rentmap runs using the workspace to finish instead of failing
+    #[arg(long)]
+    pub wait: bool,
 }
 
 impl WorkspaceArgs {
+    /// Open the workspace for a run that writes to it, locking out every other run
     pub async fn build(self) -> Result<Workspace, WorkspaceError> {
-        let workspace = Workspace::new(self.workspace);
-        workspace.init().await?;
+        self.build_with(LockMode::Exclusive).await
+    }
+
+    /// Open the workspace for a read-only run, which only waits for runs that write to it
+    pub async fn build_read_only(self) -> Result<Workspace, WorkspaceError> {
+        self.build_with(LockMode::Shared).await
+    }
+
+    async fn build_with(self, mode: LockMode) -> Result<Workspace, WorkspaceError> {
+        let mut workspace = match &self.database_url {
+            Some(url) => Workspace::with_postgres(self.workspace, url)?,
+            None => Workspace::new(self.workspace),
+        };
+        workspace.init(mode, self.wait).await?;
         Ok(workspace)
     }
 }
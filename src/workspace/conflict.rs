@@ -0,0 +1,56 @@
+use clap::ValueEnum;
+
+/// How to resolve a record that already exists in the workspace
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ConflictPolicy {
+    /// Keep the existing record
+    #[default]
+    Skip,
+    /// Replace the existing record
+    Overwrite,
+    /// Keep whichever record has the newest `created_at`
+    Newest,
+}
+
+impl ConflictPolicy {
+    /// Upsert clause for a table with the given key and value columns, valid in both SQLite
+    /// and PostgreSQL
+    pub(super) fn upsert_clause(self, table: &str, key: &str, columns: &[&str]) -> String {
+        let assignments = columns
+            .iter()
+            .map(|column| format!("{column} = excluded.{column}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match self {
+            Self::Skip => format!(" ON CONFLICT ({key}) DO NOTHING"),
+            Self::Overwrite => format!(" ON CONFLICT ({key}) DO UPDATE SET {assignments}"),
+            Self::Newest => format!(
+                " ON CONFLICT ({key}) DO UPDATE SET {assignments} WHERE excluded.created_at > {table}.created_at"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upsert_clause() {
+        let columns = ["created_at", "title"];
+        assert_eq!(
+            ConflictPolicy::Skip.upsert_clause("rent_item", "url", &columns),
+            " ON CONFLICT (url) DO NOTHING"
+        );
+        assert_eq!(
+            ConflictPolicy::Overwrite.upsert_clause("rent_item", "url", &columns),
+            " ON CONFLICT (url) DO UPDATE SET created_at = excluded.created_at, title = excluded.title"
+        );
+        assert!(
+            ConflictPolicy::Newest
+                .upsert_clause("rent_item", "url", &columns)
+                .ends_with("WHERE excluded.created_at > rent_item.created_at")
+        );
+    }
+}
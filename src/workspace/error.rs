@@ -1,6 +1,9 @@
+use std::path::PathBuf;
+
 use miette::Diagnostic;
 use thiserror::Error;
 
+use super::LockHolder;
 use crate::file::FileError;
 
 #[derive(Debug, Error, Diagnostic)]
@@ -16,4 +19,28 @@ pub enum WorkspaceError {
     #[error(transparent)]
     #[diagnostic(code(file::migration_error))]
     Migration(#[from] sqlx::migrate::MigrateError),
+
+    #[error("workspace is locked by {}", match holder {
+        Some(holder) => holder.to_string(),
+        None => "another run".to_string(),
+    })]
+    #[diagnostic(
+        code(workspace::locked),
+        help(
+            "another rentmap run is using this workspace, wait for it to finish or pass `--wait`. \
+The lock on `{}` is released as soon as that run exits",
+            path.display()
+        )
+    )]
+    Locked {
+        path: PathBuf,
+        holder: Option<LockHolder>,
+    },
+
+    #[error("{0} needs the workspace's SQLite database")]
+    #[diagnostic(
+        code(workspace::sqlite_only),
+        help("it is not available with a PostgreSQL `--database-url`, run it without one")
+    )]
+    SqliteOnly(&'static str),
 }
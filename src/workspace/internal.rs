@@ -1,52 +1,131 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 
-use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqliteSynchronous};
+use chrono::NaiveDate;
+use sanitise_file_name::sanitise;
+use sqlx::SqlitePool;
+use sqlx::sqlite::SqliteConnectOptions;
 use sqlx::types::Json;
-use sqlx::{QueryBuilder, SqlitePool};
 use tracing::{debug, info};
 use url::Url;
 
-use super::WorkspaceError;
+use super::storage::{PostgresStorage, SqliteStorage, Storage, StorageBackend};
+use super::{ConflictPolicy, LockMode, WorkspaceError, WorkspaceLock};
 use crate::file::make_directory;
-use crate::sites::rent591::{RentItem, RentList};
-use crate::web::Page;
+use crate::geocode::{BoundingBox, Distances, Geocode, ItemAddress, NearbyStation};
+use crate::sites::rent591::{
+    ExportRecord, Listing, RentItem, RentItemSummary, RentList, RentListPage, RentRecord,
+    SearchHit, SearchQuery, Stamped,
+};
+use crate::transit::Commutes;
+use crate::web::{Cookie, Page, PageSummary};
+
+const DATABASE_FILE: &str = "rentmap.sqlite";
 
 #[derive(Clone, Debug)]
 pub struct Workspace {
     pub root: PathBuf,
-    pub pool: SqlitePool,
+    pub(super) storage: StorageBackend,
+    /// Held from `init` until the last clone is dropped
+    lock: Option<Arc<WorkspaceLock>>,
 }
 
 impl Workspace {
-    /// Create a new workspace with the given root directory
+    /// Create a new workspace with the given root directory, stored in its SQLite database
     pub fn new(root: PathBuf) -> Self {
-        let path = root.join("rentmap.sqlite");
-        let options = SqliteConnectOptions::new()
-            .filename(path)
-            .foreign_keys(true)
-            .journal_mode(SqliteJournalMode::Wal)
-            .create_if_missing(true)
-            .synchronous(SqliteSynchronous::Normal)
-            .optimize_on_close(true, None);
-        let pool = SqlitePool::connect_lazy_with(options);
+        let storage = SqliteStorage::new(&root.join(DATABASE_FILE)).into();
+
+        Self {
+            root,
+            storage,
+            lock: None,
+        }
+    }
 
-        Self { root, pool }
+    /// Create a new workspace with the given root directory, stored in the PostgreSQL
+    /// database at `url`
+    ///
+    /// The root directory still holds the lock and the browser profiles, so each machine
+    /// sharing the database has its own. The lock only keeps runs on this machine apart, runs
+    /// on other machines write to the shared database concurrently.
+    pub fn with_postgres(root: PathBuf, url: &str) -> Result<Self, WorkspaceError> {
+        let storage = PostgresStorage::connect(url)?.into();
+
+        Ok(Self {
+            root,
+            storage,
+            lock: None,
+        })
     }
 
-    pub async fn init(&self) -> Result<(), WorkspaceError> {
+    /// Create the workspace if needed, lock it for this process and run migrations
+    ///
+    /// Fails with `WorkspaceError::Locked` if another process holds the lock in a conflicting
+    /// `mode`, unless `wait` is set, in which case it blocks until the lock is released.
+    pub async fn init(&mut self, mode: LockMode, wait: bool) -> Result<(), WorkspaceError> {
         make_directory(&self.root)?;
-        sqlx::migrate!().run(&self.pool).await?;
+        if self.lock.is_none() {
+            let lock = WorkspaceLock::acquire(&self.root, mode, wait).await?;
+            self.lock = Some(Arc::new(lock));
+        }
+        // read-only runs share the workspace lock, so they take turns migrating
+        let _migration = match mode {
+            LockMode::Exclusive => None,
+            LockMode::Shared => Some(WorkspaceLock::acquire_migration(&self.root).await?),
+        };
+        self.storage.migrate().await?;
         Ok(())
     }
 
+    /// Release the lock taken by `init`, for long-lived read-only runs like the preview
+    /// server that must not hold off runs writing to the workspace
+    pub fn unlock(&mut self) {
+        self.lock = None;
+    }
+
+    /// Close the connections to the database
+    pub async fn close(&self) {
+        self.storage.close().await;
+    }
+
+    /// The SQLite pool of the workspace, for operations that are only implemented for SQLite
+    pub(super) fn sqlite_pool(
+        &self,
+        operation: &'static str,
+    ) -> Result<&SqlitePool, WorkspaceError> {
+        match &self.storage {
+            StorageBackend::Sqlite(storage) => Ok(&storage.pool),
+            StorageBackend::Postgres(_) => Err(WorkspaceError::SqliteOnly(operation)),
+        }
+    }
+
+    /// Path of the workspace database
+    pub fn database_path(&self) -> PathBuf {
+        self.root.join(DATABASE_FILE)
+    }
+
+    /// Open a separate read-only pool on the workspace database, which must already exist
+    pub fn read_only_pool(&self) -> SqlitePool {
+        let options = SqliteConnectOptions::new()
+            .filename(self.database_path())
+            .read_only(true);
+
+        SqlitePool::connect_lazy_with(options)
+    }
+
+    /// Get the browser profile directory with the given name, creating it if needed
+    pub fn profile_dir(&self, name: &str) -> Result<PathBuf, WorkspaceError> {
+        let path = self.root.join("profiles").join(sanitise(name));
+        make_directory(&path)?;
+        Ok(path)
+    }
+
     // List operations
 
     /// Check if a list exists for the given URL
     pub async fn list_exists(&self, url: &Url) -> Result<bool, WorkspaceError> {
-        let exists = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM rent_list WHERE url = ?)")
-            .bind(Json(url))
-            .fetch_one(&self.pool)
-            .await?;
+        let exists = self.storage.list_exists(url).await?;
 
         debug!("check list exists");
 
@@ -55,33 +134,7 @@ impl Workspace {
 
     /// Insert a new rent list with item summaries
     pub async fn insert_list(&self, list: &RentList) -> Result<(), WorkspaceError> {
-        let mut tx = self.pool.begin().await?;
-
-        let id: i64 = sqlx::query_scalar(
-            "INSERT INTO rent_list (url, page_count, item_count) VALUES (?, ?, ?) RETURNING id",
-        )
-        .bind(&list.url)
-        .bind(list.page_count)
-        .bind(list.item_count)
-        .fetch_one(&mut *tx)
-        .await?;
-
-        for summary in list.item_summaries() {
-            sqlx::query(
-                "INSERT OR REPLACE INTO rent_item_summary (list_id, url, title, price, tags, txts, images) VALUES (?, ?, ?, ?, ?, ?, ?)"
-            )
-            .bind(id)
-            .bind(&summary.url)
-            .bind(&summary.title)
-            .bind(&summary.price)
-            .bind(&summary.tags)
-            .bind(&summary.txts)
-            .bind(&summary.images)
-            .execute(&mut *tx)
-            .await?;
-        }
-
-        tx.commit().await?;
+        self.storage.insert_list(list).await?;
 
         info!("insert list");
 
@@ -90,24 +143,30 @@ impl Workspace {
 
     /// Get the latest list for a URL
     pub async fn select_list(&self, url: &Url) -> Result<Option<RentList>, WorkspaceError> {
-        let rent_list = sqlx::query_as("SELECT url, page_count, item_count FROM rent_list WHERE url = ? ORDER BY created_at DESC LIMIT 1")
-            .bind(Json(url))
-            .fetch_optional(&self.pool)
-            .await?;
+        let rent_list = self.storage.select_list(url).await?;
 
         debug!("select list");
 
         Ok(rent_list)
     }
 
+    /// Get all item summaries from the latest list
+    pub async fn select_item_summaries(
+        &self,
+        url: &Url,
+    ) -> Result<Vec<RentItemSummary>, WorkspaceError> {
+        let summaries = self.storage.select_item_summaries(url).await?;
+
+        info!(count = summaries.len(), "select item summaries in list");
+
+        Ok(summaries)
+    }
+
     // Item operations
 
     /// Check if an item exists for the given URL
     pub async fn item_exists(&self, url: &Url) -> Result<bool, WorkspaceError> {
-        let exists = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM rent_item WHERE url = ?)")
-            .bind(Json(url))
-            .fetch_one(&self.pool)
-            .await?;
+        let exists = self.storage.item_exists(url).await?;
 
         debug!("check item exists");
 
@@ -115,33 +174,8 @@ impl Workspace {
     }
 
     /// Insert multiple rent items
-    pub async fn insert_items<'a, I>(&self, items: I) -> Result<(), WorkspaceError>
-    where
-        I: IntoIterator<Item = &'a RentItem>,
-    {
-        let mut tx = self.pool.begin().await?;
-        let items: Vec<_> = items.into_iter().collect();
-
-        for item in &items {
-            sqlx::query(
-"INSERT OR REPLACE INTO rent_item (url, title, labels, patterns, content, phone, album, area, floor, price, address) 
-VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
-                .bind(&item.url)
-                .bind(&item.title)
-                .bind(&item.labels)
-                .bind(&item.patterns)
-                .bind(&item.content)
-                .bind(&item.phone)
-                .bind(&item.album)
-                .bind(&item.area)
-                .bind(&item.floor)
-                .bind(&item.price)
-                .bind(&item.address)
-                .execute(&mut *tx)
-                .await?;
-        }
-
-        tx.commit().await?;
+    pub async fn insert_items(&self, items: &[RentItem]) -> Result<(), WorkspaceError> {
+        self.storage.insert_items(items).await?;
 
         info!(count = items.len(), "insert items");
 
@@ -150,10 +184,7 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
 
     /// Get the latest item for a URL
     pub async fn select_item(&self, url: &Url) -> Result<Option<RentItem>, WorkspaceError> {
-        let item = sqlx::query_as("SELECT url, title, labels, patterns, content, phone, album, area, floor, price, address FROM rent_item WHERE url = ?")
-            .bind(Json(url))
-            .fetch_optional(&self.pool)
-            .await?;
+        let item = self.storage.select_item(url).await?;
 
         debug!("select item");
 
@@ -169,23 +200,10 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
         refresh: bool,
         limit: Option<u32>,
     ) -> Result<Vec<Json<Url>>, WorkspaceError> {
-        let mut builder =
-            QueryBuilder::new("WITH LatestList AS (SELECT id FROM rent_list WHERE url = ");
-        builder.push_bind(Json(list_url));
-        builder.push(" ORDER BY created_at DESC LIMIT 1) SELECT DISTINCT ris.url FROM rent_item_summary ris JOIN LatestList ll ON ris.list_id = ll.id");
-
-        if !refresh {
-            builder.push(" WHERE NOT EXISTS (SELECT 1 FROM rent_item ri WHERE ri.url = ris.url)");
-        }
-
-        builder.push(" ORDER BY ris.url");
-
-        if let Some(limit) = limit {
-            builder.push(" LIMIT ");
-            builder.push_bind(limit);
-        }
-
-        let urls = builder.build_query_scalar().fetch_all(&self.pool).await?;
+        let urls = self
+            .storage
+            .select_item_urls_with(list_url, refresh, limit)
+            .await?;
 
         info!(url_count = urls.len(), refresh, limit, "select item urls");
 
@@ -194,35 +212,165 @@ VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)")
 
     /// Get all latest items from a list
     pub async fn select_items(&self, url: &Url) -> Result<Vec<RentItem>, WorkspaceError> {
-        let items = sqlx::query_as(
-            "
-WITH LatestList AS (
-    SELECT id FROM rent_list WHERE url = ? ORDER BY created_at DESC LIMIT 1
-)
-SELECT
-    ri.url, ri.title, ri.labels, ri.patterns, ri.content,
-    ri.phone, ri.album, ri.area, ri.floor, ri.price, ri.address
-FROM rent_item ri
-JOIN rent_item_summary ris ON ri.url = ris.url
-JOIN LatestList ll ON ris.list_id = ll.id",
-        )
-        .bind(Json(url))
-        .fetch_all(&self.pool)
-        .await?;
+        let items = self.storage.select_items(Some(url)).await?;
 
         info!(count = items.len(), "select items in list");
 
         Ok(items)
     }
 
+    /// Get listings with their latest summary and details, optionally limited to the latest
+    /// snapshot of one list and to listings first seen on or after a date
+    pub async fn select_listings(
+        &self,
+        list_url: Option<&Url>,
+        posted_after: Option<NaiveDate>,
+    ) -> Result<Vec<Listing>, WorkspaceError> {
+        let (summaries, first_seen): (Vec<_>, Vec<_>) = self
+            .storage
+            .select_listing_summaries(list_url, posted_after)
+            .await?
+            .into_iter()
+            .unzip();
+
+        let listings: Vec<_> = self
+            .join_records(list_url, summaries)
+            .await?
+            .into_iter()
+            .zip(first_seen)
+            .map(|(record, first_seen)| Listing::new(record, first_seen))
+            .collect();
+
+        info!(count = listings.len(), "select listings");
+
+        Ok(listings)
+    }
+
+    /// Get the item summaries of the latest snapshot of a list with their details, distances,
+    /// stations and commutes
+    pub async fn select_export_records(
+        &self,
+        list_url: &Url,
+    ) -> Result<Vec<ExportRecord>, WorkspaceError> {
+        let summaries = self.storage.select_item_summaries(list_url).await?;
+        let records = self.join_records(Some(list_url), summaries).await?;
+
+        info!(count = records.len(), "select export records");
+
+        Ok(records)
+    }
+
+    /// Get the latest snapshot of a list and its scraped items as import records, the list first
+    pub async fn select_rent_records(
+        &self,
+        list_url: &Url,
+    ) -> Result<Vec<RentRecord>, WorkspaceError> {
+        let Some(list) = self.storage.select_list(list_url).await? else {
+            return Ok(Vec::new());
+        };
+
+        let created_at = self.storage.select_list_created_at(list_url).await?;
+        let summaries = self.storage.select_item_summaries(list_url).await?;
+        let list = RentList::new(
+            list.url.0,
+            list.page_count,
+            list.item_count,
+            vec![RentListPage::new(summaries)],
+        );
+
+        let items = self.storage.select_stamped_items(list_url).await?;
+
+        let records: Vec<_> = std::iter::once(RentRecord::List(Stamped {
+            value: list,
+            created_at,
+        }))
+        .chain(items.into_iter().map(|(item, created_at)| {
+            RentRecord::Item(Box::new(Stamped {
+                value: item,
+                created_at: Some(created_at),
+            }))
+        }))
+        .collect();
+
+        info!(count = records.len(), "select rent records");
+
+        Ok(records)
+    }
+
+    /// Join summaries with the details, distances, stations and commutes of their items, of
+    /// the latest snapshot of a list or of every item if `list_url` is `None`
+    async fn join_records(
+        &self,
+        list_url: Option<&Url>,
+        summaries: Vec<RentItemSummary>,
+    ) -> Result<Vec<ExportRecord>, WorkspaceError> {
+        let items = self.storage.select_items(list_url).await?;
+        let distances = self.storage.select_item_distances(list_url).await?;
+        let stations = self.storage.select_item_stations(list_url).await?;
+        let commutes = self.storage.select_item_commutes(list_url).await?;
+
+        let mut records = ExportRecord::join(summaries, items);
+        ExportRecord::set_by_url(&mut records, distances, |record| &mut record.distances);
+        ExportRecord::set_by_url(&mut records, stations, |record| &mut record.stations);
+        ExportRecord::set_by_url(&mut records, commutes, |record| &mut record.commutes);
+
+        Ok(records)
+    }
+
+    /// Search scraped items for all terms of a query, most relevant first
+    pub async fn search_items(
+        &self,
+        query: &SearchQuery,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, WorkspaceError> {
+        let mut hits = self.storage.search_items(query, limit).await?;
+
+        for hit in &mut hits {
+            hit.highlight(&query.unindexed);
+        }
+
+        info!(count = hits.len(), "search items");
+
+        Ok(hits)
+    }
+
+    // Import operations
+
+    /// Import a list snapshot taken at `created_at` (now if `None`), returning whether it was written
+    ///
+    /// A snapshot conflicts with an existing one of the same URL and `created_at`, or with
+    /// `ConflictPolicy::Newest`, with any newer snapshot of the same URL.
+    pub async fn import_list(
+        &self,
+        list: &RentList,
+        created_at: Option<&str>,
+        policy: ConflictPolicy,
+    ) -> Result<bool, WorkspaceError> {
+        let written = self.storage.import_list(list, created_at, policy).await?;
+
+        debug!(written, "import list");
+
+        Ok(written)
+    }
+
+    /// Import items scraped at `created_at` (now if `None`), returning the number written
+    pub async fn import_items(
+        &self,
+        items: &[(&RentItem, Option<&str>)],
+        policy: ConflictPolicy,
+    ) -> Result<u64, WorkspaceError> {
+        let written = self.storage.import_items(items, policy).await?;
+
+        info!(written, ?policy, "import items");
+
+        Ok(written)
+    }
+
     // Page cache operations
 
     /// Get cached page HTML by URL
     pub async fn get_cached_page(&self, url: &Url) -> Result<Option<Page>, WorkspaceError> {
-        let page = sqlx::query_as("SELECT url, html FROM page_cache WHERE url = ?")
-            .bind(Json(url))
-            .fetch_optional(&self.pool)
-            .await?;
+        let page = self.storage.get_cached_page(url).await?;
 
         if page.is_some() {
             debug!("cached page found");
@@ -233,16 +381,141 @@ JOIN LatestList ll ON ris.list_id = ll.id",
         Ok(page)
     }
 
+    /// List all cached pages, newest first
+    pub async fn select_cached_pages(&self) -> Result<Vec<PageSummary>, WorkspaceError> {
+        let pages = self.storage.select_cached_pages().await?;
+
+        debug!(count = pages.len(), "select cached pages");
+
+        Ok(pages)
+    }
+
     /// Cache a page's HTML content
     pub async fn cache_page(&self, page: &Page) -> Result<(), WorkspaceError> {
-        sqlx::query("INSERT OR REPLACE INTO page_cache (url, html) VALUES (?, ?)")
-            .bind(&page.url)
-            .bind(&page.html)
-            .execute(&self.pool)
-            .await?;
+        self.storage.cache_page(page).await?;
 
         debug!("cache page");
 
         Ok(())
     }
+
+    // Cookie operations
+
+    /// Get all unexpired browser cookies
+    pub async fn select_cookies(&self) -> Result<Vec<Cookie>, WorkspaceError> {
+        let cookies = self.storage.select_cookies().await?;
+
+        debug!(count = cookies.len(), "select cookies");
+
+        Ok(cookies)
+    }
+
+    /// Insert or replace browser cookies
+    pub async fn save_cookies(&self, cookies: &[Cookie]) -> Result<(), WorkspaceError> {
+        self.storage.save_cookies(cookies).await?;
+
+        info!(count = cookies.len(), "save cookies");
+
+        Ok(())
+    }
+
+    // Geocode operations
+
+    /// Get the address read for a listing
+    pub async fn select_item_address(
+        &self,
+        url: &Url,
+    ) -> Result<Option<ItemAddress>, WorkspaceError> {
+        let address = self.storage.select_item_address(url).await?;
+
+        debug!(found = address.is_some(), "select item address");
+
+        Ok(address)
+    }
+
+    /// Save the address read for a listing
+    pub async fn save_item_address(&self, address: &ItemAddress) -> Result<(), WorkspaceError> {
+        self.storage.save_item_address(address).await?;
+
+        debug!(?address, "save item address");
+
+        Ok(())
+    }
+
+    /// Get the cached geocoding result of a normalized address
+    pub async fn select_geocode(&self, address: &str) -> Result<Option<Geocode>, WorkspaceError> {
+        let geocode = self.storage.select_geocode(address).await?;
+
+        debug!(address, cached = geocode.is_some(), "select geocode");
+
+        Ok(geocode)
+    }
+
+    /// Cache the geocoding result of a normalized address
+    pub async fn save_geocode(&self, geocode: &Geocode) -> Result<(), WorkspaceError> {
+        self.storage.save_geocode(geocode).await?;
+
+        debug!(?geocode, "save geocode");
+
+        Ok(())
+    }
+
+    /// Replace the distances to points of interest of a listing
+    pub async fn save_item_distances(
+        &self,
+        url: &Url,
+        distances: &Distances,
+    ) -> Result<(), WorkspaceError> {
+        self.storage.save_item_distances(url, distances).await?;
+
+        debug!(%url, ?distances, "save item distances");
+
+        Ok(())
+    }
+
+    /// Replace the nearby stations of a listing
+    pub async fn save_item_stations(
+        &self,
+        url: &Url,
+        stations: &[NearbyStation],
+    ) -> Result<(), WorkspaceError> {
+        self.storage.save_item_stations(url, stations).await?;
+
+        debug!(%url, count = stations.len(), "save item stations");
+
+        Ok(())
+    }
+
+    /// Get the location of every geocoded listing, of the latest snapshot of a list if
+    /// `list_url` is given and within a bounding box if `bounds` is, looked up in the spatial
+    /// index of geocoded coordinates
+    pub async fn select_item_locations(
+        &self,
+        list_url: Option<&Url>,
+        bounds: Option<&BoundingBox>,
+    ) -> Result<HashMap<Url, (f64, f64)>, WorkspaceError> {
+        let locations = self.storage.select_item_locations(list_url, bounds).await?;
+
+        debug!(
+            ?list_url,
+            ?bounds,
+            count = locations.len(),
+            "select item locations"
+        );
+
+        Ok(locations)
+    }
+
+    /// Save the commute minutes of a listing, keeping those to other destinations
+    pub async fn save_item_commutes(
+        &self,
+        url: &Url,
+        commutes: &Commutes,
+    ) -> Result<(), WorkspaceError> {
+        self.storage.save_item_commutes(url, commutes).await?;
+
+        debug!(%url, ?commutes, "save item commutes");
+
+        Ok(())
+    }
 }
@@ -0,0 +1,270 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions, TryLockError};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+
+use super::WorkspaceError;
+use crate::file::{FileError, PathError};
+
+const LOCK_FILE: &str = "rentmap.lock";
+const MIGRATION_LOCK_FILE: &str = "rentmap.migrate.lock";
+const WAIT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// The process holding a workspace lock exclusively, as recorded in the lock file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    pub hostname: String,
+    pub acquired_at: String,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            hostname: hostname(),
+            acquired_at: Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        }
+    }
+}
+
+impl fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "pid {} on {} since {} UTC",
+            self.pid, self.hostname, self.acquired_at
+        )
+    }
+}
+
+/// How a workspace is locked
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockMode {
+    /// For runs that write to the workspace, excluding every other run
+    Exclusive,
+    /// For read-only runs, which can share the workspace with each other but not with a
+    /// writer
+    Shared,
+}
+
+/// An advisory lock on a workspace, released when dropped
+///
+/// The lock is an OS file lock on a `rentmap.lock` file in the workspace root, so it is
+/// released by the OS when the holding process exits, however it exits. The file itself is
+/// never removed. An exclusive holder writes itself into the file so that runs waiting for
+/// it can name it.
+///
+/// The lock lives in the workspace directory, so it does not keep apart runs on different
+/// machines sharing a PostgreSQL database.
+#[derive(Debug)]
+pub struct WorkspaceLock {
+    path: PathBuf,
+    file: File,
+    mode: LockMode,
+}
+
+impl WorkspaceLock {
+    /// Acquire the lock of the workspace at `root`, polling until it is free if `wait`
+    pub async fn acquire(root: &Path, mode: LockMode, wait: bool) -> Result<Self, WorkspaceError> {
+        Self::acquire_file(root.join(LOCK_FILE), mode, wait).await
+    }
+
+    /// Wait for the lock that serializes migrations between read-only runs, which share the
+    /// workspace lock
+    pub(super) async fn acquire_migration(root: &Path) -> Result<Self, WorkspaceError> {
+        Self::acquire_file(root.join(MIGRATION_LOCK_FILE), LockMode::Exclusive, true).await
+    }
+
+    async fn acquire_file(
+        path: PathBuf,
+        mode: LockMode,
+        wait: bool,
+    ) -> Result<Self, WorkspaceError> {
+        let mut waiting = false;
+
+        loop {
+            match Self::try_acquire(&path, mode).map_err(FileError::from)? {
+                Some(lock) => {
+                    debug!(path = %path.display(), ?mode, "acquire workspace lock");
+                    return Ok(lock);
+                }
+                None if wait => {
+                    if !waiting {
+                        let holder = read_holder(&path);
+                        info!(holder = ?holder.map(|holder| holder.to_string()), "wait for workspace lock");
+                        waiting = true;
+                    }
+                    tokio::time::sleep(WAIT_INTERVAL).await;
+                }
+                None => {
+                    let holder = read_holder(&path);
+                    return Err(WorkspaceError::Locked { path, holder });
+                }
+            }
+        }
+    }
+
+    /// Try to take the lock once, `None` if it is held in a conflicting mode
+    fn try_acquire(path: &Path, mode: LockMode) -> Result<Option<Self>, PathError> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .map_err(|source| PathError::new(path, source))?;
+
+        let locked = match mode {
+            LockMode::Exclusive => file.try_lock(),
+            LockMode::Shared => file.try_lock_shared(),
+        };
+        match locked {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(source)) => return Err(PathError::new(path, source)),
+        }
+
+        if mode == LockMode::Exclusive {
+            let contents =
+                serde_json::to_string(&LockHolder::current()).expect("lock holder serializes");
+            file.set_len(0)
+                .and_then(|()| file.write_all(contents.as_bytes()))
+                .map_err(|source| PathError::new(path, source))?;
+        }
+
+        Ok(Some(Self {
+            path: path.to_path_buf(),
+            file,
+            mode,
+        }))
+    }
+}
+
+impl Drop for WorkspaceLock {
+    fn drop(&mut self) {
+        // forget the holder before the OS lock is released with the file
+        if self.mode == LockMode::Exclusive {
+            self.file.set_len(0).ok();
+        }
+        debug!(path = %self.path.display(), mode = ?self.mode, "release workspace lock");
+    }
+}
+
+/// Read the exclusive holder of a lock file, `None` if it is held shared or is unreadable
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    // some platforms refuse reads of a file locked by another process
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+}
+
+fn hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .into_iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .or_else(|| std::env::var("COMPUTERNAME").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file::TempDir;
+    use crate::workspace::Workspace;
+
+    #[tokio::test]
+    async fn test_lock_is_exclusive() {
+        let root = TempDir::new("lock-exclusive");
+
+        let lock = WorkspaceLock::acquire(&root, LockMode::Exclusive, false)
+            .await
+            .unwrap();
+        let err = WorkspaceLock::acquire(&root, LockMode::Exclusive, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            WorkspaceError::Locked { holder: Some(holder), .. } if holder.pid == std::process::id()
+        ));
+        let err = WorkspaceLock::acquire(&root, LockMode::Shared, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WorkspaceError::Locked { .. }));
+
+        drop(lock);
+        assert_eq!(read_holder(&root.join(LOCK_FILE)), None);
+        WorkspaceLock::acquire(&root, LockMode::Exclusive, false)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_shared_locks_exclude_writers_only() {
+        let root = TempDir::new("lock-shared");
+
+        let first = WorkspaceLock::acquire(&root, LockMode::Shared, false)
+            .await
+            .unwrap();
+        let second = WorkspaceLock::acquire(&root, LockMode::Shared, false)
+            .await
+            .unwrap();
+        let err = WorkspaceLock::acquire(&root, LockMode::Exclusive, false)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, WorkspaceError::Locked { holder: None, .. }));
+
+        drop(first);
+        drop(second);
+        WorkspaceLock::acquire(&root, LockMode::Exclusive, false)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_leftover_lock_file_is_not_a_lock() {
+        let root = TempDir::new("lock-leftover");
+
+        let gone = LockHolder {
+            pid: u32::MAX,
+            hostname: hostname(),
+            acquired_at: "2025-01-01 00:00:00".to_string(),
+        };
+        fs::write(root.join(LOCK_FILE), serde_json::to_string(&gone).unwrap()).unwrap();
+
+        let _lock = WorkspaceLock::acquire(&root, LockMode::Exclusive, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            read_holder(&root.join(LOCK_FILE)).map(|holder| holder.pid),
+            Some(std::process::id())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unlocked_workspace_lets_writers_in() {
+        let root = TempDir::new("lock-unlock");
+
+        let mut workspace = Workspace::new(root.to_path_buf());
+        workspace.init(LockMode::Shared, false).await.unwrap();
+        assert!(
+            WorkspaceLock::acquire(&root, LockMode::Exclusive, false)
+                .await
+                .is_err()
+        );
+
+        workspace.unlock();
+        WorkspaceLock::acquire(&root, LockMode::Exclusive, false)
+            .await
+            .unwrap();
+
+        workspace.close().await;
+    }
+}
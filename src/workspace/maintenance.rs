@@ -0,0 +1,83 @@
+use sqlx::FromRow;
+use sqlx::types::Json;
+use tracing::{debug, info};
+use url::Url;
+
+use super::storage::Storage;
+use super::{Workspace, WorkspaceError};
+
+/// Row counts and sizes of a workspace
+#[derive(Clone, Debug)]
+pub struct WorkspaceStats {
+    pub list_snapshots: i64,
+    pub item_summaries: i64,
+    pub items: i64,
+    pub cached_pages: i64,
+    /// Total HTML size of the cached pages in bytes
+    pub cache_size: i64,
+    pub cookies: i64,
+    /// Size of the database in bytes, without the write-ahead log of SQLite
+    pub database_size: i64,
+    pub lists: Vec<ListSnapshots>,
+}
+
+/// Snapshots taken of one list URL
+#[derive(Clone, Debug, FromRow)]
+pub struct ListSnapshots {
+    pub url: Json<Url>,
+    pub count: i64,
+    pub oldest: String,
+    pub newest: String,
+}
+
+/// What to remove when pruning a workspace, nothing is removed by default
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PrunePolicy {
+    /// Keep only this many of the newest snapshots of each list
+    pub keep_snapshots: Option<u32>,
+    /// Drop cached pages older than this many days
+    pub max_cache_days: Option<u32>,
+    /// Drop items that no list snapshot refers to
+    pub orphan_items: bool,
+}
+
+/// Rows removed, or that would be removed, by pruning
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PruneReport {
+    pub list_snapshots: u64,
+    pub item_summaries: u64,
+    pub items: u64,
+    pub cached_pages: u64,
+}
+
+impl Workspace {
+    // Maintenance operations
+
+    /// Count the rows and measure the size of the workspace
+    pub async fn stats(&self) -> Result<WorkspaceStats, WorkspaceError> {
+        let stats = self.storage.stats().await?;
+
+        debug!("select workspace stats");
+
+        Ok(stats)
+    }
+
+    /// Remove rows according to the policy and reclaim the space with `VACUUM`
+    ///
+    /// With `dry_run` the removal is rolled back and only the counts are reported.
+    pub async fn prune(
+        &self,
+        policy: PrunePolicy,
+        dry_run: bool,
+    ) -> Result<PruneReport, WorkspaceError> {
+        let report = self.storage.prune(policy, dry_run).await?;
+
+        if dry_run {
+            info!(?report, "prune workspace (dry run)");
+        } else {
+            info!(?report, "prune workspace");
+        }
+
+        Ok(report)
+    }
+}
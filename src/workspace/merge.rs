@@ -0,0 +1,240 @@
+use std::path::Path;
+
+use sqlx::SqliteConnection;
+use tracing::info;
+
+use super::{Workspace, WorkspaceError};
+
+/// Rows merged from another workspace
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MergeReport {
+    pub list_snapshots: u64,
+    pub item_summaries: u64,
+    pub items: u64,
+    pub cached_pages: u64,
+    pub geocodes: u64,
+}
+
+/// Child tables of `rent_item_summary` and their value column
+const SUMMARY_CHILDREN: [(&str, &str); 3] = [
+    ("rent_item_summary_tag", "tag"),
+    ("rent_item_summary_txt", "txt"),
+    ("rent_item_summary_image", "url"),
+];
+
+/// Child tables of `rent_item` and their value column
+const ITEM_CHILDREN: [(&str, &str); 3] = [
+    ("rent_item_label", "label"),
+    ("rent_item_pattern", "pattern"),
+    ("rent_item_image", "url"),
+];
+
+impl Workspace {
+    // Merge operations
+
+    /// Merge the database at `path`, which must be migrated to the same version, into this one
+    ///
+    /// List snapshots missing here are copied with their summaries. Items, cached pages,
+    /// geocodes, listing addresses, distances, nearby stations and commutes are copied when
+    /// missing or when their `created_at` is newer than the one here, ties keep the existing
+    /// row.
+    pub async fn merge_database(&self, path: &Path) -> Result<MergeReport, WorkspaceError> {
+        let mut conn = self.sqlite_pool("workspace merge")?.acquire().await?;
+
+        sqlx::query("ATTACH DATABASE ? AS other")
+            .bind(path.to_string_lossy())
+            .execute(&mut *conn)
+            .await?;
+
+        let result = Self::merge_attached(&mut conn).await;
+
+        sqlx::query("DETACH DATABASE other")
+            .execute(&mut *conn)
+            .await?;
+
+        let report = result?;
+
+        info!(path = %path.display(), ?report, "merge workspace");
+
+        Ok(report)
+    }
+
+    async fn merge_attached(conn: &mut SqliteConnection) -> Result<MergeReport, WorkspaceError> {
+        let mut tx = sqlx::Connection::begin(conn).await?;
+        let mut report = MergeReport::default();
+
+        // list snapshots are identified by URL and `created_at`, copy the missing ones
+        sqlx::query(
+            "
+CREATE TEMP TABLE merge_list AS
+SELECT o.id AS other_id, o.url, o.created_at, o.page_count, o.item_count
+FROM other.rent_list o
+WHERE NOT EXISTS (
+    SELECT 1 FROM main.rent_list m WHERE m.url = o.url AND m.created_at = o.created_at
+)",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        report.list_snapshots = sqlx::query(
+            "INSERT INTO main.rent_list (url, created_at, page_count, item_count) \
+SELECT url, created_at, page_count, item_count FROM merge_list ORDER BY created_at, other_id",
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        const LIST_IDS: &str = "
+JOIN merge_list ml ON s.list_id = ml.other_id
+JOIN main.rent_list m ON m.url = ml.url AND m.created_at = ml.created_at";
+
+        report.item_summaries = sqlx::query(&format!(
+            "INSERT INTO main.rent_item_summary (list_id, url, title, price) \
+SELECT m.id, s.url, s.title, s.price FROM other.rent_item_summary s{LIST_IDS}"
+        ))
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        for (table, column) in SUMMARY_CHILDREN {
+            sqlx::query(&format!(
+                "INSERT INTO main.{table} (list_id, item_url, position, {column}) \
+SELECT m.id, s.item_url, s.position, s.{column} FROM other.{table} s{LIST_IDS}"
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // items are identified by URL, the newest `created_at` wins
+        sqlx::query(
+            "
+CREATE TEMP TABLE merge_item AS
+SELECT o.url
+FROM other.rent_item o
+LEFT JOIN main.rent_item m ON m.url = o.url
+WHERE m.url IS NULL OR o.created_at > m.created_at",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        for (table, _) in ITEM_CHILDREN {
+            sqlx::query(&format!(
+                "DELETE FROM main.{table} WHERE item_url IN (SELECT url FROM merge_item)"
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        report.items = sqlx::query(
+            "
+INSERT INTO main.rent_item (url, created_at, title, content, phone, area, floor, price, address)
+SELECT o.url, o.created_at, o.title, o.content, o.phone, o.area, o.floor, o.price, o.address
+FROM other.rent_item o
+WHERE o.url IN (SELECT url FROM merge_item)
+ON CONFLICT (url) DO UPDATE SET
+    created_at = excluded.created_at, title = excluded.title, content = excluded.content,
+    phone = excluded.phone, area = excluded.area, floor = excluded.floor,
+    price = excluded.price, address = excluded.address",
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        for (table, column) in ITEM_CHILDREN {
+            sqlx::query(&format!(
+                "INSERT INTO main.{table} (item_url, position, {column}) \
+SELECT item_url, position, {column} FROM other.{table} WHERE item_url IN (SELECT url FROM merge_item)"
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        report.cached_pages = sqlx::query(
+            "
+INSERT INTO main.page_cache (url, created_at, html)
+SELECT url, created_at, html FROM other.page_cache WHERE true
+ON CONFLICT (url) DO UPDATE SET created_at = excluded.created_at, html = excluded.html
+WHERE excluded.created_at > page_cache.created_at",
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // geocodes, addresses and distances are keyed by address and URL, the newest wins too
+        report.geocodes = sqlx::query(
+            "
+INSERT INTO main.geocode
+    (address, created_at, lat, lng, formatted_address, location_type, place_id, provider)
+SELECT address, created_at, lat, lng, formatted_address, location_type, place_id, provider
+FROM other.geocode WHERE true
+ON CONFLICT (address) DO UPDATE SET
+    created_at = excluded.created_at, lat = excluded.lat, lng = excluded.lng,
+    formatted_address = excluded.formatted_address, location_type = excluded.location_type,
+    place_id = excluded.place_id, provider = excluded.provider
+WHERE excluded.created_at > geocode.created_at",
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        sqlx::query(
+            "
+INSERT INTO main.rent_item_address (item_url, created_at, address, normalized, source)
+SELECT item_url, created_at, address, normalized, source FROM other.rent_item_address WHERE true
+ON CONFLICT (item_url) DO UPDATE SET
+    created_at = excluded.created_at, address = excluded.address,
+    normalized = excluded.normalized, source = excluded.source
+WHERE excluded.created_at > rent_item_address.created_at",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "
+INSERT INTO main.rent_item_distance (item_url, poi, created_at, distance_km)
+SELECT item_url, poi, created_at, distance_km FROM other.rent_item_distance WHERE true
+ON CONFLICT (item_url, poi) DO UPDATE SET
+    created_at = excluded.created_at, distance_km = excluded.distance_km
+WHERE excluded.created_at > rent_item_distance.created_at",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "
+INSERT INTO main.rent_item_station
+    (item_url, line, created_at, code, name, distance_m, walk_m, walk_minutes)
+SELECT item_url, line, created_at, code, name, distance_m, walk_m, walk_minutes
+FROM other.rent_item_station WHERE true
+ON CONFLICT (item_url, line) DO UPDATE SET
+    created_at = excluded.created_at, code = excluded.code, name = excluded.name,
+    distance_m = excluded.distance_m, walk_m = excluded.walk_m,
+    walk_minutes = excluded.walk_minutes
+WHERE excluded.created_at > rent_item_station.created_at",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "
+INSERT INTO main.rent_item_commute (item_url, poi, created_at, minutes)
+SELECT item_url, poi, created_at, minutes FROM other.rent_item_commute WHERE true
+ON CONFLICT (item_url, poi) DO UPDATE SET
+    created_at = excluded.created_at, minutes = excluded.minutes
+WHERE excluded.created_at > rent_item_commute.created_at",
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DROP TABLE temp.merge_list")
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DROP TABLE temp.merge_item")
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+
+        Ok(report)
+    }
+}
@@ -1,7 +1,17 @@
 mod args;
+mod conflict;
 mod error;
 mod internal;
+mod lock;
+mod maintenance;
+mod merge;
+mod storage;
 
 pub use args::WorkspaceArgs;
+pub use conflict::ConflictPolicy;
 pub use error::WorkspaceError;
 pub use internal::Workspace;
+pub use lock::{LockHolder, LockMode, WorkspaceLock};
+pub use maintenance::{ListSnapshots, PrunePolicy, PruneReport, WorkspaceStats};
+pub use merge::MergeReport;
+pub use storage::{PostgresStorage, SqliteStorage, Storage, StorageBackend};
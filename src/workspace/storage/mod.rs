@@ -0,0 +1,1019 @@
+mod postgres;
+mod sqlite;
+
+use std::collections::HashMap;
+use std::future::Future;
+
+use chrono::NaiveDate;
+use sqlx::types::Json;
+use sqlx::{Database, Encode, FromRow, QueryBuilder, Type};
+use url::Url;
+
+use super::{ConflictPolicy, PrunePolicy, PruneReport, WorkspaceError, WorkspaceStats};
+use crate::geocode::{AddressSource, BoundingBox, Distances, Geocode, ItemAddress, NearbyStation};
+use crate::sites::rent591::{RentItem, RentItemSummary, RentList, SearchHit, SearchQuery};
+use crate::transit::Commutes;
+use crate::web::{Cookie, Page, PageSummary};
+
+pub use postgres::PostgresStorage;
+pub use sqlite::SqliteStorage;
+
+/// Database operations of a workspace, implemented for each supported database
+///
+/// Both implementations use the same schema, adapted to each database by its own
+/// migrations, and return the same rows for the same data.
+pub trait Storage {
+    /// Create or upgrade the schema
+    fn migrate(&self) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Close all connections
+    fn close(&self) -> impl Future<Output = ()> + Send;
+
+    /// Check if a list exists for the given URL
+    fn list_exists(&self, url: &Url) -> impl Future<Output = Result<bool, WorkspaceError>> + Send;
+
+    /// Insert a new snapshot of a rent list with its item summaries
+    fn insert_list(
+        &self,
+        list: &RentList,
+    ) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Get the latest snapshot of a list, without its pages
+    fn select_list(
+        &self,
+        url: &Url,
+    ) -> impl Future<Output = Result<Option<RentList>, WorkspaceError>> + Send;
+
+    /// Get when the latest snapshot of a list was taken
+    fn select_list_created_at(
+        &self,
+        url: &Url,
+    ) -> impl Future<Output = Result<Option<String>, WorkspaceError>> + Send;
+
+    /// Get all item summaries from the latest snapshot of a list, ordered by URL
+    fn select_item_summaries(
+        &self,
+        url: &Url,
+    ) -> impl Future<Output = Result<Vec<RentItemSummary>, WorkspaceError>> + Send;
+
+    /// Get the latest summary of every listing with when it was first seen in a list
+    /// snapshot, ordered by URL, optionally limited to the latest snapshot of one list and to
+    /// listings first seen on or after a date
+    fn select_listing_summaries(
+        &self,
+        list_url: Option<&Url>,
+        posted_after: Option<NaiveDate>,
+    ) -> impl Future<Output = Result<Vec<(RentItemSummary, String)>, WorkspaceError>> + Send;
+
+    /// Check if an item exists for the given URL
+    fn item_exists(&self, url: &Url) -> impl Future<Output = Result<bool, WorkspaceError>> + Send;
+
+    /// Insert or replace rent items
+    fn insert_items(
+        &self,
+        items: &[RentItem],
+    ) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Import a list snapshot taken at `created_at` (now if `None`), returning whether it was
+    /// written
+    ///
+    /// A snapshot conflicts with an existing one of the same URL and `created_at`, or with
+    /// `ConflictPolicy::Newest`, with any newer snapshot of the same URL.
+    fn import_list(
+        &self,
+        list: &RentList,
+        created_at: Option<&str>,
+        policy: ConflictPolicy,
+    ) -> impl Future<Output = Result<bool, WorkspaceError>> + Send;
+
+    /// Import items scraped at `created_at` (now if `None`), returning the number written
+    fn import_items(
+        &self,
+        items: &[(&RentItem, Option<&str>)],
+        policy: ConflictPolicy,
+    ) -> impl Future<Output = Result<u64, WorkspaceError>> + Send;
+
+    /// Get the item for a URL
+    fn select_item(
+        &self,
+        url: &Url,
+    ) -> impl Future<Output = Result<Option<RentItem>, WorkspaceError>> + Send;
+
+    /// Get item URLs from the latest snapshot of a list, ordered and optionally limited
+    ///
+    /// Without `refresh`, items that were already scraped are left out.
+    fn select_item_urls_with(
+        &self,
+        list_url: &Url,
+        refresh: bool,
+        limit: Option<u32>,
+    ) -> impl Future<Output = Result<Vec<Json<Url>>, WorkspaceError>> + Send;
+
+    /// Get the items of the latest snapshot of a list, or every item if `list_url` is `None`
+    fn select_items(
+        &self,
+        list_url: Option<&Url>,
+    ) -> impl Future<Output = Result<Vec<RentItem>, WorkspaceError>> + Send;
+
+    /// Get the items of the latest snapshot of a list with when each was scraped, ordered by URL
+    fn select_stamped_items(
+        &self,
+        list_url: &Url,
+    ) -> impl Future<Output = Result<Vec<(RentItem, String)>, WorkspaceError>> + Send;
+
+    /// Get a cached page by URL
+    fn get_cached_page(
+        &self,
+        url: &Url,
+    ) -> impl Future<Output = Result<Option<Page>, WorkspaceError>> + Send;
+
+    /// List all cached pages, newest first
+    fn select_cached_pages(
+        &self,
+    ) -> impl Future<Output = Result<Vec<PageSummary>, WorkspaceError>> + Send;
+
+    /// Insert or replace a cached page
+    fn cache_page(&self, page: &Page) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Get all unexpired browser cookies, leaving out session cookies which ended with the
+    /// browser that set them
+    fn select_cookies(&self) -> impl Future<Output = Result<Vec<Cookie>, WorkspaceError>> + Send;
+
+    /// Insert or replace browser cookies
+    fn save_cookies(
+        &self,
+        cookies: &[Cookie],
+    ) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Get the address read for a listing
+    fn select_item_address(
+        &self,
+        url: &Url,
+    ) -> impl Future<Output = Result<Option<ItemAddress>, WorkspaceError>> + Send;
+
+    /// Insert or replace the address read for a listing
+    fn save_item_address(
+        &self,
+        address: &ItemAddress,
+    ) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Get the geocoding result of a normalized address
+    fn select_geocode(
+        &self,
+        address: &str,
+    ) -> impl Future<Output = Result<Option<Geocode>, WorkspaceError>> + Send;
+
+    /// Insert or replace the geocoding result of a normalized address
+    fn save_geocode(
+        &self,
+        geocode: &Geocode,
+    ) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Get the distances to points of interest of the items in the latest snapshot of a list,
+    /// or of every item if `list_url` is `None`
+    fn select_item_distances(
+        &self,
+        list_url: Option<&Url>,
+    ) -> impl Future<Output = Result<HashMap<Url, Distances>, WorkspaceError>> + Send;
+
+    /// Replace the distances to points of interest of a listing
+    fn save_item_distances(
+        &self,
+        url: &Url,
+        distances: &Distances,
+    ) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Get the nearby stations of the items in the latest snapshot of a list, or of every item
+    /// if `list_url` is `None`, nearest first
+    fn select_item_stations(
+        &self,
+        list_url: Option<&Url>,
+    ) -> impl Future<Output = Result<HashMap<Url, Vec<NearbyStation>>, WorkspaceError>> + Send;
+
+    /// Replace the nearby stations of a listing
+    fn save_item_stations(
+        &self,
+        url: &Url,
+        stations: &[NearbyStation],
+    ) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Get the `(lat, lng)` location of every geocoded listing, of the latest snapshot of a
+    /// list if `list_url` is given and within a bounding box if `bounds` is
+    fn select_item_locations(
+        &self,
+        list_url: Option<&Url>,
+        bounds: Option<&BoundingBox>,
+    ) -> impl Future<Output = Result<HashMap<Url, (f64, f64)>, WorkspaceError>> + Send;
+
+    /// Get the commute minutes of the items in the latest snapshot of a list, or of every item
+    /// if `list_url` is `None`
+    fn select_item_commutes(
+        &self,
+        list_url: Option<&Url>,
+    ) -> impl Future<Output = Result<HashMap<Url, Commutes>, WorkspaceError>> + Send;
+
+    /// Insert or replace the commute minutes of a listing to some destinations
+    fn save_item_commutes(
+        &self,
+        url: &Url,
+        commutes: &Commutes,
+    ) -> impl Future<Output = Result<(), WorkspaceError>> + Send;
+
+    /// Search scraped items for all terms of a query, most relevant first
+    ///
+    /// Matches of the indexed terms are marked in the snippets, those of the unindexed terms
+    /// are left to `SearchHit::highlight`.
+    fn search_items(
+        &self,
+        query: &SearchQuery,
+        limit: u32,
+    ) -> impl Future<Output = Result<Vec<SearchHit>, WorkspaceError>> + Send;
+
+    /// Count the rows and measure the size of the database
+    fn stats(&self) -> impl Future<Output = Result<WorkspaceStats, WorkspaceError>> + Send;
+
+    /// Remove rows according to the policy and reclaim the space with `VACUUM`
+    ///
+    /// With `dry_run` the removal is rolled back and only the counts are reported.
+    fn prune(
+        &self,
+        policy: PrunePolicy,
+        dry_run: bool,
+    ) -> impl Future<Output = Result<PruneReport, WorkspaceError>> + Send;
+}
+
+/// Storage of a workspace, in its own SQLite database or in a shared PostgreSQL one
+#[derive(Clone, Debug)]
+pub enum StorageBackend {
+    Sqlite(SqliteStorage),
+    Postgres(PostgresStorage),
+}
+
+impl From<SqliteStorage> for StorageBackend {
+    fn from(storage: SqliteStorage) -> Self {
+        Self::Sqlite(storage)
+    }
+}
+
+impl From<PostgresStorage> for StorageBackend {
+    fn from(storage: PostgresStorage) -> Self {
+        Self::Postgres(storage)
+    }
+}
+
+/// Implement `Storage` for `StorageBackend` by forwarding each operation to its backend
+macro_rules! delegate {
+    ($(fn $name:ident(&self $(, $arg:ident: $ty:ty)*) $(-> $ret:ty)?;)*) => {
+        impl Storage for StorageBackend {
+            $(
+                async fn $name(&self $(, $arg: $ty)*) $(-> $ret)? {
+                    match self {
+                        Self::Sqlite(storage) => storage.$name($($arg),*).await,
+                        Self::Postgres(storage) => storage.$name($($arg),*).await,
+                    }
+                }
+            )*
+        }
+    };
+}
+
+delegate! {
+    fn migrate(&self) -> Result<(), WorkspaceError>;
+    fn close(&self);
+    fn list_exists(&self, url: &Url) -> Result<bool, WorkspaceError>;
+    fn insert_list(&self, list: &RentList) -> Result<(), WorkspaceError>;
+    fn select_list(&self, url: &Url) -> Result<Option<RentList>, WorkspaceError>;
+    fn select_list_created_at(&self, url: &Url) -> Result<Option<String>, WorkspaceError>;
+    fn select_item_summaries(&self, url: &Url) -> Result<Vec<RentItemSummary>, WorkspaceError>;
+    fn select_listing_summaries(
+        &self,
+        list_url: Option<&Url>,
+        posted_after: Option<NaiveDate>
+    ) -> Result<Vec<(RentItemSummary, String)>, WorkspaceError>;
+    fn item_exists(&self, url: &Url) -> Result<bool, WorkspaceError>;
+    fn insert_items(&self, items: &[RentItem]) -> Result<(), WorkspaceError>;
+    fn import_list(
+        &self,
+        list: &RentList,
+        created_at: Option<&str>,
+        policy: ConflictPolicy
+    ) -> Result<bool, WorkspaceError>;
+    fn import_items(
+        &self,
+        items: &[(&RentItem, Option<&str>)],
+        policy: ConflictPolicy
+    ) -> Result<u64, WorkspaceError>;
+    fn select_item(&self, url: &Url) -> Result<Option<RentItem>, WorkspaceError>;
+    fn select_item_urls_with(
+        &self,
+        list_url: &Url,
+        refresh: bool,
+        limit: Option<u32>
+    ) -> Result<Vec<Json<Url>>, WorkspaceError>;
+    fn select_items(&self, list_url: Option<&Url>) -> Result<Vec<RentItem>, WorkspaceError>;
+    fn select_stamped_items(
+        &self,
+        list_url: &Url
+    ) -> Result<Vec<(RentItem, String)>, WorkspaceError>;
+    fn get_cached_page(&self, url: &Url) -> Result<Option<Page>, WorkspaceError>;
+    fn select_cached_pages(&self) -> Result<Vec<PageSummary>, WorkspaceError>;
+    fn cache_page(&self, page: &Page) -> Result<(), WorkspaceError>;
+    fn select_cookies(&self) -> Result<Vec<Cookie>, WorkspaceError>;
+    fn save_cookies(&self, cookies: &[Cookie]) -> Result<(), WorkspaceError>;
+    fn select_item_address(&self, url: &Url) -> Result<Option<ItemAddress>, WorkspaceError>;
+    fn save_item_address(&self, address: &ItemAddress) -> Result<(), WorkspaceError>;
+    fn select_geocode(&self, address: &str) -> Result<Option<Geocode>, WorkspaceError>;
+    fn save_geocode(&self, geocode: &Geocode) -> Result<(), WorkspaceError>;
+    fn select_item_distances(
+        &self,
+        list_url: Option<&Url>
+    ) -> Result<HashMap<Url, Distances>, WorkspaceError>;
+    fn save_item_distances(&self, url: &Url, distances: &Distances) -> Result<(), WorkspaceError>;
+    fn select_item_stations(
+        &self,
+        list_url: Option<&Url>
+    ) -> Result<HashMap<Url, Vec<NearbyStation>>, WorkspaceError>;
+    fn save_item_stations(
+        &self,
+        url: &Url,
+        stations: &[NearbyStation]
+    ) -> Result<(), WorkspaceError>;
+    fn select_item_locations(
+        &self,
+        list_url: Option<&Url>,
+        bounds: Option<&BoundingBox>
+    ) -> Result<HashMap<Url, (f64, f64)>, WorkspaceError>;
+    fn select_item_commutes(
+        &self,
+        list_url: Option<&Url>
+    ) -> Result<HashMap<Url, Commutes>, WorkspaceError>;
+    fn save_item_commutes(&self, url: &Url, commutes: &Commutes) -> Result<(), WorkspaceError>;
+    fn search_items(&self, query: &SearchQuery, limit: u32) -> Result<Vec<SearchHit>, WorkspaceError>;
+    fn stats(&self) -> Result<WorkspaceStats, WorkspaceError>;
+    fn prune(&self, policy: PrunePolicy, dry_run: bool) -> Result<PruneReport, WorkspaceError>;
+}
+
+/// The latest summary of a listing with when it was first seen in a list snapshot
+#[derive(FromRow)]
+struct ListingRow {
+    #[sqlx(flatten)]
+    summary: RentItemSummary,
+    first_seen: String,
+}
+
+#[derive(FromRow)]
+struct StampedItemRow {
+    #[sqlx(flatten)]
+    item: RentItem,
+    created_at: String,
+}
+
+/// Keep the rows whose `column` is an item URL of the latest snapshot of a list, if given
+fn push_list_items<'a, DB>(
+    builder: &mut QueryBuilder<'a, DB>,
+    column: &str,
+    list_url: Option<&'a Url>,
+) where
+    DB: Database,
+    Json<&'a Url>: Encode<'a, DB> + Type<DB>,
+{
+    if let Some(list_url) = list_url {
+        builder
+            .push(format!(
+                "
+JOIN rent_item_summary ris ON {column} = ris.url
+    AND ris.list_id = (SELECT id FROM rent_list WHERE url = "
+            ))
+            .push_bind(Json(list_url))
+            .push(" ORDER BY created_at DESC LIMIT 1)");
+    }
+}
+
+/// Build an `ItemAddress` from a stored row, `None` if its source is unknown
+fn item_address(
+    (url, address, normalized, source): (Json<Url>, String, String, String),
+) -> Option<ItemAddress> {
+    Some(ItemAddress {
+        url: url.0,
+        address,
+        normalized,
+        source: AddressSource::from_name(&source)?,
+    })
+}
+
+/// Group stored `(item_url, poi, distance_km)` rows by listing
+fn item_distances(rows: Vec<(Json<Url>, String, f64)>) -> HashMap<Url, Distances> {
+    let mut distances: HashMap<Url, Distances> = HashMap::new();
+
+    for (url, poi, km) in rows {
+        distances.entry(url.0).or_default().insert(poi, km);
+    }
+
+    distances
+}
+
+/// Group stored `(item_url, poi, minutes)` rows by listing
+fn item_commutes(rows: Vec<(Json<Url>, String, i32)>) -> HashMap<Url, Commutes> {
+    let mut commutes: HashMap<Url, Commutes> = HashMap::new();
+
+    for (url, poi, minutes) in rows {
+        commutes
+            .entry(url.0)
+            .or_default()
+            .insert(poi, minutes as u32);
+    }
+
+    commutes
+}
+
+/// A stored `rent_item_station` row
+type StationRow = (Json<Url>, String, String, String, i32, i32, i32);
+
+/// Group stored station rows by listing, keeping their order
+fn item_stations(rows: Vec<StationRow>) -> HashMap<Url, Vec<NearbyStation>> {
+    let mut stations: HashMap<Url, Vec<NearbyStation>> = HashMap::new();
+
+    for (url, line, code, name, distance_m, walk_m, walk_minutes) in rows {
+        stations.entry(url.0).or_default().push(NearbyStation {
+            line,
+            code,
+            name,
+            distance_m: distance_m as u32,
+            walk_m: walk_m as u32,
+            walk_minutes: walk_minutes as u32,
+        });
+    }
+
+    stations
+}
+
+/// Key of the parent row of a child table holding a list field
+#[derive(Clone, Copy)]
+enum ChildKey<'a> {
+    Item(&'a Json<Url>),
+    Summary(i64, &'a Json<Url>),
+}
+
+impl ChildKey<'_> {
+    fn columns(self) -> &'static str {
+        match self {
+            Self::Item(_) => "item_url",
+            Self::Summary(..) => "list_id, item_url",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geocode::{GeocoderType, Station, nearest_stations};
+    use crate::sites::rent591::{MATCH_END, MATCH_START, RentListPage};
+
+    const LIST_URL: &str = "https://rent.591.com.tw/list?region=1";
+    const ITEM_URL: &str = "https://rent.591.com.tw/1";
+    const IMAGE_URL: &str = "https://img.591.com.tw/1.jpg";
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    /// Insert a list snapshot with one item summary
+    async fn insert_list<S: Storage>(storage: &S) -> RentList {
+        let summary = RentItemSummary::new(
+            url(ITEM_URL),
+            Some("套房".to_string()),
+            Some("12,000".to_string()),
+            vec!["近捷運".to_string()],
+            vec!["大安區".to_string(), "10坪".to_string()],
+            vec![url(IMAGE_URL)],
+        );
+        let list = RentList::new(
+            url(LIST_URL),
+            Some(1),
+            Some(1),
+            vec![RentListPage::new(vec![summary])],
+        );
+        storage.insert_list(&list).await.unwrap();
+        list
+    }
+
+    /// Insert the details of the item of `insert_list`
+    async fn insert_item<S: Storage>(storage: &S) -> RentItem {
+        let item = RentItem::new(
+            url(ITEM_URL),
+            Some("套房".to_string()),
+            vec!["可養寵物".to_string(), "近捷運".to_string()],
+            vec![],
+            "採光佳".to_string(),
+            None,
+            vec![url(IMAGE_URL)],
+            Some(url(IMAGE_URL)),
+            None,
+            None,
+            None,
+        );
+        storage
+            .insert_items(std::slice::from_ref(&item))
+            .await
+            .unwrap();
+        item
+    }
+
+    /// Geocode the item of `insert_list` at `(25.026, 121.543)`
+    async fn locate_item<S: Storage>(storage: &S) -> ItemAddress {
+        let address = ItemAddress::new(
+            url(ITEM_URL),
+            "台北市大安區復興南路二段１號".to_string(),
+            AddressSource::Ocr,
+        );
+        storage.save_item_address(&address).await.unwrap();
+        let geocode = Geocode {
+            lat: Some(25.026),
+            lng: Some(121.543),
+            provider: Some("nominatim".to_string()),
+            ..Geocode::not_found(address.normalized.clone())
+        };
+        storage.save_geocode(&geocode).await.unwrap();
+        address
+    }
+
+    async fn test_lists<S: Storage>(storage: &S) {
+        let list_url = url(LIST_URL);
+        assert!(!storage.list_exists(&list_url).await.unwrap());
+
+        insert_list(storage).await;
+
+        assert!(storage.list_exists(&list_url).await.unwrap());
+        let selected = storage.select_list(&list_url).await.unwrap().unwrap();
+        assert_eq!(
+            (selected.page_count, selected.item_count),
+            (Some(1), Some(1))
+        );
+
+        let summaries = storage.select_item_summaries(&list_url).await.unwrap();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].txts.0, ["大安區", "10坪"]);
+        assert_eq!(summaries[0].images.0, [url(IMAGE_URL)]);
+
+        let urls = storage
+            .select_item_urls_with(&list_url, false, None)
+            .await
+            .unwrap();
+        assert_eq!(urls, [Json(url(ITEM_URL))]);
+    }
+
+    async fn test_items<S: Storage>(storage: &S) {
+        let list_url = url(LIST_URL);
+        let item_url = url(ITEM_URL);
+        insert_list(storage).await;
+        let item = insert_item(storage).await;
+        storage.insert_items(&[item]).await.unwrap();
+
+        assert!(storage.item_exists(&item_url).await.unwrap());
+        let selected = storage.select_item(&item_url).await.unwrap().unwrap();
+        assert_eq!(selected.labels.0, ["可養寵物", "近捷運"]);
+        assert!(selected.patterns.is_empty());
+        assert_eq!(selected.area.map(|area| area.0), Some(url(IMAGE_URL)));
+        assert_eq!(
+            storage.select_items(Some(&list_url)).await.unwrap().len(),
+            1
+        );
+        assert_eq!(storage.select_items(None).await.unwrap().len(), 1);
+        assert!(
+            storage
+                .select_item_urls_with(&list_url, false, None)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            storage
+                .select_item_urls_with(&list_url, true, Some(1))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    async fn test_listings<S: Storage>(storage: &S) {
+        insert_list(storage).await;
+
+        let listings = storage.select_listing_summaries(None, None).await.unwrap();
+        assert_eq!(listings.len(), 1);
+        assert_eq!(listings[0].0.url.0, url(ITEM_URL));
+        let future = NaiveDate::from_ymd_opt(2999, 1, 1);
+        assert!(
+            storage
+                .select_listing_summaries(Some(&url(LIST_URL)), future)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    async fn test_import<S: Storage>(storage: &S) {
+        let list_url = url(LIST_URL);
+        let list = insert_list(storage).await;
+        let item = insert_item(storage).await;
+
+        let old = Some("2025-01-01 00:00:00");
+        assert!(
+            storage
+                .import_list(&list, old, ConflictPolicy::Skip)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !storage
+                .import_list(&list, old, ConflictPolicy::Skip)
+                .await
+                .unwrap()
+        );
+        assert!(
+            storage
+                .import_list(&list, old, ConflictPolicy::Overwrite)
+                .await
+                .unwrap()
+        );
+        let written = storage
+            .import_items(&[(&item, old)], ConflictPolicy::Newest)
+            .await
+            .unwrap();
+        assert_eq!(written, 0);
+        let written = storage
+            .import_items(&[(&item, old)], ConflictPolicy::Overwrite)
+            .await
+            .unwrap();
+        assert_eq!(written, 1);
+
+        let stamped = storage.select_stamped_items(&list_url).await.unwrap();
+        assert_eq!(stamped.len(), 1);
+        assert_eq!(Some(stamped[0].1.as_str()), old);
+        let created_at = storage.select_list_created_at(&list_url).await.unwrap();
+        assert!(created_at.as_deref() > old);
+    }
+
+    async fn test_page_cache<S: Storage>(storage: &S) {
+        let item_url = url(ITEM_URL);
+        let page = Page::new(item_url.clone(), "<html></html>".to_string());
+        storage.cache_page(&page).await.unwrap();
+        storage.cache_page(&page).await.unwrap();
+
+        let cached = storage.get_cached_page(&item_url).await.unwrap().unwrap();
+        assert_eq!(cached.html, page.html);
+        let pages = storage.select_cached_pages().await.unwrap();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].size, 13);
+    }
+
+    async fn test_cookies<S: Storage>(storage: &S) {
+        let cookie = Cookie {
+            name: "T591_TOKEN".to_string(),
+            value: "token".to_string(),
+            domain: ".591.com.tw".to_string(),
+            path: "/".to_string(),
+            expires: Some(4102444800.0),
+            http_only: true,
+            secure: false,
+            same_site: None,
+        };
+        let expired = Cookie {
+            name: "expired".to_string(),
+            expires: Some(1.0),
+            ..cookie.clone()
+        };
+        let session = Cookie {
+            name: "session".to_string(),
+            expires: None,
+            ..cookie.clone()
+        };
+        storage
+            .save_cookies(&[cookie, expired, session])
+            .await
+            .unwrap();
+
+        let cookies = storage.select_cookies().await.unwrap();
+        assert_eq!(cookies.len(), 1);
+        assert!(cookies[0].http_only);
+    }
+
+    async fn test_geocodes<S: Storage>(storage: &S) {
+        let item_url = url(ITEM_URL);
+        insert_list(storage).await;
+        let address = locate_item(storage).await;
+        storage.save_item_address(&address).await.unwrap();
+
+        let selected = storage
+            .select_item_address(&item_url)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(selected.normalized, "臺北市大安區復興南路二段1號");
+        assert_eq!(selected.source, AddressSource::Ocr);
+
+        assert!(storage.select_geocode("nowhere").await.unwrap().is_none());
+        let selected = storage
+            .select_geocode(&address.normalized)
+            .await
+            .unwrap()
+            .unwrap();
+        storage.save_geocode(&selected).await.unwrap();
+        assert_eq!(selected.location(), Some((25.026, 121.543)));
+        assert!(selected.is_from(GeocoderType::Nominatim));
+        assert!(!selected.is_from(GeocoderType::Google));
+    }
+
+    async fn test_distances<S: Storage>(storage: &S) {
+        let item_url = url(ITEM_URL);
+        insert_list(storage).await;
+
+        let distances = Distances::from([("office".to_string(), 1.5), ("gym".to_string(), 0.4)]);
+        storage
+            .save_item_distances(&item_url, &distances)
+            .await
+            .unwrap();
+        let distances = Distances::from([("office".to_string(), 1.2)]);
+        storage
+            .save_item_distances(&item_url, &distances)
+            .await
+            .unwrap();
+
+        let selected = storage
+            .select_item_distances(Some(&url(LIST_URL)))
+            .await
+            .unwrap();
+        assert_eq!(selected, HashMap::from([(item_url, distances)]));
+    }
+
+    async fn test_stations<S: Storage>(storage: &S) {
+        let item_url = url(ITEM_URL);
+        insert_list(storage).await;
+
+        let stations = nearest_stations(&Station::bundled(), (25.026, 121.543), 1000);
+        storage
+            .save_item_stations(&item_url, &stations)
+            .await
+            .unwrap();
+        storage
+            .save_item_stations(&item_url, &stations)
+            .await
+            .unwrap();
+
+        let selected = storage
+            .select_item_stations(Some(&url(LIST_URL)))
+            .await
+            .unwrap();
+        assert_eq!(selected, HashMap::from([(item_url, stations)]));
+    }
+
+    async fn test_commutes<S: Storage>(storage: &S) {
+        let item_url = url(ITEM_URL);
+        insert_list(storage).await;
+
+        storage
+            .save_item_commutes(&item_url, &Commutes::from([("office".to_string(), 35)]))
+            .await
+            .unwrap();
+        storage
+            .save_item_commutes(&item_url, &Commutes::from([("gym".to_string(), 12)]))
+            .await
+            .unwrap();
+        storage
+            .save_item_commutes(&item_url, &Commutes::from([("office".to_string(), 30)]))
+            .await
+            .unwrap();
+
+        let commutes = Commutes::from([("gym".to_string(), 12), ("office".to_string(), 30)]);
+        let selected = storage
+            .select_item_commutes(Some(&url(LIST_URL)))
+            .await
+            .unwrap();
+        assert_eq!(selected, HashMap::from([(item_url, commutes)]));
+    }
+
+    async fn test_locations<S: Storage>(storage: &S) {
+        insert_list(storage).await;
+        let address = locate_item(storage).await;
+
+        let bounds = BoundingBox {
+            min_lat: 25.0,
+            max_lat: 25.1,
+            min_lng: 121.5,
+            max_lng: 121.6,
+        };
+        let located = HashMap::from([(url(ITEM_URL), (25.026, 121.543))]);
+        let selected = storage
+            .select_item_locations(Some(&url(LIST_URL)), None)
+            .await
+            .unwrap();
+        assert_eq!(selected, located);
+        let selected = storage
+            .select_item_locations(None, Some(&bounds))
+            .await
+            .unwrap();
+        assert_eq!(selected, located);
+        let south = BoundingBox {
+            max_lat: 25.02,
+            ..bounds
+        };
+        assert!(
+            storage
+                .select_item_locations(None, Some(&south))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        // the index follows a geocode that moves
+        let mut geocode = storage
+            .select_geocode(&address.normalized)
+            .await
+            .unwrap()
+            .unwrap();
+        geocode.lat = Some(25.15);
+        storage.save_geocode(&geocode).await.unwrap();
+        assert!(
+            storage
+                .select_item_locations(None, Some(&bounds))
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        let north = BoundingBox {
+            min_lat: 25.1,
+            max_lat: 25.2,
+            ..bounds
+        };
+        assert_eq!(
+            storage
+                .select_item_locations(None, Some(&north))
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    async fn test_search<S: Storage>(storage: &S) {
+        insert_list(storage).await;
+        insert_item(storage).await;
+
+        let query = SearchQuery::parse("採光佳 寵物");
+        let hits = storage.search_items(&query, 10).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].url.0, url(ITEM_URL));
+        assert!(hits[0].rank < 0.0);
+        assert!(
+            hits[0]
+                .snippet
+                .contains(&format!("{MATCH_START}採光佳{MATCH_END}"))
+        );
+
+        // labels are searched too, and every term has to match
+        let query = SearchQuery::parse("可養寵物 套房");
+        assert_eq!(storage.search_items(&query, 10).await.unwrap().len(), 1);
+        let query = SearchQuery::parse("採光佳 電梯");
+        assert!(storage.search_items(&query, 10).await.unwrap().is_empty());
+    }
+
+    async fn test_stats<S: Storage>(storage: &S) {
+        insert_list(storage).await;
+        insert_item(storage).await;
+        let page = Page::new(url(ITEM_URL), "<html></html>".to_string());
+        storage.cache_page(&page).await.unwrap();
+
+        let stats = storage.stats().await.unwrap();
+        assert_eq!(stats.list_snapshots, 1);
+        assert_eq!(stats.item_summaries, 1);
+        assert_eq!(stats.items, 1);
+        assert_eq!(stats.cached_pages, 1);
+        assert_eq!(stats.cache_size, 13);
+        assert!(stats.database_size > 0);
+        assert_eq!(stats.lists.len(), 1);
+        assert_eq!(stats.lists[0].url.0, url(LIST_URL));
+        assert_eq!(stats.lists[0].count, 1);
+    }
+
+    async fn test_prune<S: Storage>(storage: &S) {
+        let list = insert_list(storage).await;
+        storage
+            .import_list(&list, Some("2024-01-01 00:00:00"), ConflictPolicy::Skip)
+            .await
+            .unwrap();
+        insert_item(storage).await;
+        let page = Page::new(url(ITEM_URL), "<html></html>".to_string());
+        storage.cache_page(&page).await.unwrap();
+
+        let policy = PrunePolicy {
+            keep_snapshots: Some(1),
+            max_cache_days: Some(1),
+            orphan_items: true,
+        };
+        let report = storage.prune(policy, true).await.unwrap();
+        assert_eq!(report.list_snapshots, 1);
+        assert_eq!(report.item_summaries, 1);
+        assert_eq!(report.items, 0);
+        assert_eq!(report.cached_pages, 0);
+        assert_eq!(storage.stats().await.unwrap().list_snapshots, 2);
+
+        let report = storage.prune(policy, false).await.unwrap();
+        assert_eq!(report.list_snapshots, 1);
+        let stats = storage.stats().await.unwrap();
+        assert_eq!(stats.list_snapshots, 1);
+        assert_eq!(stats.item_summaries, 1);
+        assert_eq!(stats.items, 1);
+        assert_eq!(stats.cached_pages, 1);
+    }
+
+    /// Run each test against a fresh SQLite database, and with `--ignored` against a
+    /// throwaway schema of the PostgreSQL database at `RENTMAP_TEST_POSTGRES_URL`
+    macro_rules! storage_tests {
+        ($($test:ident),* $(,)?) => {
+            mod sqlite {
+                use crate::file::TempDir;
+                use crate::workspace::storage::{SqliteStorage, Storage};
+
+                $(
+                    #[tokio::test]
+                    async fn $test() {
+                        let root = TempDir::new(concat!("storage-", stringify!($test)));
+                        let storage = SqliteStorage::new(&root.join("rentmap.sqlite"));
+                        storage.migrate().await.unwrap();
+
+                        super::$test(&storage).await;
+
+                        storage.close().await;
+                    }
+                )*
+            }
+
+            mod postgres {
+                use std::str::FromStr;
+
+                use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+
+                use crate::workspace::storage::{PostgresStorage, Storage};
+
+                $(
+                    #[tokio::test]
+                    #[ignore = "needs a PostgreSQL database at RENTMAP_TEST_POSTGRES_URL"]
+                    async fn $test() {
+                        let url = std::env::var("RENTMAP_TEST_POSTGRES_URL")
+                            .expect("RENTMAP_TEST_POSTGRES_URL is not set");
+                        let schema = format!(
+                            concat!("rentmap_", stringify!($test), "_{}"),
+                            std::process::id()
+                        );
+
+                        let options = PgConnectOptions::from_str(&url)
+                            .unwrap()
+                            .options([("search_path", format!("{schema},public"))]);
+                        let storage = PostgresStorage {
+                            pool: PgPoolOptions::new().connect_lazy_with(options),
+                        };
+                        // extensions are shared by the schemas, create them in public so
+                        // that the migrations of the first test do not own them
+                        sqlx::raw_sql(
+                            "BEGIN; SELECT pg_advisory_xact_lock(591); \
+CREATE EXTENSION IF NOT EXISTS pg_trgm SCHEMA public; COMMIT",
+                        )
+                        .execute(&storage.pool)
+                        .await
+                        .unwrap();
+                        sqlx::raw_sql(&format!("CREATE SCHEMA {schema}"))
+                            .execute(&storage.pool)
+                            .await
+                            .unwrap();
+                        storage.migrate().await.unwrap();
+
+                        super::$test(&storage).await;
+
+                        sqlx::raw_sql(&format!("DROP SCHEMA {schema} CASCADE"))
+                            .execute(&storage.pool)
+                            .await
+                            .unwrap();
+                        storage.close().await;
+                    }
+                )*
+            }
+        };
+    }
+
+    storage_tests!(
+        test_lists,
+        test_items,
+        test_listings,
+        test_import,
+        test_page_cache,
+        test_cookies,
+        test_geocodes,
+        test_distances,
+        test_stations,
+        test_commutes,
+        test_locations,
+        test_search,
+        test_stats,
+        test_prune,
+    );
+}
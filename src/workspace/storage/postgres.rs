@@ -0,0 +1,930 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use sqlx::postgres::PgPoolOptions;
+use sqlx::types::Json;
+use sqlx::{PgPool, Postgres, QueryBuilder, Transaction};
+use url::Url;
+
+use super::{
+    ChildKey, ListingRow, StampedItemRow, StationRow, Storage, item_address, item_commutes,
+    item_distances, item_stations, push_list_items,
+};
+use crate::geocode::{BoundingBox, Distances, Geocode, ItemAddress, NearbyStation};
+use crate::sites::rent591::{RentItem, RentItemSummary, RentList, SearchHit, SearchQuery};
+use crate::transit::Commutes;
+use crate::web::{Cookie, Page, PageSummary};
+use crate::workspace::{ConflictPolicy, PrunePolicy, PruneReport, WorkspaceError, WorkspaceStats};
+
+/// The column default of `created_at`, in the same format as SQLite's `CURRENT_TIMESTAMP`
+const NOW: &str = "to_char(now() AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI:SS')";
+
+/// Storage in a PostgreSQL database, which can be shared by several workspaces
+#[derive(Clone, Debug)]
+pub struct PostgresStorage {
+    pub pool: PgPool,
+}
+
+impl PostgresStorage {
+    /// Connect lazily to the database at `url` (e.g., `postgres://user@localhost/rentmap`)
+    pub fn connect(url: &str) -> Result<Self, WorkspaceError> {
+        let pool = PgPoolOptions::new().connect_lazy(url)?;
+        Ok(Self { pool })
+    }
+
+    async fn insert_item_summaries(
+        tx: &mut Transaction<'_, Postgres>,
+        list_id: i64,
+        list: &RentList,
+    ) -> Result<(), WorkspaceError> {
+        for summary in list.item_summaries() {
+            sqlx::query(
+                "INSERT INTO rent_item_summary (list_id, url, title, price) VALUES ($1, $2, $3, $4) \
+ON CONFLICT (list_id, url) DO UPDATE SET title = excluded.title, price = excluded.price",
+            )
+            .bind(list_id)
+            .bind(&summary.url)
+            .bind(&summary.title)
+            .bind(&summary.price)
+            .execute(&mut **tx)
+            .await?;
+
+            let key = ChildKey::Summary(list_id, &summary.url);
+            let tags = summary.tags.iter().map(String::as_str);
+            let txts = summary.txts.iter().map(String::as_str);
+            let images = summary.images.iter().map(Url::as_str);
+            Self::replace_values(tx, "rent_item_summary_tag", "tag", key, tags).await?;
+            Self::replace_values(tx, "rent_item_summary_txt", "txt", key, txts).await?;
+            Self::replace_values(tx, "rent_item_summary_image", "url", key, images).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Replace the values of a list field stored in a child table, keeping their order
+    async fn replace_values<'a, I>(
+        tx: &mut Transaction<'_, Postgres>,
+        table: &str,
+        column: &str,
+        key: ChildKey<'_>,
+        values: I,
+    ) -> Result<(), WorkspaceError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let (condition, parent, values_param) = match key {
+            ChildKey::Item(_) => ("item_url = $1", "$1", "$2"),
+            ChildKey::Summary(..) => ("list_id = $1 AND item_url = $2", "$1, $2", "$3"),
+        };
+
+        let delete = format!("DELETE FROM {table} WHERE {condition}");
+        let insert = format!(
+            "INSERT INTO {table} ({}, position, {column}) \
+SELECT {parent}, v.position - 1, v.value FROM unnest({values_param}::TEXT[]) WITH ORDINALITY AS v (value, position)",
+            key.columns()
+        );
+
+        let values: Vec<&str> = values.into_iter().collect();
+
+        for (sql, with_values) in [(delete, false), (insert, true)] {
+            if with_values && values.is_empty() {
+                continue;
+            }
+
+            let mut query = sqlx::query(&sql);
+            query = match key {
+                ChildKey::Item(url) => query.bind(url),
+                ChildKey::Summary(list_id, url) => query.bind(list_id).bind(url),
+            };
+            if with_values {
+                query = query.bind(&values);
+            }
+            query.execute(&mut **tx).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn replace_item_values(
+        tx: &mut Transaction<'_, Postgres>,
+        item: &RentItem,
+    ) -> Result<(), WorkspaceError> {
+        let key = ChildKey::Item(&item.url);
+        let labels = item.labels.iter().map(String::as_str);
+        let patterns = item.patterns.iter().map(String::as_str);
+        let album = item.album.iter().map(Url::as_str);
+        Self::replace_values(tx, "rent_item_label", "label", key, labels).await?;
+        Self::replace_values(tx, "rent_item_pattern", "pattern", key, patterns).await?;
+        Self::replace_values(tx, "rent_item_image", "url", key, album).await?;
+        Ok(())
+    }
+}
+
+impl Storage for PostgresStorage {
+    async fn migrate(&self) -> Result<(), WorkspaceError> {
+        sqlx::migrate!("./migrations/postgres")
+            .run(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn close(&self) {
+        self.pool.close().await;
+    }
+
+    async fn list_exists(&self, url: &Url) -> Result<bool, WorkspaceError> {
+        let exists = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM rent_list WHERE url = $1)")
+            .bind(Json(url))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(exists)
+    }
+
+    async fn insert_list(&self, list: &RentList) -> Result<(), WorkspaceError> {
+        let mut tx = self.pool.begin().await?;
+
+        let id: i64 = sqlx::query_scalar(
+            "INSERT INTO rent_list (url, page_count, item_count) VALUES ($1, $2, $3) RETURNING id",
+        )
+        .bind(&list.url)
+        .bind(list.page_count.map(i64::from))
+        .bind(list.item_count.map(i64::from))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Self::insert_item_summaries(&mut tx, id, list).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn select_list(&self, url: &Url) -> Result<Option<RentList>, WorkspaceError> {
+        // Postgres has no unsigned integers, so the counts are read as BIGINT
+        let row: Option<(Json<Url>, Option<i64>, Option<i64>)> = sqlx::query_as(
+            "SELECT url, page_count, item_count FROM rent_list WHERE url = $1 ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(Json(url))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let rent_list = row.map(|(url, page_count, item_count)| {
+            RentList::new(
+                url.0,
+                page_count.and_then(|count| count.try_into().ok()),
+                item_count.and_then(|count| count.try_into().ok()),
+                Vec::new(),
+            )
+        });
+
+        Ok(rent_list)
+    }
+
+    async fn select_list_created_at(&self, url: &Url) -> Result<Option<String>, WorkspaceError> {
+        let created_at = sqlx::query_scalar(
+            "SELECT created_at FROM rent_list WHERE url = $1 ORDER BY created_at DESC LIMIT 1",
+        )
+        .bind(Json(url))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(created_at)
+    }
+
+    async fn select_item_summaries(
+        &self,
+        url: &Url,
+    ) -> Result<Vec<RentItemSummary>, WorkspaceError> {
+        let summaries = sqlx::query_as(
+            "
+WITH LatestList AS (
+    SELECT id FROM rent_list WHERE url = $1 ORDER BY created_at DESC LIMIT 1
+)
+SELECT ris.url, ris.title, ris.price, ris.tags, ris.txts, ris.images
+FROM rent_item_summary_view ris
+JOIN LatestList ll ON ris.list_id = ll.id
+ORDER BY ris.url",
+        )
+        .bind(Json(url))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(summaries)
+    }
+
+    async fn select_listing_summaries(
+        &self,
+        list_url: Option<&Url>,
+        posted_after: Option<NaiveDate>,
+    ) -> Result<Vec<(RentItemSummary, String)>, WorkspaceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "
+WITH FirstSeen AS (
+    SELECT ris.url, MIN(rl.created_at) AS first_seen
+    FROM rent_item_summary ris
+    JOIN rent_list rl ON ris.list_id = rl.id
+    GROUP BY ris.url
+),
+Latest AS (
+    SELECT ris.*, ROW_NUMBER() OVER (PARTITION BY ris.url ORDER BY rl.created_at DESC) AS rank
+    FROM rent_item_summary_view ris
+    JOIN rent_list rl ON ris.list_id = rl.id",
+        );
+
+        if let Some(list_url) = list_url {
+            builder
+                .push(" WHERE rl.id = (SELECT id FROM rent_list WHERE url = ")
+                .push_bind(Json(list_url))
+                .push(" ORDER BY created_at DESC LIMIT 1)");
+        }
+
+        builder.push(
+            "
+)
+SELECT l.url, l.title, l.price, l.tags, l.txts, l.images, fs.first_seen
+FROM Latest l
+JOIN FirstSeen fs ON l.url = fs.url
+WHERE l.rank = 1",
+        );
+
+        if let Some(posted_after) = posted_after {
+            builder
+                .push(" AND fs.first_seen >= ")
+                .push_bind(posted_after.to_string());
+        }
+
+        builder.push(" ORDER BY l.url");
+
+        let rows: Vec<ListingRow> = builder.build_query_as().fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.summary, row.first_seen))
+            .collect())
+    }
+
+    async fn item_exists(&self, url: &Url) -> Result<bool, WorkspaceError> {
+        let exists = sqlx::query_scalar("SELECT EXISTS(SELECT 1 FROM rent_item WHERE url = $1)")
+            .bind(Json(url))
+            .fetch_one(&self.pool)
+            .await?;
+
+        Ok(exists)
+    }
+
+    async fn insert_items(&self, items: &[RentItem]) -> Result<(), WorkspaceError> {
+        let items: Vec<_> = items.iter().map(|item| (item, None)).collect();
+        self.import_items(&items, ConflictPolicy::Overwrite).await?;
+
+        Ok(())
+    }
+
+    async fn import_list(
+        &self,
+        list: &RentList,
+        created_at: Option<&str>,
+        policy: ConflictPolicy,
+    ) -> Result<bool, WorkspaceError> {
+        let mut tx = self.pool.begin().await?;
+
+        if let Some(created_at) = created_at {
+            let sql = match policy {
+                ConflictPolicy::Newest => {
+                    "SELECT id FROM rent_list WHERE url = $1 AND created_at >= $2 LIMIT 1"
+                }
+                _ => "SELECT id FROM rent_list WHERE url = $1 AND created_at = $2",
+            };
+
+            let existing: Option<i64> = sqlx::query_scalar(sql)
+                .bind(&list.url)
+                .bind(created_at)
+                .fetch_optional(&mut *tx)
+                .await?;
+
+            match (policy, existing) {
+                (_, None) => {}
+                (ConflictPolicy::Overwrite, Some(id)) => {
+                    sqlx::query("DELETE FROM rent_list WHERE id = $1")
+                        .bind(id)
+                        .execute(&mut *tx)
+                        .await?;
+                }
+                (ConflictPolicy::Skip | ConflictPolicy::Newest, Some(_)) => {
+                    return Ok(false);
+                }
+            }
+        }
+
+        let id: i64 = sqlx::query_scalar(&format!(
+            "INSERT INTO rent_list (url, created_at, page_count, item_count) VALUES ($1, COALESCE($2, {NOW}), $3, $4) RETURNING id"
+        ))
+        .bind(&list.url)
+        .bind(created_at)
+        .bind(list.page_count.map(i64::from))
+        .bind(list.item_count.map(i64::from))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        Self::insert_item_summaries(&mut tx, id, list).await?;
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+
+    async fn import_items(
+        &self,
+        items: &[(&RentItem, Option<&str>)],
+        policy: ConflictPolicy,
+    ) -> Result<u64, WorkspaceError> {
+        const COLUMNS: [&str; 8] = [
+            "created_at",
+            "title",
+            "content",
+            "phone",
+            "area",
+            "floor",
+            "price",
+            "address",
+        ];
+
+        let sql = format!(
+            "INSERT INTO rent_item (url, created_at, title, content, phone, area, floor, price, address) \
+VALUES ($1, COALESCE($2, {NOW}), $3, $4, $5, $6, $7, $8, $9){}",
+            policy.upsert_clause("rent_item", "url", &COLUMNS)
+        );
+
+        let mut tx = self.pool.begin().await?;
+        let mut written = 0;
+
+        for &(item, created_at) in items {
+            let affected = sqlx::query(&sql)
+                .bind(&item.url)
+                .bind(created_at)
+                .bind(&item.title)
+                .bind(&item.content)
+                .bind(&item.phone)
+                .bind(&item.area)
+                .bind(&item.floor)
+                .bind(&item.price)
+                .bind(&item.address)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+            if affected > 0 {
+                Self::replace_item_values(&mut tx, item).await?;
+                written += affected;
+            }
+        }
+
+        tx.commit().await?;
+
+        Ok(written)
+    }
+
+    async fn select_item(&self, url: &Url) -> Result<Option<RentItem>, WorkspaceError> {
+        let item = sqlx::query_as("SELECT url, title, labels, patterns, content, phone, album, area, floor, price, address FROM rent_item_view WHERE url = $1")
+            .bind(Json(url))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(item)
+    }
+
+    async fn select_item_urls_with(
+        &self,
+        list_url: &Url,
+        refresh: bool,
+        limit: Option<u32>,
+    ) -> Result<Vec<Json<Url>>, WorkspaceError> {
+        let mut builder = sqlx::QueryBuilder::<Postgres>::new(
+            "WITH LatestList AS (SELECT id FROM rent_list WHERE url = ",
+        );
+        builder.push_bind(Json(list_url));
+        builder.push(" ORDER BY created_at DESC LIMIT 1) SELECT DISTINCT ris.url FROM rent_item_summary ris JOIN LatestList ll ON ris.list_id = ll.id");
+
+        if !refresh {
+            builder.push(" WHERE NOT EXISTS (SELECT 1 FROM rent_item ri WHERE ri.url = ris.url)");
+        }
+
+        builder.push(" ORDER BY ris.url");
+
+        if let Some(limit) = limit {
+            builder.push(" LIMIT ");
+            builder.push_bind(i64::from(limit));
+        }
+
+        let urls = builder.build_query_scalar().fetch_all(&self.pool).await?;
+
+        Ok(urls)
+    }
+
+    async fn select_items(&self, list_url: Option<&Url>) -> Result<Vec<RentItem>, WorkspaceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "
+SELECT
+    ri.url, ri.title, ri.labels, ri.patterns, ri.content,
+    ri.phone, ri.album, ri.area, ri.floor, ri.price, ri.address
+FROM rent_item_view ri",
+        );
+        push_list_items(&mut builder, "ri.url", list_url);
+
+        let items = builder.build_query_as().fetch_all(&self.pool).await?;
+
+        Ok(items)
+    }
+
+    async fn select_stamped_items(
+        &self,
+        list_url: &Url,
+    ) -> Result<Vec<(RentItem, String)>, WorkspaceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "
+SELECT
+    ri.url, ri.created_at, ri.title, ri.labels, ri.patterns, ri.content,
+    ri.phone, ri.album, ri.area, ri.floor, ri.price, ri.address
+FROM rent_item_view ri",
+        );
+        push_list_items(&mut builder, "ri.url", Some(list_url));
+        builder.push(" ORDER BY ri.url");
+
+        let rows: Vec<StampedItemRow> = builder.build_query_as().fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.item, row.created_at))
+            .collect())
+    }
+
+    async fn get_cached_page(&self, url: &Url) -> Result<Option<Page>, WorkspaceError> {
+        let page = sqlx::query_as("SELECT url, html FROM page_cache WHERE url = $1")
+            .bind(Json(url))
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(page)
+    }
+
+    async fn select_cached_pages(&self) -> Result<Vec<PageSummary>, WorkspaceError> {
+        let pages = sqlx::query_as(
+            "SELECT url, created_at, octet_length(html)::BIGINT AS size FROM page_cache ORDER BY created_at DESC, url",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(pages)
+    }
+
+    async fn cache_page(&self, page: &Page) -> Result<(), WorkspaceError> {
+        sqlx::query(
+            "INSERT INTO page_cache (url, html) VALUES ($1, $2) \
+ON CONFLICT (url) DO UPDATE SET created_at = excluded.created_at, html = excluded.html",
+        )
+        .bind(&page.url)
+        .bind(&page.html)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn select_cookies(&self) -> Result<Vec<Cookie>, WorkspaceError> {
+        let cookies = sqlx::query_as(
+            "SELECT name, value, domain, path, expires, http_only, secure, same_site FROM browser_cookie WHERE expires > extract(epoch FROM now())",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(cookies)
+    }
+
+    async fn save_cookies(&self, cookies: &[Cookie]) -> Result<(), WorkspaceError> {
+        let mut tx = self.pool.begin().await?;
+
+        for cookie in cookies {
+            sqlx::query(
+                "INSERT INTO browser_cookie (name, value, domain, path, expires, http_only, secure, same_site) VALUES ($1, $2, $3, $4, $5, $6, $7, $8) \
+ON CONFLICT (name, domain, path) DO UPDATE SET created_at = excluded.created_at, value = excluded.value, expires = excluded.expires, \
+http_only = excluded.http_only, secure = excluded.secure, same_site = excluded.same_site",
+            )
+            .bind(&cookie.name)
+            .bind(&cookie.value)
+            .bind(&cookie.domain)
+            .bind(&cookie.path)
+            .bind(cookie.expires)
+            .bind(cookie.http_only)
+            .bind(cookie.secure)
+            .bind(&cookie.same_site)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn select_item_address(&self, url: &Url) -> Result<Option<ItemAddress>, WorkspaceError> {
+        let row = sqlx::query_as(
+            "SELECT item_url, address, normalized, source FROM rent_item_address WHERE item_url = $1",
+        )
+        .bind(Json(url))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.and_then(item_address))
+    }
+
+    async fn save_item_address(&self, address: &ItemAddress) -> Result<(), WorkspaceError> {
+        sqlx::query(
+            "INSERT INTO rent_item_address (item_url, address, normalized, source) VALUES ($1, $2, $3, $4) \
+ON CONFLICT (item_url) DO UPDATE SET created_at = excluded.created_at, address = excluded.address, \
+normalized = excluded.normalized, source = excluded.source",
+        )
+        .bind(Json(&address.url))
+        .bind(&address.address)
+        .bind(&address.normalized)
+        .bind(address.source.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn select_geocode(&self, address: &str) -> Result<Option<Geocode>, WorkspaceError> {
+        let geocode = sqlx::query_as(
+            "SELECT address, lat, lng, formatted_address, location_type, place_id, provider FROM geocode WHERE address = $1",
+        )
+        .bind(address)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(geocode)
+    }
+
+    async fn save_geocode(&self, geocode: &Geocode) -> Result<(), WorkspaceError> {
+        sqlx::query(
+            "INSERT INTO geocode (address, lat, lng, formatted_address, location_type, place_id, provider) VALUES ($1, $2, $3, $4, $5, $6, $7) \
+ON CONFLICT (address) DO UPDATE SET created_at = excluded.created_at, lat = excluded.lat, lng = excluded.lng, \
+formatted_address = excluded.formatted_address, location_type = excluded.location_type, place_id = excluded.place_id, provider = excluded.provider",
+        )
+        .bind(&geocode.address)
+        .bind(geocode.lat)
+        .bind(geocode.lng)
+        .bind(&geocode.formatted_address)
+        .bind(&geocode.location_type)
+        .bind(&geocode.place_id)
+        .bind(&geocode.provider)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn select_item_distances(
+        &self,
+        list_url: Option<&Url>,
+    ) -> Result<HashMap<Url, Distances>, WorkspaceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "
+SELECT rid.item_url, rid.poi, rid.distance_km
+FROM rent_item_distance rid",
+        );
+        push_list_items(&mut builder, "rid.item_url", list_url);
+
+        let rows = builder.build_query_as().fetch_all(&self.pool).await?;
+
+        Ok(item_distances(rows))
+    }
+
+    async fn save_item_distances(
+        &self,
+        url: &Url,
+        distances: &Distances,
+    ) -> Result<(), WorkspaceError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM rent_item_distance WHERE item_url = $1")
+            .bind(Json(url))
+            .execute(&mut *tx)
+            .await?;
+
+        for (poi, km) in distances {
+            sqlx::query(
+                "INSERT INTO rent_item_distance (item_url, poi, distance_km) VALUES ($1, $2, $3)",
+            )
+            .bind(Json(url))
+            .bind(poi)
+            .bind(km)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn select_item_stations(
+        &self,
+        list_url: Option<&Url>,
+    ) -> Result<HashMap<Url, Vec<NearbyStation>>, WorkspaceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "
+SELECT rst.item_url, rst.line, rst.code, rst.name, rst.distance_m, rst.walk_m, rst.walk_minutes
+FROM rent_item_station rst",
+        );
+        push_list_items(&mut builder, "rst.item_url", list_url);
+        builder.push("\nORDER BY rst.walk_m, rst.line");
+
+        let rows: Vec<StationRow> = builder.build_query_as().fetch_all(&self.pool).await?;
+
+        Ok(item_stations(rows))
+    }
+
+    async fn save_item_stations(
+        &self,
+        url: &Url,
+        stations: &[NearbyStation],
+    ) -> Result<(), WorkspaceError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM rent_item_station WHERE item_url = $1")
+            .bind(Json(url))
+            .execute(&mut *tx)
+            .await?;
+
+        for station in stations {
+            sqlx::query(
+                "INSERT INTO rent_item_station (item_url, line, code, name, distance_m, walk_m, walk_minutes) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+            )
+            .bind(Json(url))
+            .bind(&station.line)
+            .bind(&station.code)
+            .bind(&station.name)
+            .bind(station.distance_m as i32)
+            .bind(station.walk_m as i32)
+            .bind(station.walk_minutes as i32)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn select_item_locations(
+        &self,
+        list_url: Option<&Url>,
+        bounds: Option<&BoundingBox>,
+    ) -> Result<HashMap<Url, (f64, f64)>, WorkspaceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "
+SELECT ria.item_url, g.lat, g.lng
+FROM rent_item_address ria
+JOIN geocode g ON ria.normalized = g.address",
+        );
+        push_list_items(&mut builder, "ria.item_url", list_url);
+        builder.push("\nWHERE g.lat IS NOT NULL AND g.lng IS NOT NULL");
+
+        // the GiST index narrows down to the box, whose containment test allows a small
+        // epsilon, so the coordinates are checked exactly too
+        if let Some(bounds) = bounds {
+            builder
+                .push(" AND point(g.lng, g.lat) <@ box(point(")
+                .push_bind(bounds.min_lng)
+                .push(", ")
+                .push_bind(bounds.min_lat)
+                .push("), point(")
+                .push_bind(bounds.max_lng)
+                .push(", ")
+                .push_bind(bounds.max_lat)
+                .push("))")
+                .push(" AND g.lat BETWEEN ")
+                .push_bind(bounds.min_lat)
+                .push(" AND ")
+                .push_bind(bounds.max_lat)
+                .push(" AND g.lng BETWEEN ")
+                .push_bind(bounds.min_lng)
+                .push(" AND ")
+                .push_bind(bounds.max_lng);
+        }
+
+        let rows: Vec<(Json<Url>, f64, f64)> =
+            builder.build_query_as().fetch_all(&self.pool).await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(url, lat, lng)| (url.0, (lat, lng)))
+            .collect())
+    }
+
+    async fn select_item_commutes(
+        &self,
+        list_url: Option<&Url>,
+    ) -> Result<HashMap<Url, Commutes>, WorkspaceError> {
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "
+SELECT ric.item_url, ric.poi, ric.minutes
+FROM rent_item_commute ric",
+        );
+        push_list_items(&mut builder, "ric.item_url", list_url);
+
+        let rows = builder.build_query_as().fetch_all(&self.pool).await?;
+
+        Ok(item_commutes(rows))
+    }
+
+    async fn save_item_commutes(
+        &self,
+        url: &Url,
+        commutes: &Commutes,
+    ) -> Result<(), WorkspaceError> {
+        let mut tx = self.pool.begin().await?;
+
+        for (poi, &minutes) in commutes {
+            sqlx::query(
+                "INSERT INTO rent_item_commute (item_url, poi, minutes) VALUES ($1, $2, $3) \
+ON CONFLICT (item_url, poi) DO UPDATE SET created_at = excluded.created_at, minutes = excluded.minutes",
+            )
+            .bind(Json(url))
+            .bind(poi)
+            .bind(minutes as i32)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    async fn search_items(
+        &self,
+        query: &SearchQuery,
+        limit: u32,
+    ) -> Result<Vec<SearchHit>, WorkspaceError> {
+        // show the content around the first term, indexed ones first as they rank the hits
+        let first = query
+            .indexed
+            .iter()
+            .chain(&query.unindexed)
+            .next()
+            .cloned()
+            .unwrap_or_default();
+
+        let mut builder = QueryBuilder::<Postgres>::new(
+            "SELECT ri.url, ri.title, '…' || substr(COALESCE(ri.content, ''), greatest(strpos(lower(COALESCE(ri.content, '')), lower(",
+        );
+        builder.push_bind(first);
+        builder.push(")) - 24, 1), 64) || '…' AS snippet, -(0");
+
+        // weighted like the title, labels and content columns of the SQLite BM25 rank
+        for pattern in query.indexed_patterns() {
+            builder.push(" + CASE WHEN ri.title ILIKE ");
+            builder.push_bind(pattern.clone());
+            builder.push(" THEN 10 ELSE 0 END + CASE WHEN EXISTS (SELECT 1 FROM rent_item_label ril WHERE ril.item_url = ri.url AND ril.label ILIKE ");
+            builder.push_bind(pattern.clone());
+            builder.push(") THEN 5 ELSE 0 END + CASE WHEN ri.content ILIKE ");
+            builder.push_bind(pattern);
+            builder.push(" THEN 1 ELSE 0 END");
+        }
+
+        builder.push(")::DOUBLE PRECISION AS rank FROM rent_item ri WHERE true");
+
+        for pattern in query.indexed_patterns().chain(query.like_patterns()) {
+            builder.push(" AND (ri.title ILIKE ");
+            builder.push_bind(pattern.clone());
+            builder.push(" OR ri.content ILIKE ");
+            builder.push_bind(pattern.clone());
+            builder.push(" OR EXISTS (SELECT 1 FROM rent_item_label ril WHERE ril.item_url = ri.url AND ril.label ILIKE ");
+            builder.push_bind(pattern);
+            builder.push("))");
+        }
+
+        builder.push(" ORDER BY rank, ri.created_at DESC LIMIT ");
+        builder.push_bind(i64::from(limit));
+
+        let mut hits: Vec<SearchHit> = builder.build_query_as().fetch_all(&self.pool).await?;
+
+        for hit in &mut hits {
+            hit.highlight(&query.indexed);
+        }
+
+        Ok(hits)
+    }
+
+    async fn stats(&self) -> Result<WorkspaceStats, WorkspaceError> {
+        let count = |table: &str| format!("SELECT COUNT(*) FROM {table}");
+
+        let list_snapshots = sqlx::query_scalar(&count("rent_list"))
+            .fetch_one(&self.pool)
+            .await?;
+        let item_summaries = sqlx::query_scalar(&count("rent_item_summary"))
+            .fetch_one(&self.pool)
+            .await?;
+        let items = sqlx::query_scalar(&count("rent_item"))
+            .fetch_one(&self.pool)
+            .await?;
+        let cookies = sqlx::query_scalar(&count("browser_cookie"))
+            .fetch_one(&self.pool)
+            .await?;
+
+        let (cached_pages, cache_size): (i64, i64) = sqlx::query_as(
+            "SELECT COUNT(*), COALESCE(SUM(octet_length(html)), 0)::BIGINT FROM page_cache",
+        )
+        .fetch_one(&self.pool)
+        .await?;
+
+        let database_size = sqlx::query_scalar("SELECT pg_database_size(current_database())")
+            .fetch_one(&self.pool)
+            .await?;
+
+        let lists = sqlx::query_as(
+            "SELECT url, COUNT(*) AS count, MIN(created_at) AS oldest, MAX(created_at) AS newest FROM rent_list GROUP BY url ORDER BY url",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(WorkspaceStats {
+            list_snapshots,
+            item_summaries,
+            items,
+            cached_pages,
+            cache_size,
+            cookies,
+            database_size,
+            lists,
+        })
+    }
+
+    async fn prune(
+        &self,
+        policy: PrunePolicy,
+        dry_run: bool,
+    ) -> Result<PruneReport, WorkspaceError> {
+        let mut tx = self.pool.begin().await?;
+        let mut report = PruneReport::default();
+
+        if let Some(keep) = policy.keep_snapshots {
+            let summaries_before: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM rent_item_summary")
+                    .fetch_one(&mut *tx)
+                    .await?;
+
+            report.list_snapshots = sqlx::query(
+                "
+DELETE FROM rent_list WHERE id IN (
+    SELECT id FROM (
+        SELECT id, ROW_NUMBER() OVER (PARTITION BY url ORDER BY created_at DESC) AS rank
+        FROM rent_list
+    ) ranked WHERE rank > $1
+)",
+            )
+            .bind(i64::from(keep))
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+
+            // summaries are removed with their list by `ON DELETE CASCADE`
+            let summaries_after: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rent_item_summary")
+                .fetch_one(&mut *tx)
+                .await?;
+            report.item_summaries = (summaries_before - summaries_after) as u64;
+        }
+
+        if let Some(days) = policy.max_cache_days {
+            report.cached_pages = sqlx::query(
+                "DELETE FROM page_cache WHERE created_at < to_char(now() AT TIME ZONE 'UTC' - make_interval(days => $1), 'YYYY-MM-DD HH24:MI:SS')",
+            )
+            .bind(days as i32)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        if policy.orphan_items {
+            report.items = sqlx::query(
+                "DELETE FROM rent_item WHERE NOT EXISTS (SELECT 1 FROM rent_item_summary ris WHERE ris.url = rent_item.url)",
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        if dry_run {
+            tx.rollback().await?;
+            return Ok(report);
+        }
+
+        tx.commit().await?;
+
+        // VACUUM cannot run in the implicit transaction of a prepared statement
+        sqlx::raw_sql("VACUUM").execute(&self.pool).await?;
+
+        Ok(report)
+    }
+}